@@ -0,0 +1,64 @@
+// Built package archives kept after installing
+use std::path::{Path, PathBuf};
+
+/// Directory used as makepkg's `PKGDEST`.
+///
+/// Every archive reap builds lands here and is kept after `pacman -U`, so it can be
+/// reinstalled for a rollback or published to a local repository.
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    root: PathBuf,
+}
+
+impl ArtifactStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// `~/.cache/reap/packages`
+    pub fn default_root() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("reap/packages")
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Create the directory so makepkg can write to it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created.
+    pub fn ensure(&self) -> std::io::Result<&Path> {
+        std::fs::create_dir_all(&self.root)?;
+        Ok(&self.root)
+    }
+
+    /// Kept package archives, sorted by file name (signatures are skipped)
+    pub fn list(&self) -> Vec<PathBuf> {
+        let mut packages: Vec<PathBuf> = std::fs::read_dir(&self.root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_package_archive(path))
+            .collect();
+        packages.sort();
+        packages
+    }
+}
+
+impl Default for ArtifactStore {
+    fn default() -> Self {
+        Self::new(Self::default_root())
+    }
+}
+
+/// Whether `path` is a package archive (`*.pkg.tar.*`) rather than a signature
+pub fn is_package_archive(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| name.contains(".pkg.tar") && !name.ends_with(".sig"))
+}
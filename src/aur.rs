@@ -1,17 +1,26 @@
+use crate::artifacts::ArtifactStore;
+use crate::aur_rpc::{self, AurPackage, SearchBy};
+use crate::chroot::Chroot;
+use crate::core::{OutcomeStatus, ReapError};
+use crate::journal::{Journal, JournalStep, StepStatus, Transaction};
+use crate::pacman_db::{InstallReason, PacmanDb};
+use crate::scheduler::{BuildExecutor, BuildReport, Scheduler};
+use crate::solver::{self, BuildOrigin, PlannedBuild};
+use crate::srcinfo::SrcInfo;
 use crate::utils;
+use crate::version::Version;
 use anyhow::Result;
 use futures::future::join_all;
 use owo_colors::OwoColorize;
-use reqwest::Client; // Use async client for parallel downloads
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::path::PathBuf;
 use std::process::Command;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use tokio::time::timeout;
 
-// Keep blocking client for synchronous functions
-use reqwest::blocking::Client as BlockingClient;
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub name: String,
@@ -20,23 +29,15 @@ pub struct SearchResult {
     pub source: crate::core::Source,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-pub struct AurResult {
-    #[serde(rename = "Name")]
-    pub name: String,
-    #[serde(rename = "Version")]
-    pub version: String,
-    #[serde(rename = "Description")]
-    pub description: Option<String>,
-}
-
-#[derive(Clone, Debug, Deserialize)]
-pub struct AurResponse {
-    pub results: Vec<AurResult>,
-}
-
-pub struct AurInfo {
-    pub version: String,
+impl From<AurPackage> for SearchResult {
+    fn from(pkg: AurPackage) -> Self {
+        SearchResult {
+            name: pkg.name,
+            version: pkg.version,
+            description: pkg.description.unwrap_or_default(),
+            source: crate::core::Source::Aur,
+        }
+    }
 }
 
 /// Fetch package info from AUR
@@ -44,13 +45,11 @@ pub struct AurInfo {
 /// # Errors
 ///
 /// Returns an error if the request to the AUR fails or if the package is not found.
-pub fn fetch_package_info(pkg: &str) -> Result<AurInfo, Box<dyn Error + Send + Sync>> {
-    let url = format!("https://aur.archlinux.org/rpc/?v=5&type=info&arg[]={}", pkg);
-    let client = BlockingClient::new();
-    let resp = client.get(&url).send()?;
-    let aur_resp: AurResponse = resp.json()?;
-    if let Some(r) = aur_resp.results.into_iter().next() {
-        Ok(AurInfo { version: r.version })
+#[allow(dead_code)]
+pub fn fetch_package_info(pkg: &str) -> Result<AurPackage, Box<dyn Error + Send + Sync>> {
+    let results = aur_rpc::client().info_blocking(&[pkg])?;
+    if let Some(r) = results.into_iter().next() {
+        Ok(r)
     } else {
         Err("Package not found".into())
     }
@@ -66,22 +65,11 @@ pub async fn search(query: &str) -> Result<Vec<SearchResult>, Box<dyn Error + Se
     if let Some(cached) = crate::utils::get_cached_search(query) {
         return Ok(cached);
     }
-    let url = format!(
-        "https://aur.archlinux.org/rpc/?v=5&type=search&arg={}",
-        query
-    );
-    let client = reqwest::Client::new();
-    let resp = client.get(&url).send().await?;
-    let aur_resp: AurResponse = resp.json().await?;
-    let results: Vec<SearchResult> = aur_resp
-        .results
+    let results: Vec<SearchResult> = aur_rpc::client()
+        .search(SearchBy::NameDesc, query)
+        .await?
         .into_iter()
-        .map(|r| SearchResult {
-            name: r.name,
-            version: r.version,
-            description: r.description.unwrap_or_default(),
-            source: crate::core::Source::Aur,
-        })
+        .map(SearchResult::from)
         .collect();
     #[cfg(feature = "cache")]
     crate::utils::cache_search_result(query, &results);
@@ -89,158 +77,371 @@ pub async fn search(query: &str) -> Result<Vec<SearchResult>, Box<dyn Error + Se
 }
 
 /// Get AUR search results (blocking)
-pub fn aur_search_results(query: &str) -> Vec<AurResult> {
-    let url = format!(
-        "https://aur.archlinux.org/rpc/?v=5&type=search&arg={}",
-        query
-    );
-    if let Ok(resp) = reqwest::blocking::get(&url) {
-        if let Ok(json) = resp.json::<AurResponse>() {
-            return json.results;
-        }
-    }
-    vec![]
+#[allow(dead_code)]
+pub fn aur_search_results(query: &str) -> Vec<AurPackage> {
+    aur_rpc::client()
+        .search_blocking(SearchBy::NameDesc, query)
+        .unwrap_or_default()
 }
 
 #[cfg(feature = "cache")]
+#[allow(dead_code)]
 pub async fn get_pkgbuild_cached(pkg: &str) -> String {
     crate::utils::async_get_pkgbuild_cached(pkg).await
 }
 
 #[cfg(not(feature = "cache"))]
+#[allow(dead_code)]
 pub async fn get_pkgbuild_cached(pkg: &str) -> String {
-    let url = format!(
-        "https://aur.archlinux.org/cgit/aur.git/plain/PKGBUILD?h={}",
-        pkg
-    );
-    match reqwest::get(&url).await {
-        Ok(resp) => resp.text().await.unwrap_or_default(),
-        Err(_) => String::from("[reap] PKGBUILD not found."),
-    }
+    aur_rpc::client()
+        .fetch_file(pkg, "PKGBUILD")
+        .await
+        .unwrap_or_else(|_| String::from("[reap] PKGBUILD not found."))
 }
 
-/// Install packages using yay or pacman
+/// Install packages with all of their dependencies.
+///
+/// The targets are solved into a build plan: repo dependencies are installed in a single
+/// pacman transaction, then AUR and tap packages are built by the [`Scheduler`], in
+/// parallel where the dependency graph allows.
 ///
 /// # Errors
 ///
-/// Returns an error if the installation fails.
-// Refactor async/parallel flows to use owned values or Arc<T> in tokio::spawn
-// For install(), clone bin and pkg for each task, no references moved into async
-// Add explicit return types for async blocks using ?
-pub async fn install(pkgs: Vec<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let yay = which::which("yay").is_ok();
-    let bin = if yay { "yay" } else { "pacman" };
-    println!("[reap] Installing packages: {:?} ({} -S)...", pkgs, bin);
-    let mut tasks: Vec<tokio::task::JoinHandle<Result<(String, bool), anyhow::Error>>> = Vec::new();
-    for &package in &pkgs {
-        let bin = bin.to_string();
-        let pkg = package.to_string();
-        tasks.push(tokio::spawn(async move {
-            let pkgb = get_pkgbuild_cached(&pkg).await;
-            let deps = get_deps(&pkgb);
-            if !deps.is_empty() {
-                eprintln!("[reap] Dependencies for {}: {:?}", pkg.yellow(), deps);
-                for dep in &deps {
-                    if !crate::pacman::is_installed(dep) {
-                        println!("[reap] Installing missing dependency: {}", dep.yellow());
-                        let status = std::process::Command::new(&bin)
-                            .arg("-S")
-                            .arg(dep)
-                            .status()?;
-                        if status.success() {
-                            println!("[reap] Installed dependency: {}", dep.green());
-                        } else {
-                            eprintln!("[reap] Failed to install dependency: {}", dep.red());
-                        }
-                    } else {
-                        println!("[reap] Dependency already installed: {}", dep.green());
-                    }
-                }
-            } else {
-                println!("[reap] No dependencies found for {}.", pkg);
-            }
-            let status = std::process::Command::new(&bin)
-                .arg("-S")
-                .arg(&pkg)
-                .status()?;
-            Ok((pkg, status.success()))
-        }));
-    }
-    let results = join_all(tasks).await;
-    for res in results {
-        match res {
-            Ok(Ok((pkg, true))) => println!("[reap] Installed {}.", pkg.green()),
-            Ok(Ok((pkg, false))) => eprintln!("[reap] Install failed for {}.", pkg.red()),
-            Ok(Err(e)) => eprintln!("[reap] Task error: {}", e),
-            Err(e) => eprintln!("[reap] Task join error: {}", e),
-        }
+/// Returns an error if the dependencies cannot be solved or a build fails.
+pub async fn install(
+    pkgs: Vec<&str>,
+    opts: &crate::core::InstallOptions,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let reports = install_planned(pkgs, opts).await?;
+    let failed: Vec<&str> = reports
+        .iter()
+        .filter(|r| r.status != OutcomeStatus::Installed)
+        .map(|r| r.pkgbase.as_str())
+        .collect();
+    if !failed.is_empty() {
+        return Err(format!("not installed: {}", failed.join(" ")).into());
     }
     Ok(())
 }
 
-/// Uninstall a package
+/// Solve, build and install packages like [`install`], returning a report for each build.
 ///
 /// # Errors
 ///
-/// Returns an error if the uninstallation fails.
-pub fn uninstall(package: &str) {
-    let yay = which::which("yay").is_ok();
-    let bin = if yay { "yay" } else { "pacman" };
-    println!("[reap] Uninstalling {} ({} -R)...", package.yellow(), bin);
-    let status = Command::new(bin).arg("-R").arg(package).status();
-    match status {
-        Ok(s) if s.success() => println!("[reap] Uninstalled {}.", package.green()),
-        Ok(_) => eprintln!("[reap] Uninstall failed for {}.", package.red()),
-        Err(e) => eprintln!("[reap] Failed to run -R <pkg>: {}", e),
+/// Returns an error if the dependencies cannot be solved, a review is declined or the repo
+/// packages cannot be installed. Failed builds are only reported.
+pub async fn install_planned(
+    pkgs: Vec<&str>,
+    opts: &crate::core::InstallOptions,
+) -> Result<Vec<BuildReport>, Box<dyn std::error::Error + Send + Sync>> {
+    println!("[reap] Resolving dependencies for {:?}...", pkgs);
+    let targets: Vec<String> = pkgs.iter().map(|p| p.to_string()).collect();
+    // With --noconfirm an ambiguous provider is picked without prompting
+    let noconfirm = opts.noconfirm;
+    let plan = tokio::task::spawn_blocking(move || {
+        let policy = solver::ProviderPolicy::active(noconfirm);
+        solver::resolve(&solver::SystemLookup::new(), &policy, &targets)
+    })
+    .await??;
+    if plan.is_empty() {
+        println!("[reap] Nothing to do.");
+        return Ok(Vec::new());
+    }
+    plan.print();
+    if opts.dry_run {
+        println!("[reap] Dry run: nothing was built or installed.");
+        return Ok(Vec::new());
     }
-}
 
-/// Get PKGBUILD preview
-pub fn get_pkgbuild_preview(pkg: &str) -> String {
-    let url = format!(
-        "https://aur.archlinux.org/cgit/aur.git/plain/PKGBUILD?h={}",
-        pkg
+    let mut steps = Vec::new();
+    if !plan.repo.is_empty() {
+        steps.push(JournalStep::new("repo", plan.repo.clone()));
+    }
+    steps.extend(
+        plan.builds
+            .iter()
+            .map(|b| JournalStep::new(&b.pkgbase, b.pkgnames.clone())),
     );
-    if let Ok(resp) = reqwest::blocking::get(&url) {
-        if let Ok(text) = resp.text() {
-            return text;
+    let (journal, tx) = crate::core::begin_transaction("install", plan.targets.clone(), steps)?;
+    let result = execute_plan(plan, opts, &journal, tx).await;
+    // Failed steps leave the transaction for `reap recover`
+    crate::core::finish_transaction(&journal);
+    result
+}
+
+/// Install the repo packages of a journaled plan, then build and install the rest
+async fn execute_plan(
+    plan: solver::BuildPlan,
+    opts: &crate::core::InstallOptions,
+    journal: &Journal,
+    mut tx: Transaction,
+) -> Result<Vec<BuildReport>, Box<dyn std::error::Error + Send + Sync>> {
+    // Only packages this transaction newly pulls in become dependencies; one already
+    // installed keeps its install reason even when it is rebuilt to meet a constraint
+    let installed: HashSet<String> = PacmanDb::system()
+        .local()
+        .map(|db| db.iter().map(|p| p.name.clone()).collect())
+        .unwrap_or_default();
+    let pulled_in = |name: &String| !plan.is_target(name) && !installed.contains(name);
+    let (deps, explicit): (Vec<&String>, Vec<&String>) =
+        plan.repo.iter().partition(|name| pulled_in(name));
+    for (names, asdeps) in [(deps, true), (explicit, false)] {
+        if names.is_empty() {
+            continue;
+        }
+        println!("[reap] Installing repo packages: {:?}", names);
+        let mut args = vec!["-S", "--needed"];
+        if asdeps {
+            args.push("--asdeps");
+        }
+        args.extend(names.iter().map(|name| name.as_str()));
+        crate::pacman::run(args)?;
+    }
+    crate::core::mark_step(journal, &mut tx, "repo", StepStatus::Done);
+
+    let log = crate::tui::LogPane::default();
+    // Reviews and edits may prompt, so every AUR clone is prepared before building starts
+    let mut build_dirs = HashMap::new();
+    for build in plan.builds.iter().filter(|b| b.origin == BuildOrigin::Aur) {
+        let dir = crate::core::prepare_aur_pkgbase(&build.pkgbase, &log, opts)?;
+        build_dirs.insert(build.pkgbase.clone(), dir);
+    }
+    let chroot = crate::core::chroot_enabled(opts);
+    crate::core::ensure_chroot(opts)?;
+    // Host builds need their dependencies installed; chroot builds get them injected
+    let scheduler = Scheduler::new(crate::core::build_jobs(opts), !chroot);
+    let executor = PlanExecutor {
+        opts: opts.clone(),
+        log,
+        chroot,
+        build_dirs,
+        dependencies: plan
+            .builds
+            .iter()
+            .flat_map(|b| b.pkgnames.iter())
+            .filter(|name| pulled_in(name))
+            .cloned()
+            .collect(),
+        journal: journal.clone(),
+        tx: Mutex::new(tx),
+        built: Mutex::new(HashMap::new()),
+    };
+    let (plan, reports) = tokio::task::spawn_blocking(move || {
+        let reports = scheduler.run(&plan, &executor);
+        (plan, reports)
+    })
+    .await?;
+    for report in &reports {
+        match &report.status {
+            OutcomeStatus::Installed => {
+                println!("[reap] Installed {}.", report.pkgnames.join(" ").green())
+            }
+            OutcomeStatus::Failed(e) => eprintln!("[reap] {} failed: {}", report.pkgbase.red(), e),
+            OutcomeStatus::Skipped(reason) => {
+                eprintln!("[reap] Skipped {}: {}", report.pkgbase.yellow(), reason)
+            }
         }
     }
-    String::from("[reap] PKGBUILD not found.")
+
+    let make_only: Vec<String> = plan
+        .make_only
+        .iter()
+        .filter(|name| !installed.contains(*name))
+        .cloned()
+        .collect();
+    let record = crate::make_deps::MakeDeps::default();
+    if let Err(e) = record.record(&plan.targets.join(" "), &make_only) {
+        eprintln!("[reap] Could not record make-only dependencies: {}", e);
+    }
+    if reports.iter().all(|r| r.status == OutcomeStatus::Installed) {
+        crate::core::remove_make_deps(opts);
+    }
+    Ok(reports)
 }
 
-/// Extract dependencies from PKGBUILD
-pub fn get_deps(pkgb: &str) -> Vec<String> {
-    let mut deps = Vec::new();
-    let mut in_dep = false;
-    let mut dep_buf = String::new();
-    for line in pkgb.lines() {
-        let trimmed = line.trim_start();
-        if trimmed.starts_with("depends=") {
-            in_dep = true;
-            dep_buf.push_str(trimmed.split_once('=').map(|x| x.1).unwrap_or("").trim());
-            if trimmed.ends_with(')') {
-                in_dep = false;
-            }
-        } else if in_dep {
-            dep_buf.push_str(trimmed);
-            if trimmed.ends_with(')') {
-                in_dep = false;
+/// Builds plan entries for the [`Scheduler`]: AUR bases from their prepared clones and tap
+/// packages from the tap checkout
+struct PlanExecutor {
+    opts: crate::core::InstallOptions,
+    log: crate::tui::LogPane,
+    chroot: bool,
+    build_dirs: HashMap<String, PathBuf>,
+    /// Built packages that are not targets, installed as dependencies
+    dependencies: HashSet<String>,
+    journal: Journal,
+    tx: Mutex<Transaction>,
+    /// pkgbase of each built archive, to journal the installs
+    built: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl PlanExecutor {
+    fn mark(&self, pkgbase: &str, status: StepStatus) {
+        let mut tx = self.tx.lock().unwrap_or_else(|e| e.into_inner());
+        crate::core::mark_step(&self.journal, &mut tx, pkgbase, status);
+    }
+
+    fn build_tap(
+        &self,
+        build: &PlannedBuild,
+        tap_name: &str,
+        deps: &[PathBuf],
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let store = ArtifactStore::default();
+        let tap = crate::tap::discover_taps()
+            .into_iter()
+            .find(|t| t.name == tap_name)
+            .ok_or_else(|| format!("tap '{}' is no longer configured", tap_name))?;
+        let dir = crate::tap::ensure_tap_cloned(&tap).join(&build.pkgbase);
+        println!(
+            "[reap] Building {} from tap {}",
+            build.pkgbase.yellow(),
+            tap_name
+        );
+        if self.chroot {
+            let chroot = Chroot::default();
+            let deps = crate::core::chroot_deps(&dir, deps);
+            chroot.build(&dir, store.ensure()?, &deps)?;
+        } else {
+            // The plan installed the dependencies, and parallel builds must not prompt
+            crate::core::run_makepkg(&build.pkgbase, &dir, store.ensure()?, &self.log)?;
+        }
+        crate::core::publish_built(&crate::core::package_list(&dir, store.root())?, &self.log);
+        let packages = crate::core::built_packages(&dir, store.root(), &build.pkgnames)?;
+        println!("[reap] Built {}.", build.pkgnames.join(" ").green());
+        crate::devel::record_build(
+            &build.pkgbase,
+            &build.pkgnames,
+            build.origin.clone(),
+            &build.srcinfo,
+        );
+        Ok(packages)
+    }
+}
+
+impl BuildExecutor for PlanExecutor {
+    fn build(&self, build: &PlannedBuild, deps: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+        let result = match &build.origin {
+            BuildOrigin::Aur => crate::core::build_prepared(
+                &build.pkgbase,
+                &self.build_dirs[&build.pkgbase],
+                &build.pkgnames,
+                deps,
+                &self.log,
+                &self.opts,
+            )
+            .map_err(|e| e.to_string()),
+            BuildOrigin::Tap(tap_name) => self
+                .build_tap(build, tap_name, deps)
+                .map_err(|e| e.to_string()),
+        };
+        match &result {
+            Ok(archives) => {
+                let mut built = self.built.lock().unwrap_or_else(|e| e.into_inner());
+                for archive in archives {
+                    built.insert(archive.clone(), build.pkgbase.clone());
+                }
             }
+            Err(e) => self.mark(&build.pkgbase, StepStatus::Failed(e.clone())),
         }
-        if !in_dep && !dep_buf.is_empty() {
-            let dep_line = dep_buf.trim_matches(&['(', ')', '"', '\'', ' '] as &[_]);
-            deps.extend(
-                dep_line
-                    .split_whitespace()
-                    .map(|s| s.trim_matches(&['"', '\'', ' '] as &[_]))
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.to_string()),
+        result
+    }
+
+    fn install(&self, archives: &[PathBuf]) -> Result<(), String> {
+        let result = crate::core::install_built(archives, &self.log).map_err(|e| e.to_string());
+        let deps: Vec<String> = archives
+            .iter()
+            .filter_map(|a| built_package_name(a))
+            .filter(|name| self.dependencies.contains(name))
+            .collect();
+        let marked = match &result {
+            Ok(()) => crate::pacman::set_install_reason(&deps, InstallReason::Dependency),
+            Err(_) => Ok(()),
+        };
+        if let Err(e) = marked {
+            eprintln!(
+                "[reap] Could not mark {} as dependencies: {}",
+                deps.join(" "),
+                e
             );
-            dep_buf.clear();
         }
+        let pkgbases: Vec<String> = {
+            let built = self.built.lock().unwrap_or_else(|e| e.into_inner());
+            archives
+                .iter()
+                .filter_map(|a| built.get(a).cloned())
+                .collect()
+        };
+        for pkgbase in pkgbases {
+            let status = match &result {
+                Ok(()) => StepStatus::Done,
+                Err(e) => StepStatus::Failed(e.clone()),
+            };
+            self.mark(&pkgbase, status);
+        }
+        result
+    }
+}
+
+/// Uninstall packages in one transaction, printing any failure.
+///
+/// The caller has already confirmed the removal, so pacman does not prompt again.
+pub fn uninstall(packages: &[String]) {
+    let yay = which::which("yay").is_ok();
+    let bin = if yay { "yay" } else { "pacman" };
+    let list = packages.join(" ");
+    println!("[reap] Uninstalling {} ({} -R)...", list.yellow(), bin);
+    let mut args = vec!["-R", "--noconfirm"];
+    args.extend(packages.iter().map(String::as_str));
+    let result = if yay {
+        // yay runs pacman itself, so it queues for the lock like reap's own calls
+        crate::pacman::lock_transaction().and_then(|_transaction| {
+            let status = Command::new(bin).args(&args).status()?;
+            if !status.success() {
+                return Err(ReapError::CommandFailed(format!(
+                    "yay -R {} exited with {}",
+                    list, status
+                )));
+            }
+            Ok(())
+        })
+    } else {
+        crate::pacman::run(args)
+    };
+    match result {
+        Ok(()) => println!("[reap] Uninstalled {}.", list.green()),
+        Err(e) => eprintln!("[reap] Uninstall failed for {}: {}", list.red(), e),
     }
-    deps
+}
+
+/// Get PKGBUILD preview
+pub fn get_pkgbuild_preview(pkg: &str) -> String {
+    aur_rpc::client()
+        .fetch_file_blocking(pkg, "PKGBUILD")
+        .unwrap_or_else(|_| String::from("[reap] PKGBUILD not found."))
+}
+
+/// Runtime dependencies of `pkgname`, a member of `srcinfo`, for the host architecture
+pub fn get_deps(srcinfo: &SrcInfo, pkgname: &str) -> Vec<String> {
+    srcinfo.depends_of(pkgname)
+}
+
+/// Fetch the .SRCINFO of the package base that builds `pkgname`
+///
+/// # Errors
+///
+/// Returns an error if the AUR cannot be queried or the file cannot be fetched or parsed.
+pub async fn fetch_srcinfo(pkgname: &str) -> Result<SrcInfo, Box<dyn Error + Send + Sync>> {
+    let groups = group_by_pkgbase(&aur_rpc::client(), &[pkgname]).await?;
+    let pkgbase = groups.first().map_or(pkgname, |(base, _)| base.as_str());
+    Ok(crate::srcinfo::fetch(pkgbase).await?)
+}
+
+/// Whether the AUR has a package named exactly `pkg`
+pub async fn aur_has_package(pkg: &str) -> bool {
+    aur_rpc::client()
+        .info(&[pkg])
+        .await
+        .is_ok_and(|pkgs| pkgs.iter().any(|p| p.name == pkg))
 }
 
 /// Upgrade all packages
@@ -249,29 +450,29 @@ pub fn get_deps(pkgb: &str) -> Vec<String> {
 //
 // Returns an error if the upgrade fails.
 pub async fn upgrade_all() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let outdated = get_outdated();
+    let outdated = get_outdated().await;
     if outdated.is_empty() {
         println!("[reap] All packages are up to date.");
         return Ok(());
     }
     println!("[reap] Outdated packages:");
     for pkg in &outdated {
-        println!("  - {}", pkg);
+        println!("  - {} {} -> {}", pkg.name, pkg.local, pkg.remote);
     }
     let mut to_upgrade = Vec::new();
     for pkg in &outdated {
-        if utils::is_pinned(pkg) {
-            println!("[reap] Skipping pinned package: {}", pkg);
+        if utils::is_pinned(&pkg.name) {
+            println!("[reap] Skipping pinned package: {}", pkg.name);
             continue;
         }
-        to_upgrade.push(pkg.as_str());
+        to_upgrade.push(pkg.name.as_str());
     }
     if to_upgrade.is_empty() {
         println!("[reap] No packages to upgrade (all pinned).");
         return Ok(());
     }
     println!("[reap] Upgrading {} packages...", to_upgrade.len());
-    let res = install(to_upgrade).await;
+    let res = install(to_upgrade, &crate::core::InstallOptions::default()).await;
     match res {
         Ok(_) => println!("[reap] Upgrade complete."),
         Err(e) => eprintln!("[reap] Upgrade failed: {}", e),
@@ -299,36 +500,120 @@ pub fn install_local(path: &str) {
         "[reap] Installing local package from {} (sudo pacman -U)...",
         path.yellow()
     );
-    let status = Command::new("sudo")
-        .arg("pacman")
-        .arg("-U")
-        .arg(path)
-        .status()
-        .expect("Failed to run sudo pacman -U <file>");
-    if status.success() {
-        println!("[reap] Local install complete: {}.", path.green());
-    } else {
-        eprintln!("[reap] Local install failed: {}.", path.red());
+    match crate::pacman::run(["-U", path]) {
+        Ok(()) => println!("[reap] Local install complete: {}.", path.green()),
+        Err(e) => eprintln!("[reap] Local install failed: {}: {}", path.red(), e),
     }
 }
 
-// Get a list of outdated packages
-pub fn get_outdated() -> Vec<String> {
-    use crate::aur::fetch_package_info;
-    use crate::pacman;
-    let installed = pacman::list_installed_aur();
-    let mut outdated = Vec::new();
-    for pkg in installed {
-        let local_ver = pacman::get_version(&pkg);
-        if let Ok(remote) = fetch_package_info(&pkg) {
-            if let Some(local_ver) = local_ver {
-                if local_ver != remote.version {
-                    outdated.push(pkg);
-                }
-            }
+/// Maximum number of `arg[]=` parameters sent in a single info request
+pub const INFO_BATCH_SIZE: usize = 100;
+
+/// An installed foreign package with a newer version on the AUR
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateCandidate {
+    pub name: String,
+    pub local: String,
+    pub remote: String,
+    /// Unix timestamp the package was flagged out-of-date on the AUR, if any
+    pub out_of_date: Option<i64>,
+    pub maintainer: Option<String>,
+}
+
+/// Compare installed `(name, version)` pairs against the AUR using chunked
+/// multi-info requests that run concurrently.
+///
+/// Packages that are not found on the AUR are skipped.
+///
+/// # Errors
+///
+/// Returns an error if any of the batched RPC requests fail.
+pub async fn check_updates_with(
+    client: &aur_rpc::AurClient,
+    installed: &[(String, String)],
+) -> Result<Vec<UpdateCandidate>, aur_rpc::RpcError> {
+    let tasks = installed.chunks(INFO_BATCH_SIZE).map(|chunk| {
+        let names: Vec<&str> = chunk.iter().map(|(name, _)| name.as_str()).collect();
+        async move { client.info(&names).await }
+    });
+    let mut remote = std::collections::HashMap::new();
+    for batch in join_all(tasks).await {
+        for pkg in batch? {
+            remote.insert(pkg.name.clone(), pkg);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (name, local) in installed {
+        let Some(pkg) = remote.remove(name) else {
+            continue;
+        };
+        if Version::parse(local) < Version::parse(&pkg.version) {
+            candidates.push(UpdateCandidate {
+                name: name.clone(),
+                local: local.clone(),
+                remote: pkg.version,
+                out_of_date: pkg.out_of_date,
+                maintainer: pkg.maintainer,
+            });
+        }
+    }
+    Ok(candidates)
+}
+
+/// Map package names to their AUR pkgbase, grouping members of the same split package.
+///
+/// Groups keep the order in which their first member was requested. Names the AUR does
+/// not know are kept as their own base.
+///
+/// # Errors
+///
+/// Returns an error if any of the batched RPC requests fail.
+pub async fn group_by_pkgbase<S: AsRef<str>>(
+    client: &aur_rpc::AurClient,
+    names: &[S],
+) -> Result<Vec<(String, Vec<String>)>, aur_rpc::RpcError> {
+    let tasks = names
+        .chunks(INFO_BATCH_SIZE)
+        .map(|chunk| async move { client.info(chunk).await });
+    let mut bases = std::collections::HashMap::new();
+    for batch in join_all(tasks).await {
+        for pkg in batch? {
+            bases.insert(pkg.name, pkg.package_base);
+        }
+    }
+
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for name in names {
+        let name = name.as_ref();
+        let base = bases.get(name).map_or(name, String::as_str);
+        match groups.iter_mut().find(|(b, _)| b == base) {
+            Some((_, members)) => members.push(name.to_string()),
+            None => groups.push((base.to_string(), vec![name.to_string()])),
+        }
+    }
+    Ok(groups)
+}
+
+/// Package name of a built archive such as `foo-1.0-1-x86_64.pkg.tar.zst`
+pub fn built_package_name(file: &std::path::Path) -> Option<String> {
+    let file_name = file.file_name()?.to_str()?;
+    // name-pkgver-pkgrel-arch.pkg.tar.*; the name itself may contain dashes
+    let mut parts = file_name.rsplitn(4, '-');
+    let (_arch, _pkgrel, _pkgver) = (parts.next()?, parts.next()?, parts.next()?);
+    parts.next().map(str::to_string)
+}
+
+/// Get the installed foreign packages that have a newer version on the AUR
+pub async fn get_outdated() -> Vec<UpdateCandidate> {
+    let installed = crate::pacman::list_foreign_versions();
+    match check_updates_with(&aur_rpc::client(), &installed).await {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            eprintln!("[aur] Update check failed: {}", e);
+            Vec::new()
         }
     }
-    outdated
 }
 
 /// Parallel search across multiple queries with smart caching
@@ -341,7 +626,7 @@ pub async fn parallel_search(
         queries.len()
     );
 
-    let client = Client::new();
+    let client = aur_rpc::client();
     let tasks: Vec<_> = queries
         .iter()
         .map(|query| {
@@ -357,23 +642,14 @@ pub async fn parallel_search(
                 }
 
                 // Fetch from AUR with timeout
-                let url = format!(
-                    "https://aur.archlinux.org/rpc/?v=5&type=search&arg={}",
-                    query
-                );
-                let response = timeout(Duration::from_secs(10), client.get(&url).send()).await??;
-                let aur_resp: AurResponse = response.json().await?;
-
-                let results: Vec<SearchResult> = aur_resp
-                    .results
-                    .into_iter()
-                    .map(|r| SearchResult {
-                        name: r.name,
-                        version: r.version,
-                        description: r.description.unwrap_or_default(),
-                        source: crate::core::Source::Aur,
-                    })
-                    .collect();
+                let results: Vec<SearchResult> = timeout(
+                    Duration::from_secs(10),
+                    client.search(SearchBy::NameDesc, &query),
+                )
+                .await??
+                .into_iter()
+                .map(SearchResult::from)
+                .collect();
 
                 // Cache the results
                 #[cfg(feature = "cache")]
@@ -420,7 +696,7 @@ pub async fn parallel_pkgbuild_fetch(
         packages.len()
     );
 
-    let client = Client::new();
+    let client = aur_rpc::client();
     let tasks: Vec<_> = packages
         .iter()
         .map(|pkg| {
@@ -436,29 +712,15 @@ pub async fn parallel_pkgbuild_fetch(
                 }
 
                 // Fetch PKGBUILD from AUR
-                let url = format!(
-                    "https://aur.archlinux.org/cgit/aur.git/plain/PKGBUILD?h={}",
-                    pkg
-                );
-                let response = timeout(Duration::from_secs(15), client.get(&url).send()).await??;
-
-                if response.status().is_success() {
-                    let pkgbuild = response.text().await?;
-
-                    // Cache the PKGBUILD
-                    #[cfg(feature = "cache")]
-                    utils::cache::save_pkgbuild(&pkg, &pkgbuild);
-
-                    println!("[aur] Downloaded PKGBUILD for: {}", pkg);
-                    Ok((pkg, pkgbuild))
-                } else {
-                    Err(format!(
-                        "Failed to fetch PKGBUILD for {}: HTTP {}",
-                        pkg,
-                        response.status()
-                    )
-                    .into())
-                }
+                let pkgbuild =
+                    timeout(Duration::from_secs(15), client.fetch_file(&pkg, "PKGBUILD")).await??;
+
+                // Cache the PKGBUILD
+                #[cfg(feature = "cache")]
+                utils::cache::save_pkgbuild(&pkg, &pkgbuild);
+
+                println!("[aur] Downloaded PKGBUILD for: {}", pkg);
+                Ok((pkg, pkgbuild))
             }
         })
         .collect();
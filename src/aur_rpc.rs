@@ -0,0 +1,292 @@
+use crate::config::GlobalConfig;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Default AUR endpoint used when `aur_url` is not set in reap.toml
+pub const DEFAULT_AUR_URL: &str = "https://aur.archlinux.org";
+
+const RPC_VERSION: u8 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Errors returned by the AUR RPC client
+#[derive(Debug, Error)]
+pub enum RpcError {
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("AUR returned HTTP {status} for {url}")]
+    Status { status: u16, url: String },
+    #[error("AUR RPC error: {0}")]
+    Rpc(String),
+}
+
+/// Field used by an RPC `search` request (`by=` parameter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[allow(dead_code)]
+pub enum SearchBy {
+    Name,
+    #[default]
+    NameDesc,
+    Maintainer,
+    Depends,
+    MakeDepends,
+    OptDepends,
+    CheckDepends,
+    Provides,
+}
+
+impl SearchBy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchBy::Name => "name",
+            SearchBy::NameDesc => "name-desc",
+            SearchBy::Maintainer => "maintainer",
+            SearchBy::Depends => "depends",
+            SearchBy::MakeDepends => "makedepends",
+            SearchBy::OptDepends => "optdepends",
+            SearchBy::CheckDepends => "checkdepends",
+            SearchBy::Provides => "provides",
+        }
+    }
+}
+
+/// A package record as returned by the AUR RPC (`search` omits the dependency arrays)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct AurPackage {
+    #[serde(rename = "ID")]
+    pub id: u64,
+    pub name: String,
+    #[serde(rename = "PackageBaseID")]
+    pub package_base_id: u64,
+    pub package_base: String,
+    pub version: String,
+    pub description: Option<String>,
+    #[serde(rename = "URL")]
+    pub url: Option<String>,
+    pub num_votes: u32,
+    pub popularity: f64,
+    pub out_of_date: Option<i64>,
+    pub maintainer: Option<String>,
+    pub first_submitted: i64,
+    pub last_modified: i64,
+    #[serde(rename = "URLPath")]
+    pub url_path: Option<String>,
+    pub depends: Vec<String>,
+    pub make_depends: Vec<String>,
+    pub opt_depends: Vec<String>,
+    pub check_depends: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub provides: Vec<String>,
+    pub replaces: Vec<String>,
+    pub groups: Vec<String>,
+    pub license: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+/// Raw RPC envelope; `type` is `search`, `multiinfo` or `error`
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    results: Vec<AurPackage>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl RpcResponse {
+    fn into_results(self) -> Result<Vec<AurPackage>, RpcError> {
+        if self.kind == "error" {
+            return Err(RpcError::Rpc(
+                self.error.unwrap_or_else(|| "unknown error".to_string()),
+            ));
+        }
+        Ok(self.results)
+    }
+}
+
+/// Typed client for the AUR RPC v5 interface and the cgit/git endpoints next to it.
+///
+/// The base URL comes from `aur_url` in reap.toml, so reap can be pointed at an
+/// internal AUR mirror (or a local mock server in tests).
+#[derive(Debug, Clone)]
+pub struct AurClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl AurClient {
+    pub fn new(base_url: &str) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from_config(config: &GlobalConfig) -> Self {
+        Self::new(config.aur_url())
+    }
+
+    #[allow(dead_code)]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// URL of an RPC `search` request
+    pub fn search_url(&self, by: SearchBy, query: &str) -> String {
+        format!(
+            "{}/rpc/?v={}&type=search&by={}&arg={}",
+            self.base_url,
+            RPC_VERSION,
+            by.as_str(),
+            urlencoding::encode(query)
+        )
+    }
+
+    /// URL of a multi-`arg[]` RPC `info` request
+    pub fn info_url<S: AsRef<str>>(&self, names: &[S]) -> String {
+        let mut url = format!("{}/rpc/?v={}&type=info", self.base_url, RPC_VERSION);
+        for name in names {
+            url.push_str("&arg[]=");
+            url.push_str(&urlencoding::encode(name.as_ref()));
+        }
+        url
+    }
+
+    /// Git clone URL of a package base
+    pub fn clone_url(&self, pkgbase: &str) -> String {
+        format!("{}/{}.git", self.base_url, pkgbase)
+    }
+
+    /// cgit URL of a single file (PKGBUILD, .SRCINFO, ...) in a package base
+    pub fn file_url(&self, pkgbase: &str, file: &str) -> String {
+        format!(
+            "{}/cgit/aur.git/plain/{}?h={}",
+            self.base_url,
+            file,
+            urlencoding::encode(pkgbase)
+        )
+    }
+
+    /// cgit URL of a package base's PKGBUILD
+    #[allow(dead_code)]
+    pub fn pkgbuild_url(&self, pkgbase: &str) -> String {
+        self.file_url(pkgbase, "PKGBUILD")
+    }
+
+    /// Search the AUR by the given field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the RPC answers with an error response.
+    pub async fn search(&self, by: SearchBy, query: &str) -> Result<Vec<AurPackage>, RpcError> {
+        self.get_rpc(&self.search_url(by, query)).await
+    }
+
+    /// Fetch full info for several packages in one request
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the RPC answers with an error response.
+    pub async fn info<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<AurPackage>, RpcError> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.get_rpc(&self.info_url(names)).await
+    }
+
+    /// Fetch a raw file from a package base's git tree
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the file does not exist.
+    pub async fn fetch_file(&self, pkgbase: &str, file: &str) -> Result<String, RpcError> {
+        let url = self.file_url(pkgbase, file);
+        let resp = self.http.get(&url).send().await?;
+        if !resp.status().is_success() {
+            return Err(RpcError::Status {
+                status: resp.status().as_u16(),
+                url,
+            });
+        }
+        Ok(resp.text().await?)
+    }
+
+    /// Blocking variant of [`AurClient::search`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the RPC answers with an error response.
+    pub fn search_blocking(&self, by: SearchBy, query: &str) -> Result<Vec<AurPackage>, RpcError> {
+        self.get_rpc_blocking(&self.search_url(by, query))
+    }
+
+    /// Blocking variant of [`AurClient::info`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the RPC answers with an error response.
+    #[allow(dead_code)]
+    pub fn info_blocking<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<AurPackage>, RpcError> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.get_rpc_blocking(&self.info_url(names))
+    }
+
+    /// Blocking variant of [`AurClient::fetch_file`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the file does not exist.
+    pub fn fetch_file_blocking(&self, pkgbase: &str, file: &str) -> Result<String, RpcError> {
+        let url = self.file_url(pkgbase, file);
+        let resp = blocking_client().get(&url).send()?;
+        if !resp.status().is_success() {
+            return Err(RpcError::Status {
+                status: resp.status().as_u16(),
+                url,
+            });
+        }
+        Ok(resp.text()?)
+    }
+
+    async fn get_rpc(&self, url: &str) -> Result<Vec<AurPackage>, RpcError> {
+        let resp = self.http.get(url).send().await?;
+        let body: RpcResponse = resp.json().await?;
+        body.into_results()
+    }
+
+    fn get_rpc_blocking(&self, url: &str) -> Result<Vec<AurPackage>, RpcError> {
+        let resp = blocking_client().get(url).send()?;
+        let body: RpcResponse = resp.json()?;
+        body.into_results()
+    }
+}
+
+impl Default for AurClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_AUR_URL)
+    }
+}
+
+fn blocking_client() -> reqwest::blocking::Client {
+    reqwest::blocking::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+static CONFIGURED_URL: Lazy<String> = Lazy::new(|| GlobalConfig::load().aur_url().to_string());
+
+/// Client for the AUR endpoint configured in reap.toml
+pub fn client() -> AurClient {
+    AurClient::new(&CONFIGURED_URL)
+}
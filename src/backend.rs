@@ -1,4 +1,5 @@
 use crate::aur::SearchResult;
+use crate::core::{InstallOutcome, ReapError, Source};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use futures::FutureExt;
@@ -6,6 +7,7 @@ use std::error::Error;
 use std::path::Path;
 use std::process::Command;
 use std::str::FromStr;
+use std::time::Instant;
 
 /// Backend trait for all supported package sources.
 ///
@@ -29,32 +31,48 @@ pub trait Backend: Send + Sync {
     fn is_available(&self) -> bool;
     #[allow(dead_code)]
     async fn search(&self, query: &str) -> Vec<SearchResult>;
-    async fn install(&self, package: &str);
+    /// Install `package`, reporting the installed version, source and time taken
+    async fn install(&self, package: &str) -> Result<InstallOutcome, ReapError>;
     #[allow(dead_code)]
-    async fn upgrade(&self);
-    async fn audit(&self, package: &str);
+    async fn upgrade(&self) -> Result<(), ReapError>;
+    async fn audit(&self, package: &str) -> Result<(), ReapError>;
     #[allow(dead_code)]
-    async fn gpg_check(&self, package: &str);
+    async fn gpg_check(&self, package: &str) -> Result<(), ReapError>;
+}
+
+/// Map the boxed errors of the older helpers onto [`ReapError`]
+fn command_failed(e: Box<dyn Error + Send + Sync>) -> ReapError {
+    ReapError::CommandFailed(e.to_string())
 }
 
 #[allow(dead_code)]
 pub fn build_and_install(pkgdir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let store = crate::artifacts::ArtifactStore::default();
     let status = Command::new("makepkg")
-        .arg("-si")
-        .arg("--noconfirm")
+        .args(["-s", "-f", "--noconfirm"])
+        .env("PKGDEST", store.ensure()?)
         .current_dir(pkgdir)
         .status()
         .context("failed to execute makepkg")?;
     if !status.success() {
         return Err("makepkg failed".into());
     }
+    let packages = crate::core::package_list(pkgdir, store.root())?;
+    crate::core::install_built(&packages, &crate::tui::LogPane::default())?;
     Ok(())
 }
 
-pub struct AurBackend;
+pub struct AurBackend {
+    opts: crate::core::InstallOptions,
+}
 impl AurBackend {
     pub fn new() -> Self {
-        AurBackend
+        Self::with_options(crate::core::InstallOptions::default())
+    }
+
+    /// Backend that builds with the given edit/dry-run/review options
+    pub fn with_options(opts: crate::core::InstallOptions) -> Self {
+        AurBackend { opts }
     }
 }
 impl Default for AurBackend {
@@ -74,26 +92,38 @@ impl Backend for AurBackend {
     async fn search(&self, query: &str) -> Vec<SearchResult> {
         crate::aur::search(query).await.unwrap_or_default()
     }
-    async fn install(&self, package: &str) {
+    async fn install(&self, package: &str) -> Result<InstallOutcome, ReapError> {
+        let start = Instant::now();
         let log = crate::tui::LogPane::default();
         log.push(&format!(
             "[reap][backend] Installing {} using native AUR logic",
             package
         ));
-        // Use Default for InstallOptions
-        let opts = crate::core::InstallOptions::default();
-        let _ = crate::core::install_aur_native(package, &log, &opts)
-            .await
-            .context("AUR native install failed");
+        crate::core::install_aur_native(package, &self.opts).await?;
+        if self.opts.dry_run {
+            return Ok(InstallOutcome::skipped(
+                package,
+                Some(Source::Aur),
+                start.elapsed(),
+                "dry run",
+            ));
+        }
+        Ok(InstallOutcome::installed(
+            package,
+            Source::Aur,
+            start.elapsed(),
+        ))
     }
-    async fn upgrade(&self) {
-        let _ = crate::aur::upgrade_all().await;
+    async fn upgrade(&self) -> Result<(), ReapError> {
+        crate::aur::upgrade_all().await.map_err(command_failed)
     }
-    async fn audit(&self, package: &str) {
-        crate::utils::audit_package(package);
+    async fn audit(&self, package: &str) -> Result<(), ReapError> {
+        crate::utils::audit_package(package).await;
+        Ok(())
     }
-    async fn gpg_check(&self, package: &str) {
+    async fn gpg_check(&self, package: &str) -> Result<(), ReapError> {
         crate::gpg::check_key(package).await;
+        Ok(())
     }
 }
 
@@ -118,16 +148,25 @@ impl Backend for PacmanBackend {
             .filter(|r| r.source == crate::core::Source::Pacman)
             .collect()
     }
-    async fn install(&self, package: &str) {
-        crate::pacman::install(package);
+    async fn install(&self, package: &str) -> Result<InstallOutcome, ReapError> {
+        let start = Instant::now();
+        crate::pacman::install(package)?;
+        Ok(InstallOutcome::installed(
+            package,
+            Source::Pacman,
+            start.elapsed(),
+        ))
     }
-    async fn upgrade(&self) { /* handled in aur::upgrade for now */
+    async fn upgrade(&self) -> Result<(), ReapError> {
+        crate::pacman::run(["-Su"])
     }
-    async fn audit(&self, package: &str) {
+    async fn audit(&self, package: &str) -> Result<(), ReapError> {
         println!("[reap] Pacman audit for {} (not implemented)", package);
+        Ok(())
     }
-    async fn gpg_check(&self, package: &str) {
+    async fn gpg_check(&self, package: &str) -> Result<(), ReapError> {
         println!("[reap] Pacman GPG check for {} (not implemented)", package);
+        Ok(())
     }
 }
 
@@ -155,23 +194,32 @@ impl Backend for FlatpakBackend {
     async fn search(&self, query: &str) -> Vec<SearchResult> {
         crate::flatpak::search(query)
     }
-    async fn install(&self, package: &str) {
-        match crate::flatpak::install_flatpak(package).await {
-            Ok(_) => println!("[reap][backend] Installed {}", package),
-            Err(e) => eprintln!("[reap][backend] Install failed for {}: {:?}", package, e),
-        }
-    }
-    async fn upgrade(&self) {
-        match crate::flatpak::upgrade_flatpak().await {
-            Ok(_) => println!("[reap][backend] Upgrade all succeeded"),
-            Err(e) => eprintln!("[reap][backend] Upgrade all failed: {:?}", e),
-        }
+    async fn install(&self, package: &str) -> Result<InstallOutcome, ReapError> {
+        let start = Instant::now();
+        crate::flatpak::install_flatpak(package)
+            .await
+            .map_err(command_failed)?;
+        println!("[reap][backend] Installed {}", package);
+        Ok(InstallOutcome::installed(
+            package,
+            Source::Flatpak,
+            start.elapsed(),
+        ))
+    }
+    async fn upgrade(&self) -> Result<(), ReapError> {
+        crate::flatpak::upgrade_flatpak()
+            .await
+            .map_err(command_failed)?;
+        println!("[reap][backend] Upgrade all succeeded");
+        Ok(())
     }
-    async fn audit(&self, _package: &str) {
+    async fn audit(&self, _package: &str) -> Result<(), ReapError> {
         println!("Audit not implemented for Flatpak yet.");
+        Ok(())
     }
-    async fn gpg_check(&self, _package: &str) {
+    async fn gpg_check(&self, _package: &str) -> Result<(), ReapError> {
         println!("GPG check not implemented for Flatpak yet.");
+        Ok(())
     }
 }
 
@@ -261,30 +309,45 @@ impl Backend for AptBackend {
         }
         results
     }
-    async fn install(&self, package: &str) {
-        let _ = std::process::Command::new("sudo")
+    async fn install(&self, package: &str) -> Result<InstallOutcome, ReapError> {
+        let start = Instant::now();
+        let status = std::process::Command::new("sudo")
             .arg("apt")
             .arg("install")
             .arg("-y")
             .arg(package)
-            .status();
-    }
-    async fn upgrade(&self) {
-        let _ = std::process::Command::new("sudo")
+            .status()?;
+        if !status.success() {
+            return Err(ReapError::CommandFailed(format!(
+                "apt install {} exited with {}",
+                package, status
+            )));
+        }
+        Ok(InstallOutcome::installed(
+            package,
+            Source::Custom("apt".to_string()),
+            start.elapsed(),
+        ))
+    }
+    async fn upgrade(&self) -> Result<(), ReapError> {
+        std::process::Command::new("sudo")
             .arg("apt")
             .arg("update")
-            .status();
-        let _ = std::process::Command::new("sudo")
+            .status()?;
+        std::process::Command::new("sudo")
             .arg("apt")
             .arg("upgrade")
             .arg("-y")
-            .status();
+            .status()?;
+        Ok(())
     }
-    async fn audit(&self, _package: &str) {
+    async fn audit(&self, _package: &str) -> Result<(), ReapError> {
         println!("Audit not implemented for Apt yet.");
+        Ok(())
     }
-    async fn gpg_check(&self, _package: &str) {
+    async fn gpg_check(&self, _package: &str) -> Result<(), ReapError> {
         println!("GPG check not implemented for Apt yet.");
+        Ok(())
     }
 }
 
@@ -308,7 +371,7 @@ impl BackendImpl {
         }
     }
     #[allow(dead_code)]
-    pub async fn install(&self, pkg: &str) {
+    pub async fn install(&self, pkg: &str) -> Result<InstallOutcome, ReapError> {
         match self {
             BackendImpl::Aur(b) => b.install(pkg).await,
             BackendImpl::Flatpak(b) => b.install(pkg).await,
@@ -317,7 +380,7 @@ impl BackendImpl {
         }
     }
     #[allow(dead_code)]
-    pub async fn upgrade(&self) {
+    pub async fn upgrade(&self) -> Result<(), ReapError> {
         match self {
             BackendImpl::Aur(b) => b.upgrade().await,
             BackendImpl::Flatpak(b) => b.upgrade().await,
@@ -326,7 +389,7 @@ impl BackendImpl {
         }
     }
     #[allow(dead_code)]
-    pub async fn audit(&self, pkg: &str) {
+    pub async fn audit(&self, pkg: &str) -> Result<(), ReapError> {
         match self {
             BackendImpl::Aur(b) => b.audit(pkg).await,
             BackendImpl::Flatpak(b) => b.audit(pkg).await,
@@ -335,7 +398,7 @@ impl BackendImpl {
         }
     }
     #[allow(dead_code)]
-    pub async fn gpg_check(&self, pkg: &str) {
+    pub async fn gpg_check(&self, pkg: &str) -> Result<(), ReapError> {
         match self {
             BackendImpl::Aur(b) => b.gpg_check(pkg).await,
             BackendImpl::Flatpak(b) => b.gpg_check(pkg).await,
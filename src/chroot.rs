@@ -0,0 +1,123 @@
+// Clean-chroot builds with devtools (mkarchroot, arch-nspawn, makechrootpkg)
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+/// Errors produced while preparing the chroot or building in it
+#[derive(Debug, Error)]
+pub enum ChrootError {
+    #[error("{0} not found; install devtools for chroot builds")]
+    MissingTool(&'static str),
+    #[error("{command} exited with {status}")]
+    Command { command: String, status: String },
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A devtools chroot directory: the base system in `root/` plus per-build working copies.
+///
+/// The base is created once with `base-devel` and refreshed before each build, so builds
+/// only see the packages they declare and `build()` never runs on the host.
+#[derive(Debug, Clone)]
+pub struct Chroot {
+    dir: PathBuf,
+}
+
+impl Chroot {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.cache/reap/chroot`
+    pub fn default_dir() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("reap/chroot")
+    }
+
+    /// The reusable base chroot
+    pub fn root(&self) -> PathBuf {
+        self.dir.join("root")
+    }
+
+    /// Whether the base chroot has been created (mkarchroot leaves `.arch-chroot` behind)
+    pub fn exists(&self) -> bool {
+        self.root().join(".arch-chroot").exists()
+    }
+
+    /// Create the base chroot on first use, otherwise bring it up to date
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if devtools is missing or mkarchroot/arch-nspawn fail.
+    pub fn ensure(&self) -> Result<(), ChrootError> {
+        if self.exists() {
+            return run(
+                "arch-nspawn",
+                Command::new("arch-nspawn").arg(self.root()).args([
+                    "pacman",
+                    "-Syu",
+                    "--noconfirm",
+                ]),
+            );
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        run(
+            "mkarchroot",
+            Command::new("mkarchroot")
+                .arg(self.root())
+                .arg("base-devel"),
+        )
+    }
+
+    /// Build the PKGBUILD in `build_dir` in a clean copy of the base chroot.
+    ///
+    /// `deps` are locally built archives installed into the copy first, for dependencies the
+    /// sync repositories cannot provide. Built archives are moved into `pkgdest`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if devtools is missing, makechrootpkg fails or an archive cannot be moved.
+    pub fn build(
+        &self,
+        build_dir: &Path,
+        pkgdest: &Path,
+        deps: &[PathBuf],
+    ) -> Result<(), ChrootError> {
+        let mut cmd = Command::new("makechrootpkg");
+        cmd.arg("-c").arg("-r").arg(&self.dir);
+        for dep in deps {
+            cmd.arg("-I").arg(dep);
+        }
+        cmd.env("PKGDEST", pkgdest).current_dir(build_dir);
+        run("makechrootpkg", &mut cmd)?;
+        // Without a PKGDEST in makepkg.conf the archives are left next to the PKGBUILD
+        for entry in std::fs::read_dir(build_dir)?.flatten() {
+            let path = entry.path();
+            if crate::artifacts::is_package_archive(&path) {
+                std::fs::rename(&path, pkgdest.join(entry.file_name()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Chroot {
+    fn default() -> Self {
+        Self::new(Self::default_dir())
+    }
+}
+
+fn run(tool: &'static str, cmd: &mut Command) -> Result<(), ChrootError> {
+    if which::which(tool).is_err() {
+        return Err(ChrootError::MissingTool(tool));
+    }
+    let status = cmd.status()?;
+    if !status.success() {
+        return Err(ChrootError::Command {
+            command: tool.to_string(),
+            status: status.to_string(),
+        });
+    }
+    Ok(())
+}
@@ -49,6 +49,28 @@ pub struct Cli {
     pub downgrade: Option<String>,
     #[arg(long = "diff", help = "Show PKGBUILD diff before install/upgrade")]
     pub diff: bool,
+    #[arg(long = "chroot", help = "Build packages in a clean devtools chroot")]
+    pub chroot: bool,
+    #[arg(
+        long = "remove-make",
+        help = "Remove make-only dependencies after a successful build"
+    )]
+    pub remove_make: bool,
+    #[arg(
+        long = "cascade",
+        help = "Also remove installed packages that depend on the removed ones"
+    )]
+    pub cascade: bool,
+    #[arg(
+        long = "recursive",
+        help = "Also remove dependencies that nothing else needs"
+    )]
+    pub recursive: bool,
+    #[arg(
+        long = "devel",
+        help = "Also upgrade VCS packages whose upstream source has new commits"
+    )]
+    pub devel: bool,
     #[arg(
         long = "resolve-deps",
         help = "Automatically install missing dependencies before build"
@@ -109,6 +131,8 @@ pub enum Commands {
     Audit { pkg: String },
     /// Rollback a package
     Rollback { pkg: String },
+    /// Resume or roll back an interrupted install or upgrade
+    Recover,
     /// Sync package database
     SyncDb,
     /// Pin a package
@@ -144,6 +168,11 @@ pub enum Commands {
         #[command(subcommand)]
         cmd: TapCmd,
     },
+    /// Local repository of built packages
+    Repo {
+        #[command(subcommand)]
+        cmd: RepoCmd,
+    },
     /// Generate shell completion
     Completion { shell: String },
     /// Backup current config to backup directory
@@ -155,6 +184,24 @@ pub enum Commands {
         #[arg(long = "all", help = "Include orphaned pacman packages, not just AUR")]
         all: bool,
     },
+    /// Change the install reason of installed packages
+    #[command(group(clap::ArgGroup::new("reason").required(true)))]
+    Mark {
+        #[arg(required = true)]
+        pkgs: Vec<String>,
+        #[arg(
+            long = "asdeps",
+            group = "reason",
+            help = "Mark as installed as a dependency"
+        )]
+        asdeps: bool,
+        #[arg(
+            long = "asexplicit",
+            group = "reason",
+            help = "Mark as explicitly installed"
+        )]
+        asexplicit: bool,
+    },
     /// Manage global configuration
     Config {
         #[command(subcommand)]
@@ -229,6 +276,22 @@ pub enum TapCmd {
     List,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum RepoCmd {
+    /// List the packages kept in the local repository
+    List,
+    /// Delete old builds, keeping the newest versions of each package
+    Prune {
+        #[arg(long, default_value_t = 1, help = "Builds to keep per package")]
+        keep: usize,
+    },
+    /// Serve the local repository over HTTP for other machines
+    Serve {
+        #[arg(long, default_value = "0.0.0.0:8080")]
+        bind: String,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum ConfigCmd {
     /// Set a config key
@@ -0,0 +1,203 @@
+// Persistent per-pkgbase git clones of AUR packages
+use crate::srcinfo::SrcInfo;
+use crate::version::vercmp;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+/// Ref inside each clone that points at the last commit the user reviewed
+const REVIEWED_REF: &str = "refs/reap/reviewed";
+
+/// git's empty tree, the base of the diff for a package that was never reviewed
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Errors produced while maintaining the clone cache
+#[derive(Debug, Error)]
+pub enum CloneCacheError {
+    #[error("git {command} failed: {stderr}")]
+    Git { command: String, stderr: String },
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0} has no PKGBUILD (is it an AUR package base?)")]
+    MissingPkgbuild(String),
+}
+
+/// Clones of AUR package bases kept between builds, one directory per pkgbase.
+///
+/// Existing clones are updated with `git fetch` and a fast-forward merge, so upgrades only
+/// download what changed and there is always history to diff against.
+#[derive(Debug, Clone)]
+pub struct CloneCache {
+    root: PathBuf,
+}
+
+impl CloneCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// `~/.cache/reap/clones`
+    pub fn default_root() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("reap/clones")
+    }
+
+    /// Directory of the clone for `pkgbase`
+    pub fn path(&self, pkgbase: &str) -> PathBuf {
+        self.root.join(pkgbase)
+    }
+
+    /// Clone `pkgbase` from `url`, or fetch and fast-forward an existing clone.
+    ///
+    /// Local edits to tracked files (from `--edit`) are discarded first; build artefacts
+    /// are left in place.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if git fails, the clone cannot fast-forward or has no PKGBUILD.
+    pub fn sync(&self, pkgbase: &str, url: &str) -> Result<PathBuf, CloneCacheError> {
+        let dir = self.path(pkgbase);
+        if dir.join(".git").exists() {
+            git(&dir, &["reset", "--quiet", "--hard", "HEAD"])?;
+            git(&dir, &["fetch", "--quiet", "origin"])?;
+            git(&dir, &["merge", "--quiet", "--ff-only", "@{upstream}"])?;
+        } else {
+            std::fs::create_dir_all(&self.root)?;
+            let dir_arg = dir.to_string_lossy();
+            git(&self.root, &["clone", "--quiet", url, dir_arg.as_ref()])?;
+        }
+        if !dir.join("PKGBUILD").exists() {
+            return Err(CloneCacheError::MissingPkgbuild(pkgbase.to_string()));
+        }
+        Ok(dir)
+    }
+
+    /// Commit currently checked out for `pkgbase`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no clone or it has no commits.
+    pub fn head(&self, pkgbase: &str) -> Result<String, CloneCacheError> {
+        git(&self.path(pkgbase), &["rev-parse", "HEAD"])
+    }
+
+    /// Last commit of `pkgbase` the user reviewed, if any
+    pub fn reviewed(&self, pkgbase: &str) -> Option<String> {
+        git(
+            &self.path(pkgbase),
+            &["rev-parse", "--verify", "--quiet", REVIEWED_REF],
+        )
+        .ok()
+    }
+
+    /// Record `commit` as reviewed for `pkgbase`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ref cannot be updated.
+    pub fn mark_reviewed(&self, pkgbase: &str, commit: &str) -> Result<(), CloneCacheError> {
+        git(&self.path(pkgbase), &["update-ref", REVIEWED_REF, commit]).map(|_| ())
+    }
+
+    /// Whether HEAD of `pkgbase` differs from the last reviewed commit
+    pub fn needs_review(&self, pkgbase: &str) -> bool {
+        match (self.reviewed(pkgbase), self.head(pkgbase)) {
+            (Some(reviewed), Ok(head)) => reviewed != head,
+            _ => true,
+        }
+    }
+
+    /// Patch of every file changed between the reviewed commit and HEAD.
+    ///
+    /// For a package that was never reviewed this is the full contents of the repository.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if git fails.
+    pub fn diff_since_reviewed(&self, pkgbase: &str) -> Result<String, CloneCacheError> {
+        let base = self
+            .reviewed(pkgbase)
+            .unwrap_or_else(|| EMPTY_TREE.to_string());
+        git(
+            &self.path(pkgbase),
+            &["diff", "--no-color", "--stat", "--patch", &base, "HEAD"],
+        )
+    }
+
+    /// One-line summaries of the commits after the reviewed one, newest first.
+    ///
+    /// Returns `None` when nothing has been reviewed yet.
+    pub fn commits_since_reviewed(&self, pkgbase: &str) -> Option<Vec<String>> {
+        let reviewed = self.reviewed(pkgbase)?;
+        let range = format!("{}..HEAD", reviewed);
+        let log = git(&self.path(pkgbase), &["log", "--oneline", &range]).ok()?;
+        Some(log.lines().map(str::to_string).collect())
+    }
+
+    /// Contents of `file` at `rev` in the clone of `pkgbase`
+    pub fn file_at(&self, pkgbase: &str, rev: &str, file: &str) -> Option<String> {
+        git(&self.path(pkgbase), &["show", &format!("{}:{}", rev, file)]).ok()
+    }
+
+    /// Commits that changed `file` in the clone of `pkgbase`, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no clone or git fails.
+    pub fn file_history(&self, pkgbase: &str, file: &str) -> Result<Vec<String>, CloneCacheError> {
+        let log = git(&self.path(pkgbase), &["log", "--format=%H", "--", file])?;
+        Ok(log.lines().map(str::to_string).collect())
+    }
+
+    /// Newest commit whose `.SRCINFO` has version `requested`, with the full version found.
+    ///
+    /// Without a pkgrel in `requested` any release of that pkgver matches.
+    pub fn find_version(&self, pkgbase: &str, requested: &str) -> Option<(String, String)> {
+        self.file_history(pkgbase, ".SRCINFO")
+            .ok()?
+            .into_iter()
+            .find_map(|commit| {
+                let srcinfo = SrcInfo::parse(&self.file_at(pkgbase, &commit, ".SRCINFO")?).ok()?;
+                let version = srcinfo.full_version();
+                vercmp(&version, requested)
+                    .is_eq()
+                    .then_some((commit, version))
+            })
+    }
+
+    /// Check out `rev` in the clone of `pkgbase`, discarding local edits, and return what was
+    /// checked out before so it can be restored with another `checkout`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no clone or `rev` does not exist.
+    pub fn checkout(&self, pkgbase: &str, rev: &str) -> Result<String, CloneCacheError> {
+        let dir = self.path(pkgbase);
+        let previous = match git(&dir, &["symbolic-ref", "--quiet", "--short", "HEAD"]) {
+            Ok(branch) => branch,
+            Err(_) => self.head(pkgbase)?,
+        };
+        git(&dir, &["reset", "--quiet", "--hard", "HEAD"])?;
+        git(&dir, &["checkout", "--quiet", rev])?;
+        Ok(previous)
+    }
+}
+
+impl Default for CloneCache {
+    fn default() -> Self {
+        Self::new(Self::default_root())
+    }
+}
+
+/// Run git in `dir` and return its trimmed stdout
+fn git(dir: &Path, args: &[&str]) -> Result<String, CloneCacheError> {
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        return Err(CloneCacheError::Git {
+            command: args.join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -15,7 +15,7 @@ impl ReapConfig {
     pub fn load() -> Self {
         let global = GlobalConfig::load();
         ReapConfig {
-            ignored_packages: vec![],
+            ignored_packages: global.ignored_packages.clone().unwrap_or_default(),
             parallel: global
                 .enable_cache
                 .map(|x| if x { 4 } else { 2 })
@@ -29,6 +29,7 @@ impl ReapConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GlobalConfig {
     pub backend_order: Vec<String>,
     pub auto_resolve_deps: bool,
@@ -38,6 +39,12 @@ pub struct GlobalConfig {
     pub show_tips: Option<bool>,
     pub enable_cache: Option<bool>,
     pub enable_lua_hooks: Option<bool>,
+    /// Base URL of the AUR (or an internal mirror); defaults to aur.archlinux.org
+    pub aur_url: Option<String>,
+    /// Packages upgrades leave alone, such as deliberately downgraded ones
+    pub ignored_packages: Option<Vec<String>>,
+    /// GPG key that signs local repo builds and its database
+    pub repo_sign_key: Option<String>,
 }
 
 impl Default for GlobalConfig {
@@ -56,6 +63,9 @@ impl Default for GlobalConfig {
             show_tips: Some(false),
             enable_cache: Some(true),
             enable_lua_hooks: Some(false),
+            aur_url: None,
+            ignored_packages: None,
+            repo_sign_key: None,
         }
     }
 }
@@ -82,6 +92,13 @@ impl GlobalConfig {
         println!("[config] Using default config.");
         GlobalConfig::default()
     }
+
+    /// AUR base URL, falling back to the public AUR
+    pub fn aur_url(&self) -> &str {
+        self.aur_url
+            .as_deref()
+            .unwrap_or(crate::aur_rpc::DEFAULT_AUR_URL)
+    }
 }
 
 pub fn set_config_key(key: &str, value_str: &str) {
@@ -98,6 +115,30 @@ pub fn set_config_key(key: &str, value_str: &str) {
     let _ = fs::write(&path, doc.to_string());
 }
 
+/// Add `pkg` to `ignored_packages` in reap.toml so upgrades skip it
+///
+/// # Errors
+///
+/// Returns an error if reap.toml cannot be written.
+pub fn ignore_package(pkg: &str) -> std::io::Result<()> {
+    let path = config_path();
+    let mut doc = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| s.parse::<DocumentMut>().ok())
+        .unwrap_or_default();
+    let ignored = doc["ignored_packages"].or_insert(value(toml_edit::Array::new()));
+    let missing = ignored
+        .as_array_mut()
+        .filter(|list| !list.iter().any(|p| p.as_str() == Some(pkg)));
+    if let Some(list) = missing {
+        list.push(pkg);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, doc.to_string())
+}
+
 pub fn get_config_key(key: &str) -> Option<String> {
     let path = config_path();
     if path.exists() {
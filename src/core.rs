@@ -1,33 +1,46 @@
+use crate::artifacts::ArtifactStore;
 use crate::aur;
-use crate::aur::upgrade_all;
-use crate::backend::{AurBackend, Backend};
+use crate::chroot::{Chroot, ChrootError};
 use crate::cli::Cli;
-use crate::cli::{Commands, ConfigCmd, TapCmd};
+use crate::cli::{Commands, ConfigCmd, RepoCmd, TapCmd};
+use crate::clone_cache::{CloneCache, CloneCacheError};
 use crate::config::GlobalConfig;
 use crate::config::ReapConfig;
 use crate::flatpak;
+use crate::graph::GraphVisualizer;
+use crate::history::HistoryManager;
 use crate::hooks::{HookContext, post_install, pre_install};
+use crate::interactive::InteractiveManager;
+use crate::journal::{Journal, JournalError, JournalStep, StepStatus, Transaction};
+use crate::local_repo::{
+    LOCAL_REPO_NAME, LocalRepo, LocalRepoError, PACMAN_CACHE, RepoPackage, find_archives,
+};
+use crate::make_deps::MakeDeps;
 use crate::pacman;
+use crate::pacman_db::{InstallReason, PackageDb, PacmanDb};
 use crate::profiles::ProfileManager;
+use crate::solver::BuildOrigin;
+use crate::srcinfo::SrcInfo;
 use crate::tap::{Tap, discover_taps, find_tap_for_pkg};
 use crate::trust::TrustEngine;
 use crate::tui;
 use crate::tui::LogPane;
+use crate::upgrade::{self, UpgradePlan};
 use crate::utils;
+use crate::version::{Dependency, vercmp};
 use anyhow::anyhow;
 use anyhow::{Context, Result};
 use chrono::Local;
-use futures::FutureExt;
 use futures::future::join_all;
-use indicatif::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::fs;
 use std::io::BufRead;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::sync::Semaphore;
 
@@ -38,6 +51,152 @@ pub enum ReapError {
     CommandFailed(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Review of {0} was declined")]
+    ReviewDeclined(String),
+    #[error("pacman database is locked: {0}")]
+    DatabaseLocked(String),
+    #[error(transparent)]
+    CloneCache(#[from] CloneCacheError),
+    #[error(transparent)]
+    Chroot(#[from] ChrootError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error("{0} is not installed")]
+    NotInstalled(String),
+}
+
+/// How the operation on a single package ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutcomeStatus {
+    Installed,
+    Skipped(String),
+    Failed(String),
+}
+
+/// Result of installing one package, collected for the end-of-run summary
+#[derive(Debug, Clone)]
+pub struct InstallOutcome {
+    pub package: String,
+    pub source: Option<Source>,
+    /// Version now installed, when pacman knows the package
+    pub version: Option<String>,
+    pub duration: Duration,
+    pub status: OutcomeStatus,
+}
+
+impl InstallOutcome {
+    pub fn installed(package: &str, source: Source, duration: Duration) -> Self {
+        let version = match source {
+            Source::Flatpak => None,
+            _ => pacman::get_version(package),
+        };
+        Self {
+            package: package.to_string(),
+            source: Some(source),
+            version,
+            duration,
+            status: OutcomeStatus::Installed,
+        }
+    }
+
+    pub fn skipped(
+        package: &str,
+        source: Option<Source>,
+        duration: Duration,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            package: package.to_string(),
+            source,
+            version: None,
+            duration,
+            status: OutcomeStatus::Skipped(reason.into()),
+        }
+    }
+
+    pub fn failed(
+        package: &str,
+        source: Option<Source>,
+        duration: Duration,
+        reason: impl ToString,
+    ) -> Self {
+        Self {
+            package: package.to_string(),
+            source,
+            version: None,
+            duration,
+            status: OutcomeStatus::Failed(reason.to_string()),
+        }
+    }
+
+    /// Outcome of a build/install step that returns no value of its own
+    pub fn from_result<E: ToString>(
+        package: &str,
+        source: Source,
+        duration: Duration,
+        dry_run: bool,
+        result: Result<(), E>,
+    ) -> Self {
+        match result {
+            Ok(()) if dry_run => Self::skipped(package, Some(source), duration, "dry run"),
+            Ok(()) => Self::installed(package, source, duration),
+            Err(e) => Self::failed(package, Some(source), duration, e),
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, OutcomeStatus::Failed(_))
+    }
+}
+
+/// Render the per-run summary table of all package outcomes
+pub fn format_summary(outcomes: &[InstallOutcome]) -> String {
+    let mut out = format!(
+        "{:<28} {:<16} {:<14} {:>8}  {}\n",
+        "PACKAGE", "VERSION", "SOURCE", "TIME", "RESULT"
+    );
+    for o in outcomes {
+        let result = match &o.status {
+            OutcomeStatus::Installed => "installed".to_string(),
+            OutcomeStatus::Skipped(reason) => format!("skipped: {}", reason),
+            OutcomeStatus::Failed(reason) => format!("failed: {}", reason),
+        };
+        out.push_str(&format!(
+            "{:<28} {:<16} {:<14} {:>7.1}s  {}\n",
+            o.package,
+            o.version.as_deref().unwrap_or("-"),
+            o.source.as_ref().map_or("-", Source::label),
+            o.duration.as_secs_f64(),
+            result
+        ));
+    }
+    out
+}
+
+/// Print the summary table; returns `true` if any package failed
+pub fn print_summary(outcomes: &[InstallOutcome]) -> bool {
+    use owo_colors::OwoColorize;
+    if outcomes.is_empty() {
+        return false;
+    }
+    println!("\n{} Summary", "📋".bright_blue());
+    for line in format_summary(outcomes).lines() {
+        if line.contains(" failed: ") {
+            println!("{}", line.red());
+        } else {
+            println!("{}", line);
+        }
+    }
+    let failed = outcomes.iter().filter(|o| o.is_failed()).count();
+    if failed > 0 {
+        println!(
+            "{} {} of {} package(s) failed",
+            "❌".bright_red(),
+            failed,
+            outcomes.len()
+        );
+    }
+    failed > 0
 }
 
 /// Backup package state before install (files and pacman db)
@@ -115,6 +274,45 @@ pub struct InstallOptions {
     pub strict_signatures: bool,
     #[allow(dead_code)]
     pub max_parallel: usize,
+    /// Open the PKGBUILD in `$EDITOR` before building
+    pub edit: bool,
+    /// Print the resolved plan without building or installing anything
+    pub dry_run: bool,
+    /// Always show the review diff, even when changes are accepted automatically
+    pub diff: bool,
+    /// Accept prompts such as the pre-build review without asking
+    pub noconfirm: bool,
+    /// Build in a clean devtools chroot instead of on the host
+    pub chroot: bool,
+    /// Remove make-only dependencies after a successful transaction without asking
+    pub remove_make: bool,
+    /// Also remove packages that depend on the ones being removed
+    pub cascade: bool,
+    /// Also remove dependencies that are no longer needed
+    pub recursive: bool,
+    /// Also upgrade VCS packages whose upstream commit moved since they were built
+    pub devel: bool,
+}
+
+impl InstallOptions {
+    /// Options from the global command-line flags
+    pub fn from_cli(cli: &Cli) -> Self {
+        Self {
+            insecure: cli.insecure,
+            gpg_keyserver: cli.gpg_keyserver.clone(),
+            max_parallel: ReapConfig::load().parallel,
+            edit: cli.edit,
+            dry_run: cli.dry_run,
+            diff: cli.diff,
+            noconfirm: cli.noconfirm || cli.yes,
+            chroot: cli.chroot,
+            remove_make: cli.remove_make,
+            cascade: cli.cascade,
+            recursive: cli.recursive,
+            devel: cli.devel,
+            ..Default::default()
+        }
+    }
 }
 
 pub fn get_installed_packages() -> HashMap<String, Source> {
@@ -129,16 +327,16 @@ pub fn get_installed_packages() -> HashMap<String, Source> {
         }
     }
     // Pacman
-    if let Ok(out) = Command::new("pacman").arg("-Qq").output() {
-        for line in String::from_utf8_lossy(&out.stdout).lines() {
-            pkgs.insert(line.trim().to_string(), Source::Pacman);
+    if let Ok(local) = PacmanDb::system().local() {
+        for pkg in local.iter() {
+            pkgs.insert(pkg.name.clone(), Source::Pacman);
         }
     }
     pkgs
 }
 
 /// Resolve the best source for a package, using tap, repo, AUR, or flatpak, in priority order.
-pub fn resolve_package_source(
+pub async fn resolve_package_source(
     pkg: &str,
     forced_tap: Option<&str>,
     config: &GlobalConfig,
@@ -159,13 +357,20 @@ pub fn resolve_package_source(
     {
         return Some((Source::Pacman, None, 20, None));
     }
-    // 3. AUR
-    if config.backend_order.contains(&"aur".to_string())
-        && aur::aur_search_results(pkg).iter().any(|r| r.name == pkg)
-    {
+    // 3. An up-to-date build in the local repo instead of rebuilding from the AUR
+    if config.backend_order.contains(&"aur".to_string()) && local_build(pkg).await.is_some() {
+        return Some((
+            Source::BinaryRepo(LOCAL_REPO_NAME.to_string()),
+            None,
+            15,
+            None,
+        ));
+    }
+    // 4. AUR
+    if config.backend_order.contains(&"aur".to_string()) && aur::aur_has_package(pkg).await {
         return Some((Source::Aur, None, 10, None));
     }
-    // 4. Flatpak
+    // 5. Flatpak
     if config.backend_order.contains(&"flatpak".to_string()) {
         let output = std::process::Command::new("flatpak")
             .arg("search")
@@ -187,7 +392,7 @@ pub async fn install_with_priority(
     _confirm: bool,
     log: Arc<LogPane>,
     opts: &InstallOptions,
-) {
+) -> InstallOutcome {
     use owo_colors::OwoColorize;
     let start = Instant::now();
     
@@ -205,13 +410,15 @@ pub async fn install_with_priority(
         tap: None,
     };
     
-    println!("{} Running pre-install hooks...", "🔧".bright_cyan());
-    log.push(&format!("{} pre_install executing for {}", "🔧".to_string(), pkg));
-    pre_install(&ctx);
+    if !opts.dry_run {
+        println!("{} Running pre-install hooks...", "🔧".bright_cyan());
+        log.push(&format!("{} pre_install executing for {}", "🔧".to_string(), pkg));
+        pre_install(&ctx);
+    }
     
     let global_config = GlobalConfig::load();
-    if let Some((source, tap_name, prio, tap_obj)) =
-        resolve_package_source(pkg, None, &global_config)
+    let outcome = if let Some((source, tap_name, prio, tap_obj)) =
+        resolve_package_source(pkg, None, &global_config).await
     {
         // Print source information with colors
         match &source {
@@ -258,7 +465,19 @@ pub async fn install_with_priority(
             tap_name.as_deref().unwrap_or(""),
             prio
         ));
-        match source {
+        // AUR dry runs print the solved build plan below
+        if opts.dry_run && !matches!(source, Source::Aur) {
+            println!(
+                "[reap] Dry run: would install {} from {}",
+                pkg,
+                source.label()
+            );
+            return InstallOutcome::skipped(pkg, Some(source), start.elapsed(), "dry run");
+        }
+        let fail = |reason: &str| {
+            InstallOutcome::failed(pkg, Some(source.clone()), start.elapsed(), reason)
+        };
+        let outcome = match source {
             Source::Custom(ref _tap_repo) => {
                 if let Some(tap) = tap_obj {
                     let tap_path = crate::tap::ensure_tap_cloned(&tap);
@@ -335,7 +554,7 @@ pub async fn install_with_priority(
                                             "{} Aborting install. Use --insecure to override.",
                                             "✋".red()
                                         ));
-                                        return;
+                                        return fail("PKGBUILD signature verification failed");
                                     } else {
                                         log.push(&format!(
                                             "{} Continuing install due to --insecure.",
@@ -354,7 +573,7 @@ pub async fn install_with_priority(
                                         "{} Aborting install. Use --insecure to override.",
                                         "✋".red()
                                     ));
-                                    return;
+                                    return fail("PKGBUILD signature verification failed");
                                 } else {
                                     log.push(&format!(
                                         "{} Continuing install due to --insecure.",
@@ -365,7 +584,7 @@ pub async fn install_with_priority(
                         } else {
                             log.push(&format!("{} PKGBUILD.sig missing. Aborting install. Use --insecure to override.", "❌".red()));
                             if !opts.insecure {
-                                return;
+                                return fail("PKGBUILD.sig missing");
                             } else {
                                 log.push(&format!(
                                     "{} Continuing install due to --insecure.",
@@ -379,37 +598,73 @@ pub async fn install_with_priority(
                             "⚠️".yellow()
                         ));
                         if !opts.insecure {
-                            return;
+                            return fail("tap publisher not verified");
                         }
                     }
                 }
                 // ...proceed with install if verified or --insecure...
+                InstallOutcome::skipped(
+                    pkg,
+                    Some(source.clone()),
+                    start.elapsed(),
+                    "tap install not implemented",
+                )
             }
             Source::Pacman => {
                 log.push(&format!("[reap][pacman] Installing {} from repo", pkg));
-                pacman::install(pkg);
+                if let Err(e) = pacman::install(pkg) {
+                    return fail(&e.to_string());
+                }
                 log.push(&format!("[✓] Installed {} from Pacman", pkg));
+                InstallOutcome::installed(pkg, Source::Pacman, start.elapsed())
             }
             Source::Aur => {
                 println!("{} Building {} from AUR source...", "🔨".bright_yellow(), pkg.bright_white());
                 log.push(&format!("[reap][aur] Installing {} from AUR", pkg));
-                let opts = InstallOptions {
-                    insecure: false,
-                    gpg_keyserver: None,
-                    fast_mode: false,
-                    strict_signatures: false,
-                    max_parallel: 4,
-                };
-                let _ = install_aur_native(pkg, &log, &opts).await;
+                if let Err(e) = aur::install(vec![pkg], opts).await {
+                    log.push(&format!("[reap][aur] Failed to install {}: {}", pkg, e));
+                    println!("{} Failed to install {}: {}", "❌".bright_red(), pkg, e);
+                    return fail(&e.to_string());
+                }
+                if opts.dry_run {
+                    return InstallOutcome::skipped(
+                        pkg,
+                        Some(Source::Aur),
+                        start.elapsed(),
+                        "dry run",
+                    );
+                }
                 println!("{} Successfully installed {} from AUR!", "✅".bright_green(), pkg.bright_white().bold());
                 log.push(&format!("[✓] Installed {} from AUR", pkg));
+                InstallOutcome::installed(pkg, Source::Aur, start.elapsed())
             }
             Source::Flatpak => {
                 log.push(&format!("[reap][flatpak] Installing {} from Flatpak", pkg));
-                let _ = flatpak::install_flatpak(pkg).await;
+                if let Err(e) = flatpak::install_flatpak(pkg).await {
+                    return fail(&e.to_string());
+                }
+                InstallOutcome::installed(pkg, Source::Flatpak, start.elapsed())
             }
-            _ => log.push(&format!("[!] Unknown source for {}", pkg)),
-        }
+            Source::BinaryRepo(ref repo) => {
+                log.push(&format!("[reap][repo] Installing {} from {}", pkg, repo));
+                let result = if repo == LOCAL_REPO_NAME {
+                    local_build(pkg)
+                        .await
+                        .ok_or_else(|| ReapError::CommandFailed("no local build".to_string()))
+                        .and_then(|build| install_built(&[build.path], &log))
+                } else {
+                    pacman::install(&format!("{}/{}", repo, pkg))
+                };
+                if let Err(e) = result {
+                    return fail(&e.to_string());
+                }
+                InstallOutcome::installed(pkg, source.clone(), start.elapsed())
+            }
+            _ => {
+                log.push(&format!("[!] Unknown source for {}", pkg));
+                fail("unknown source")
+            }
+        };
         println!("{} Running post-install hooks...", "🔧".bright_cyan());
         log.push(&format!("[reap][hook] post_install executing for {}", pkg));
         post_install(&ctx);
@@ -423,6 +678,7 @@ pub async fn install_with_priority(
             "[reap][timing] install_with_priority for {} took: {:?}",
             pkg, elapsed
         ));
+        outcome
     } else {
         println!("{} Could not resolve source for {}", 
             "❌".bright_red(), 
@@ -433,7 +689,8 @@ pub async fn install_with_priority(
             pkg
         ));
         crate::utils::rollback(pkg);
-    }
+        InstallOutcome::failed(pkg, None, start.elapsed(), "could not resolve source")
+    };
     // Backup before install
     if let Ok(backup_path) = backup_package_state(pkg) {
         log.push(&format!(
@@ -441,8 +698,7 @@ pub async fn install_with_priority(
             backup_path.display()
         ));
     }
-    // Show PKGBUILD diff before install
-    show_pkgbuild_diff(pkg);
+    outcome
 }
 
 pub async fn unified_search(query: &str) -> Vec<aur::SearchResult> {
@@ -487,65 +743,120 @@ pub fn print_search_results(results: &[aur::SearchResult]) {
 }
 
 // === Bulk Install Logic ===
-pub async fn parallel_install(pkgs: &[String], config: Arc<ReapConfig>, log: Arc<LogPane>) {
-    let max_parallel = 4; // or config.parallel
-    let semaphore = Arc::new(Semaphore::new(max_parallel));
-    let mut tasks = Vec::new();
+pub async fn parallel_install(
+    pkgs: &[String],
+    config: Arc<ReapConfig>,
+    log: Arc<LogPane>,
+    opts: &InstallOptions,
+) -> Vec<InstallOutcome> {
+    // AUR packages are built as one plan so dependents wait for their dependencies
+    let global_config = GlobalConfig::load();
+    let mut aur_pkgs = Vec::new();
+    let mut other_pkgs = Vec::new();
     for pkg in pkgs {
+        match resolve_package_source(pkg, None, &global_config).await {
+            Some((Source::Aur, ..)) => aur_pkgs.push(pkg.clone()),
+            _ => other_pkgs.push(pkg.clone()),
+        }
+    }
+    let semaphore = Arc::new(Semaphore::new(build_jobs(opts)));
+    let mut tasks = Vec::new();
+    for pkg in &other_pkgs {
         let sem = Arc::clone(&semaphore);
         let pkg = pkg.clone();
         let config = Arc::clone(&config);
         let log = Arc::clone(&log);
+        let opts = opts.clone();
         let permit_fut = sem.acquire_owned();
         tasks.push(tokio::spawn(async move {
             let _permit = permit_fut.await.unwrap();
-            install_with_priority(&pkg, config, true, log, &InstallOptions::default()).await;
+            install_with_priority(&pkg, config, true, log, &opts).await
         }));
     }
-    let _ = join_all(tasks).await;
+    let mut outcomes = collect_outcomes(&other_pkgs, join_all(tasks).await);
+    if !aur_pkgs.is_empty() {
+        outcomes.extend(handle_install_parallel(aur_pkgs, opts).await);
+    }
+    outcomes
 }
 
-pub async fn parallel_upgrade(pkgs: &[String], config: Arc<ReapConfig>, log: Arc<LogPane>) {
+pub async fn parallel_upgrade(
+    pkgs: &[String],
+    config: Arc<ReapConfig>,
+    log: Arc<LogPane>,
+) -> Vec<InstallOutcome> {
     let mut tasks = Vec::new();
     for pkg in pkgs {
         let config = Arc::clone(&config);
         let log = Arc::clone(&log);
         let pkg = pkg.clone();
         tasks.push(tokio::spawn(async move {
-            install_with_priority(&pkg, config, true, log, &InstallOptions::default()).await;
+            install_with_priority(&pkg, config, true, log, &InstallOptions::default()).await
         }));
     }
-    let _ = join_all(tasks).await;
+    let outcomes = collect_outcomes(pkgs, join_all(tasks).await);
     log.push("[reap] All upgrades complete.");
+    outcomes
+}
+
+/// Outcomes of spawned per-package tasks; a task that panicked counts as failed
+fn collect_outcomes(
+    pkgs: &[String],
+    results: Vec<Result<InstallOutcome, tokio::task::JoinError>>,
+) -> Vec<InstallOutcome> {
+    results
+        .into_iter()
+        .zip(pkgs)
+        .map(|(result, pkg)| {
+            result.unwrap_or_else(|e| InstallOutcome::failed(pkg, None, Duration::ZERO, e))
+        })
+        .collect()
 }
 
 pub fn repo_has_package(pkg: &str, repo: &str) -> bool {
-    let output = std::process::Command::new("pacman")
-        .args(["-Slq", repo])
-        .output();
-    if let Ok(out) = output {
-        String::from_utf8_lossy(&out.stdout)
-            .lines()
-            .any(|l| l.trim() == pkg)
-    } else {
-        false
-    }
+    PacmanDb::system()
+        .sync_cached(repo)
+        .is_some_and(|db| db.contains(pkg))
 }
 
+/// Binary repos for AUR packages configured in pacman.conf, reap's local repo first
 pub fn get_enabled_binary_repos() -> Vec<String> {
     let conf = std::fs::read_to_string("/etc/pacman.conf").unwrap_or_default();
     let mut repos = Vec::new();
     for line in conf.lines() {
         if let Some(repo) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
-            if repo.ends_with("-aur") || repo == "chaotic-aur" || repo == "ghostctl-aur" {
+            if repo.ends_with("-aur")
+                || repo == "chaotic-aur"
+                || repo == "ghostctl-aur"
+                || repo == LOCAL_REPO_NAME
+            {
                 repos.push(repo.to_string());
             }
         }
     }
+    repos.sort_by_key(|repo| repo != LOCAL_REPO_NAME);
     repos
 }
 
-pub fn detect_source(pkg: &str, repo: Option<&str>, binary_only: bool) -> Option<Source> {
+/// The local repo's newest build of `pkg`, unless the AUR now has a newer version
+pub async fn local_build(pkg: &str) -> Option<RepoPackage> {
+    let build = LocalRepo::default().newest(pkg)?;
+    let aur = crate::aur_rpc::client()
+        .info(&[pkg])
+        .await
+        .unwrap_or_default();
+    let outdated = aur
+        .iter()
+        .find(|p| p.name == pkg)
+        .is_some_and(|p| vercmp(&p.version, &build.version) == std::cmp::Ordering::Greater);
+    (!outdated).then_some(build)
+}
+
+pub async fn detect_source(pkg: &str, repo: Option<&str>, binary_only: bool) -> Option<Source> {
+    // A package already built at the wanted version is never rebuilt
+    if repo.is_none_or(|r| r == LOCAL_REPO_NAME) && local_build(pkg).await.is_some() {
+        return Some(Source::BinaryRepo(LOCAL_REPO_NAME.to_string()));
+    }
     if let Some(repo_name) = repo {
         if repo_has_package(pkg, repo_name) {
             return Some(Source::BinaryRepo(repo_name.to_string()));
@@ -561,7 +872,7 @@ pub fn detect_source(pkg: &str, repo: Option<&str>, binary_only: bool) -> Option
         }
     }
     if !binary_only {
-        if aur::aur_search_results(pkg).iter().any(|r| r.name == pkg) {
+        if aur::aur_has_package(pkg).await {
             return Some(Source::Aur);
         }
         let output = std::process::Command::new("flatpak")
@@ -577,51 +888,209 @@ pub fn detect_source(pkg: &str, repo: Option<&str>, binary_only: bool) -> Option
     None
 }
 
-pub fn handle_install(pkgs: Vec<String>) {
-    let backend: Box<dyn Backend> = Box::new(AurBackend::new());
-    for pkg in pkgs {
-        println!("[reap] Installing {}...", pkg);
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(backend.install(&pkg));
+/// Install AUR packages as one build plan, so each package is only built once the packages
+/// it depends on are, with independent builds running in parallel
+pub async fn handle_install_parallel(
+    pkgs: Vec<String>,
+    opts: &InstallOptions,
+) -> Vec<InstallOutcome> {
+    let start = Instant::now();
+    let targets: Vec<&str> = pkgs.iter().map(String::as_str).collect();
+    let reports = match aur::install_planned(targets, opts).await {
+        Ok(reports) => reports,
+        Err(e) => {
+            let e = e.to_string();
+            return pkgs
+                .iter()
+                .map(|pkg| InstallOutcome::failed(pkg, Some(Source::Aur), start.elapsed(), &e))
+                .collect();
+        }
+    };
+    let outcomes = pkgs
+        .iter()
+        .map(|pkg| {
+            let report = reports.iter().find(|r| r.pkgnames.contains(pkg));
+            match report {
+                Some(report) => InstallOutcome {
+                    package: pkg.clone(),
+                    source: Some(Source::Aur),
+                    version: Some(report.version.clone()),
+                    duration: report.duration,
+                    status: report.status.clone(),
+                },
+                // Not built: installed from the repos or already satisfied
+                None => InstallOutcome::from_result(
+                    pkg,
+                    Source::Pacman,
+                    start.elapsed(),
+                    opts.dry_run,
+                    Ok::<(), String>(()),
+                ),
+            }
+        })
+        .collect();
+    println!("[reap] All installs complete.");
+    outcomes
+}
+
+/// What removing a set of packages takes with it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemovalPlan {
+    /// The packages asked for
+    pub targets: Vec<String>,
+    /// Packages depending on a removed one, removed with `--cascade`
+    pub dependents: Vec<String>,
+    /// Dependencies nothing else needs any more, removed with `--recursive`
+    pub orphaned: Vec<String>,
+    /// Packages that would be left with an unsatisfied dependency, and the removed package
+    /// they need
+    pub broken: Vec<(String, String)>,
+}
+
+impl RemovalPlan {
+    /// Every package the plan removes
+    pub fn packages(&self) -> Vec<String> {
+        self.targets
+            .iter()
+            .chain(&self.dependents)
+            .chain(&self.orphaned)
+            .cloned()
+            .collect()
     }
 }
 
-pub async fn handle_install_parallel(pkgs: Vec<String>, max_parallel: usize) {
-    let semaphore = Arc::new(Semaphore::new(max_parallel));
-    let pb = ProgressBar::new(pkgs.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .expect("Failed to create ProgressStyle")
-            .progress_chars("#>-"),
-    );
-    let mut handles = Vec::new();
-    for pkg in pkgs {
-        let permit = semaphore.clone().acquire_owned().await.unwrap();
-        let pb = pb.clone();
-        let pkg = pkg.clone();
-        handles.push(tokio::spawn(async move {
-            let _permit = permit;
-            let _ = std::panic::AssertUnwindSafe(async {
-                handle_install(vec![pkg.clone()]);
-            })
-            .catch_unwind()
-            .await;
-            pb.inc(1);
-            Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
-        }));
+/// Work out what removing `targets` from `local` breaks or leaves behind.
+///
+/// Installed packages whose dependencies only removed packages satisfy are broken; with
+/// `cascade` they are removed too, until nothing else breaks. With `recursive` the
+/// dependencies that no explicitly installed or `keep` package still reaches are added.
+///
+/// # Errors
+///
+/// Returns an error if a target is not installed.
+pub fn plan_removal(
+    local: &PackageDb,
+    targets: &[String],
+    keep: &[String],
+    cascade: bool,
+    recursive: bool,
+) -> Result<RemovalPlan, ReapError> {
+    if let Some(missing) = targets.iter().find(|pkg| !local.contains(pkg)) {
+        return Err(ReapError::NotInstalled(missing.clone()));
     }
-    let _ = join_all(handles).await;
-    pb.finish_with_message("All installs complete.");
-    println!("[reap] All installs complete.");
+    let mut graph = GraphVisualizer::from_local(local);
+    graph.graph.root_packages.extend(keep.iter().cloned());
+    let mut plan = RemovalPlan {
+        targets: targets.to_vec(),
+        ..Default::default()
+    };
+    loop {
+        let removed = plan.packages();
+        let mut broken: Vec<(String, String)> = Vec::new();
+        for pkg in &removed {
+            for dependent in graph.dependents_of(pkg) {
+                let unsatisfied = !removed.contains(dependent)
+                    && !broken.iter().any(|(name, _)| name == dependent)
+                    && local.get(dependent).is_some_and(|dependent| {
+                        dependent.depends.iter().any(|dep| {
+                            let providers = local.providers(&Dependency::parse(dep));
+                            providers.iter().any(|p| p.name == *pkg)
+                                && providers.iter().all(|p| removed.contains(&p.name))
+                        })
+                    });
+                if unsatisfied {
+                    broken.push((dependent.clone(), pkg.clone()));
+                }
+            }
+        }
+        if broken.is_empty() || !cascade {
+            plan.broken = broken;
+            break;
+        }
+        plan.dependents
+            .extend(broken.into_iter().map(|(dependent, _)| dependent));
+    }
+    plan.dependents.sort();
+    if recursive {
+        plan.orphaned = graph.find_orphans_if_all_removed(&plan.packages());
+    }
+    Ok(plan)
 }
 
-pub fn handle_removal(pkgs: &[String]) {
-    for pkg in pkgs {
-        println!("[reap] Removing {}...", pkg);
-        aur::uninstall(pkg);
+/// Remove `pkgs` in one transaction after showing what else it removes or breaks.
+///
+/// Removal that would break other packages is refused unless `--cascade` removes them as
+/// well. Every removed package is snapshotted in the history first, so it can be rolled
+/// back.
+pub fn handle_removal(pkgs: &[String], opts: &InstallOptions) {
+    let db = PacmanDb::system();
+    let local = match db.local() {
+        Ok(local) => local,
+        Err(e) => {
+            eprintln!("[reap] Could not read the local package database: {}", e);
+            return;
+        }
+    };
+    let keep = ProfileManager::new()
+        .get_active_profile()
+        .map(|p| p.keep_packages)
+        .unwrap_or_default();
+    let plan = match plan_removal(&local, pkgs, &keep, opts.cascade, opts.recursive) {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("[reap] {}", e);
+            return;
+        }
+    };
+    if !plan.broken.is_empty() {
+        eprintln!("[reap] Removing {} would break:", pkgs.join(" "));
+        for (dependent, needed) in &plan.broken {
+            eprintln!("    {} (needs {})", dependent, needed);
+        }
+        eprintln!("[reap] Run with --cascade to remove them as well.");
+        return;
+    }
+    let groups = [
+        ("Requested", &plan.targets),
+        ("Dependents (--cascade)", &plan.dependents),
+        ("Unneeded dependencies (--recursive)", &plan.orphaned),
+    ];
+    for (label, group) in groups.iter().filter(|(_, group)| !group.is_empty()) {
+        println!("{}:", label);
+        for name in group.iter() {
+            let size = local.get(name).map(|pkg| pkg.installed_size).unwrap_or(0);
+            println!("    {} {}", name, utils::format_size(size));
+        }
     }
+    let packages = plan.packages();
+    let reclaimed: u64 = packages
+        .iter()
+        .filter_map(|name| local.get(name))
+        .map(|pkg| pkg.installed_size)
+        .sum();
+    println!(
+        "{} packages, {} reclaimed",
+        packages.len(),
+        utils::format_size(reclaimed)
+    );
+    if opts.dry_run {
+        return;
+    }
+    if !opts.noconfirm && !InteractiveManager::new().confirm_removal(&packages) {
+        return;
+    }
+    let mut history = HistoryManager::new();
+    for pkg in packages.iter().filter_map(|name| local.get(name)) {
+        let source = if db.sync_repo_of(&pkg.name).is_some() {
+            Source::Pacman
+        } else {
+            Source::Aur
+        };
+        if let Err(e) = history.create_snapshot(&pkg.name, &pkg.version, &source) {
+            eprintln!("[reap] Could not snapshot {}: {}", pkg.name, e);
+        }
+    }
+    aur::uninstall(&packages);
 }
 
 pub fn handle_local_install(pkgs: &[String]) {
@@ -642,41 +1111,44 @@ pub fn handle_search(terms: &[String]) {
     }
 }
 
-pub fn handle_update() {
+pub async fn handle_update() {
     use owo_colors::OwoColorize;
     println!("{} Checking for package updates...", "🔍".bright_blue());
     
     let config = crate::config::ReapConfig::load();
-    let installed = crate::pacman::list_installed_aur();
-    let mut updates_available: Vec<(String, String, String)> = Vec::new();
+    let installed: Vec<(String, String)> = crate::pacman::list_foreign_versions()
+        .into_iter()
+        .filter(|(pkg, _)| {
+            if config.is_ignored(pkg) {
+                println!("{} Skipping ignored package: {}", "⏭️".yellow(), pkg.dimmed());
+                return false;
+            }
+            true
+        })
+        .collect();
     
     println!("{} Scanning {} AUR packages...", "📦".bright_cyan(), installed.len());
     
-    for pkg in installed {
-        if config.is_ignored(&pkg) {
-            println!("{} Skipping ignored package: {}", "⏭️".yellow(), pkg.dimmed());
-            continue;
-        }
-        
-        if let Ok(remote) = crate::aur::fetch_package_info(&pkg) {
-            let local_ver = crate::pacman::get_version(&pkg);
-            if let Some(local) = local_ver {
-                if local != remote.version {
-                    updates_available.push((pkg.clone(), local, remote.version));
-                }
+    let updates_available =
+        match crate::aur::check_updates_with(&crate::aur_rpc::client(), &installed).await {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                eprintln!("{} Update check failed: {}", "❌".red(), e);
+                return;
             }
-        }
-    }
+        };
     
     if updates_available.is_empty() {
         println!("{} All AUR packages are up to date!", "✅".bright_green());
     } else {
         println!("\n{} {} package(s) can be updated:", "📋".bright_yellow(), updates_available.len().to_string().bright_white());
-        for (pkg, local_ver, remote_ver) in &updates_available {
-            println!("  {} {} → {}", 
-                pkg.bright_white(), 
-                local_ver.red(), 
-                remote_ver.bright_green()
+        for update in &updates_available {
+            let flagged = if update.out_of_date.is_some() { " (flagged out-of-date)" } else { "" };
+            println!("  {} {} → {}{}", 
+                update.name.bright_white(), 
+                update.local.red(), 
+                update.remote.bright_green(),
+                flagged.yellow()
             );
         }
         println!("\n{} Run {} to upgrade all packages", 
@@ -690,39 +1162,64 @@ pub fn handle_sync_db() {
     use owo_colors::OwoColorize;
     println!("{} Synchronizing package databases...", "🔄".bright_blue());
     
-    let status = std::process::Command::new("sudo")
-        .arg("pacman")
-        .arg("-Sy")
-        .status();
-    
-    match status {
-        Ok(s) if s.success() => println!("{} Database sync completed", "✅".bright_green()),
-        Ok(_) => eprintln!("{} Failed to sync database", "❌".bright_red()),
-        Err(e) => eprintln!("{} Error syncing database: {}", "❌".bright_red(), e),
+    match pacman::run(["-Sy"]) {
+        Ok(()) => println!("{} Database sync completed", "✅".bright_green()),
+        Err(e) => eprintln!("{} Failed to sync database: {}", "❌".bright_red(), e),
     }
 }
 
-pub fn handle_upgrade_all() {
+/// Upgrade the whole system from one plan covering the sync repos, AUR, taps and Flatpak.
+///
+/// Backends follow the active profile's `backend_order`. Pinned packages and those ignored in
+/// reap.toml or the profile are held back.
+pub async fn handle_upgrade_all(opts: &InstallOptions) -> Vec<InstallOutcome> {
     use owo_colors::OwoColorize;
-    println!("{} Upgrading all packages...", "🚀".bright_blue());
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    if let Err(e) = rt.block_on(aur::upgrade_all()) {
-        eprintln!("{} Upgrade all failed: {}", "❌".bright_red(), e);
+    println!(
+        "{} Checking all backends for upgrades...",
+        "🔍".bright_blue()
+    );
+    let profile = ProfileManager::new()
+        .get_active_profile()
+        .unwrap_or_default();
+    let config = ReapConfig::load();
+    let candidates = upgrade::collect(&profile.backend_order, opts.devel).await;
+    let plan = UpgradePlan::new(candidates, &profile.backend_order, |upgrade| {
+        let name = &upgrade.name;
+        if utils::is_pinned(name) || profile.pinned_packages.contains(name) {
+            Some("pinned".to_string())
+        } else if config.is_ignored(name) || profile.ignored_packages.contains(name) {
+            Some("ignored".to_string())
+        } else {
+            None
+        }
+    });
+    plan.print();
+    if plan.is_empty() {
+        println!("{} Everything is up to date.", "✅".bright_green());
+        return Vec::new();
     }
+    if opts.dry_run {
+        println!("[reap] Dry run: nothing was upgraded.");
+        return plan
+            .upgrades
+            .iter()
+            .map(|u| {
+                InstallOutcome::skipped(&u.name, Some(u.source.source()), Duration::ZERO, "dry run")
+            })
+            .collect();
+    }
+    let prompt = format!("Upgrade {} packages?", plan.upgrades.len());
+    if !opts.noconfirm && !InteractiveManager::confirm_action(&prompt, true) {
+        return Vec::new();
+    }
+    upgrade::execute(&plan, opts).await
 }
 
 pub fn handle_clean() {
     println!("[reap] Cleaning package cache...");
-    let status = std::process::Command::new("sudo")
-        .arg("pacman")
-        .arg("-Sc")
-        .arg("--noconfirm")
-        .status();
-
-    match status {
-        Ok(s) if s.success() => println!("[reap] Cache cleaned successfully"),
-        Ok(_) => eprintln!("[reap] Failed to clean cache"),
-        Err(e) => eprintln!("[reap] Error cleaning cache: {}", e),
+    match pacman::run(["-Sc", "--noconfirm"]) {
+        Ok(()) => println!("[reap] Cache cleaned successfully"),
+        Err(e) => eprintln!("[reap] Failed to clean cache: {}", e),
     }
 }
 
@@ -734,187 +1231,292 @@ pub fn handle_doctor() {
     }
 }
 
-pub fn handle_upgrade(parallel: bool) {
+pub async fn handle_upgrade(parallel: bool, opts: &InstallOptions) -> Vec<InstallOutcome> {
     let config = crate::config::ReapConfig::load();
-    let installed = crate::pacman::list_installed_aur();
-    let mut to_upgrade: Vec<String> = Vec::new();
-    for pkg in installed {
-        if config.is_ignored(&pkg) {
-            println!("[reap] Skipping ignored package: {}", pkg);
-            continue;
-        }
-        if let Ok(remote) = crate::aur::fetch_package_info(&pkg) {
-            let local_ver = crate::pacman::get_version(&pkg);
-            if local_ver.as_deref() != Some(&remote.version) {
-                to_upgrade.push(pkg.to_string());
-            }
-        }
-    }
+    let to_upgrade = outdated_not_ignored(&config, opts.devel).await;
     if to_upgrade.is_empty() {
         println!("[reap] All AUR packages up to date.");
-        return;
+        return Vec::new();
     }
     println!("[reap] Upgrading: {:?}", to_upgrade);
     if parallel {
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(handle_install_parallel(to_upgrade, config.parallel));
-    } else {
-        for pkg in to_upgrade {
-            let _ = tokio::runtime::Runtime::new()
-                .unwrap()
-                .block_on(crate::aur::install(vec![pkg.as_str()]));
+        return handle_install_parallel(to_upgrade, opts).await;
+    }
+    // Still one plan and transaction, building one package base at a time unless the
+    // profile sets parallel_jobs
+    let opts = InstallOptions {
+        max_parallel: 1,
+        ..opts.clone()
+    };
+    handle_install_parallel(to_upgrade, &opts).await
+}
+
+/// Names of outdated AUR packages, skipping those on the ignore list
+///
+/// With `devel`, AUR VCS packages whose upstream commit moved are added as well.
+async fn outdated_not_ignored(config: &crate::config::ReapConfig, devel: bool) -> Vec<String> {
+    let mut outdated: Vec<String> = crate::aur::get_outdated()
+        .await
+        .into_iter()
+        .map(|update| update.name)
+        .collect();
+    if devel {
+        let installed = pacman::list_installed_aur();
+        let moved = tokio::task::spawn_blocking(move || crate::devel::check(&installed))
+            .await
+            .unwrap_or_default();
+        for (_, package) in moved
+            .into_iter()
+            .filter(|(_, p)| p.origin == BuildOrigin::Aur)
+        {
+            for name in package.pkgnames {
+                if !outdated.contains(&name) {
+                    outdated.push(name);
+                }
+            }
         }
     }
+    let mut to_upgrade = Vec::new();
+    for name in outdated {
+        if config.is_ignored(&name) {
+            println!("[reap] Skipping ignored package: {}", name);
+            continue;
+        }
+        to_upgrade.push(name);
+    }
+    to_upgrade
 }
 
+/// Reinstall an earlier version of `pkg` from a kept build or pacman's cache.
+///
+/// The install history is shown, then the versions with an archive are offered. The chosen
+/// one is installed, pinned and recorded as a rollback in the history.
 pub fn handle_rollback(pkg: &str) {
-    // Restore or remove utils::rollback and hooks::on_rollback
-    if let Some(rollback_fn) = std::option::Option::Some(utils::rollback) {
-        rollback_fn(pkg);
+    let mut history = HistoryManager::new();
+    if let Err(e) = history.show_history(pkg) {
+        println!("[reap] {}", e);
+    }
+    let installed = pacman::get_version(pkg);
+    let candidates = history.rollback_candidates(pkg, installed.as_deref());
+    if candidates.is_empty() {
+        eprintln!(
+            "[reap] No other version of {} is kept in {} or {}.",
+            pkg,
+            ArtifactStore::default_root().display(),
+            crate::local_repo::PACMAN_CACHE
+        );
+        return;
+    }
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|c| format!("{} ({})", c.version, c.path.display()))
+        .collect();
+    let prompt = format!(
+        "Roll {} back from {} to:",
+        pkg,
+        installed.as_deref().unwrap_or("not installed")
+    );
+    let Some(choice) = InteractiveManager::new().select_from_list(&items, &prompt) else {
+        return;
+    };
+    if let Err(e) = history.rollback_to_version(pkg, &candidates[choice].version) {
+        eprintln!("[reap] Rollback of {} failed: {}", pkg, e);
     }
 }
 
+/// List orphaned packages with the space removing them reclaims, and with `remove` uninstall
+/// them all in one `pacman -Rns` transaction.
+///
+/// Orphans come from the whole local dependency graph, so packages only other orphans need
+/// are included and the profile's `keep_packages` protect their dependencies too. Without
+/// `all` only AUR orphans are listed, along with the repo packages only they need.
 pub fn handle_orphan(remove: bool, all: bool) {
-    let output = std::process::Command::new("pacman")
-        .args(["-Qdtq"])
-        .output();
-    let mut aur_orphans = Vec::new();
-    let mut repo_orphans = Vec::new();
-    if let Ok(out) = output {
-        for pkg in String::from_utf8_lossy(&out.stdout).lines() {
-            let repo_check = std::process::Command::new("pacman")
-                .arg("-Si")
-                .arg(pkg)
-                .output();
-            let is_repo = repo_check
-                .as_ref()
-                .map(|o| o.status.success())
-                .unwrap_or(false);
-            if is_repo {
-                repo_orphans.push(pkg.to_string());
-            } else {
-                aur_orphans.push(pkg.to_string());
-            }
+    let db = PacmanDb::system();
+    let local = match db.local() {
+        Ok(local) => local,
+        Err(e) => {
+            eprintln!("[reap] Could not read the local package database: {}", e);
+            return;
         }
+    };
+    let mut keep = ProfileManager::new()
+        .get_active_profile()
+        .map(|p| p.keep_packages)
+        .unwrap_or_default();
+    if !all {
+        let orphans = local.orphans(&keep);
+        let aur: Vec<String> = orphans
+            .iter()
+            .filter(|pkg| db.sync_repo_of(&pkg.name).is_none())
+            .map(|pkg| pkg.name.clone())
+            .collect();
+        let needed = local.required_by(&aur);
+        keep.extend(
+            orphans
+                .iter()
+                .filter(|pkg| !needed.contains(&pkg.name))
+                .map(|pkg| pkg.name.clone()),
+        );
     }
-    if !aur_orphans.is_empty() {
-        println!("Orphaned AUR packages:\n");
-        for pkg in &aur_orphans {
-            println!("    {}", pkg);
-        }
-        if remove {
-            for pkg in &aur_orphans {
-                println!("[reap] Uninstalling orphaned AUR package: {}", pkg);
-                crate::aur::uninstall(pkg);
-            }
-        } else {
-            println!("\nRun with --remove to uninstall.");
-        }
-    } else {
-        println!("No orphaned AUR packages found.");
+    let plan = local.orphans(&keep);
+    if plan.is_empty() {
+        println!("No orphaned packages found.");
+        return;
     }
-    if all && !repo_orphans.is_empty() {
-        println!("\nOrphaned pacman packages:\n");
-        for pkg in &repo_orphans {
-            println!("    {}", pkg);
-        }
-        if remove {
-            for pkg in &repo_orphans {
-                println!("[reap] Uninstalling orphaned pacman package: {}", pkg);
-                crate::aur::uninstall(pkg);
-            }
+    println!("Orphaned packages:\n");
+    for pkg in &plan {
+        let source = if db.sync_repo_of(&pkg.name).is_some() {
+            "repo"
         } else {
-            println!("\nRun with --remove to uninstall.");
-        }
+            "aur"
+        };
+        println!(
+            "    {} {} [{}] {}",
+            pkg.name,
+            pkg.version,
+            source,
+            utils::format_size(pkg.installed_size)
+        );
+    }
+    let reclaimed = utils::format_size(plan.iter().map(|pkg| pkg.installed_size).sum());
+    println!("\n{} packages, {} reclaimed", plan.len(), reclaimed);
+    if !remove {
+        println!("\nRun with --remove to uninstall.");
+        return;
+    }
+    let args = ["-Rns"]
+        .into_iter()
+        .chain(plan.iter().map(|pkg| pkg.name.as_str()));
+    match pacman::run(args) {
+        Ok(()) => println!(
+            "[reap] Removed {} orphaned packages, reclaiming {}",
+            plan.len(),
+            reclaimed
+        ),
+        Err(e) => eprintln!("[reap] Could not remove orphaned packages: {}", e),
     }
 }
 
-pub fn show_pkgbuild_diff(pkg: &str) {
-    let local_path = std::env::temp_dir().join(format!("reap-aur-{}/PKGBUILD", pkg));
-    let local = std::fs::read_to_string(&local_path).unwrap_or_default();
-    let remote = crate::aur::get_pkgbuild_preview(pkg);
-    let diff = diff::lines(&local, &remote);
-    for d in diff {
-        match d {
-            diff::Result::Left(l) => println!("\x1b[31m- {}\x1b[0m", l),
-            diff::Result::Right(r) => println!("\x1b[32m+ {}\x1b[0m", r),
-            diff::Result::Both(l, _) => println!("  {}", l),
+/// Review gate run before building `pkgbase` from its cached clone.
+///
+/// Shows the diff of every file since the last reviewed commit and asks for acceptance,
+/// unless `--noconfirm` or the profile's `skip_review` accepts it (`--diff` still shows the
+/// diff then). Accepting at the prompt records HEAD as reviewed, so an unchanged package is
+/// not prompted again; an automatic accept records nothing, so the next interactive run
+/// still shows every change nobody looked at.
+pub fn review_changes(
+    clones: &CloneCache,
+    pkgbase: &str,
+    opts: &InstallOptions,
+) -> Result<(), ReapError> {
+    use owo_colors::OwoColorize;
+    if !clones.needs_review(pkgbase) {
+        println!("[reap] {} unchanged since last review.", pkgbase);
+        return Ok(());
+    }
+    let skip_review = ProfileManager::new()
+        .get_active_profile()
+        .map(|p| p.skip_review.unwrap_or(false))
+        .unwrap_or(false);
+
+    let auto_accept = opts.noconfirm || skip_review;
+    if auto_accept && !opts.diff {
+        println!("[reap] Accepting changes to {} without review.", pkgbase);
+        return Ok(());
+    }
+    let head = clones.head(pkgbase)?;
+    let diff = clones.diff_since_reviewed(pkgbase)?;
+    match clones.reviewed(pkgbase) {
+        Some(reviewed) => println!(
+            "{} Changes to {} since reviewed commit {}:",
+            "🔍".bright_cyan(),
+            pkgbase.bright_white(),
+            &reviewed[..reviewed.len().min(12)]
+        ),
+        None => println!(
+            "{} {} has not been reviewed before, showing all files:",
+            "🔍".bright_cyan(),
+            pkgbase.bright_white()
+        ),
+    }
+    for line in diff.lines() {
+        if line.starts_with('+') && !line.starts_with("+++") {
+            println!("{}", line.green());
+        } else if line.starts_with('-') && !line.starts_with("---") {
+            println!("{}", line.red());
+        } else {
+            println!("{}", line);
         }
     }
+    if auto_accept {
+        return Ok(());
+    }
+    let prompt = format!("Accept these changes and build {}?", pkgbase);
+    if !crate::interactive::InteractiveManager::confirm_action(&prompt, false) {
+        return Err(ReapError::ReviewDeclined(pkgbase.to_string()));
+    }
+    clones.mark_reviewed(pkgbase, &head)?;
+    Ok(())
 }
 
-pub async fn install_aur_native(
-    pkg: &str,
+/// Build and install a single AUR package with its dependencies, as a solved build plan
+///
+/// # Errors
+///
+/// Returns an error if the plan cannot be solved or any of its builds is not installed.
+pub async fn install_aur_native(pkg: &str, opts: &InstallOptions) -> Result<(), ReapError> {
+    aur::install(vec![pkg], opts)
+        .await
+        .map_err(|e| ReapError::CommandFailed(e.to_string()))
+}
+
+/// Update the clone of an AUR package base and run the review and edit steps.
+///
+/// These steps may prompt, so they run one package at a time before any building. Returns
+/// the clone directory for [`build_prepared`].
+///
+/// # Errors
+///
+/// Returns an error if the clone cannot be updated or the review is declined.
+pub fn prepare_aur_pkgbase(
+    pkgbase: &str,
     log: &LogPane,
     opts: &InstallOptions,
-) -> Result<(), ReapError> {
-    use chrono::Local;
+) -> Result<PathBuf, ReapError> {
     use std::env;
-    use std::fs;
-    use std::process::{Command, Stdio};
-    let now = Local::now().format("%Y-%m-%d %H:%M:%S");
-    let cache_dir = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("/tmp"));
-    let build_dir = cache_dir.join(format!("reap-aur-{}-{}", pkg, now));
-    let repo_url = format!("https://aur.archlinux.org/{}.git", pkg);
-    let log_line = |step: &str, msg: &str| {
-        use owo_colors::OwoColorize;
-        let entry = format!("[{}][reap][aur][{}] {}", now, step, msg);
-        log.push(&entry);
-        // Also print colorized output to console
-        match step {
-            "fetch" => println!("{} {}", "📥".bright_blue(), msg.bright_white()),
-            "build" => println!("{} {}", "🔨".bright_yellow(), msg.bright_white()),
-            "install" => println!("{} {}", "📦".bright_green(), msg.bright_white()),
-            "deps" => println!("{} {}", "🔗".bright_cyan(), msg.bright_white()),
-            "error" => println!("{} {}", "❌".bright_red(), msg.bright_red()),
-            "success" => println!("{} {}", "✅".bright_green(), msg.bright_green()),
-            _ => println!("{} {}", "ℹ️".bright_blue(), msg.bright_white()),
-        }
-    };
+    use std::process::Command;
+    let log_line = |step: &str, msg: &str| aur_log(log, step, msg);
+    let repo_url = crate::aur_rpc::client().clone_url(pkgbase);
     // --- Fetch PKGBUILD ---
-    log_line("fetch", &format!("Fetching PKGBUILD for {}", pkg));
-    let mut clone_cmd = Command::new("git");
-    clone_cmd
-        .arg("clone")
-        .arg(&repo_url)
-        .arg(&build_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-    match clone_cmd.spawn().and_then(|mut child| {
-        let stdout = child.stdout.take().unwrap();
-        let stderr = child.stderr.take().unwrap();
-        let mut reader = std::io::BufReader::new(stdout);
-        let mut err_reader = std::io::BufReader::new(stderr);
-        let mut buf = String::new();
-        let mut err_buf = String::new();
-        while reader.read_line(&mut buf).unwrap_or(0) > 0 {
-            log_line("clone", buf.trim_end());
-            buf.clear();
-        }
-        while err_reader.read_line(&mut err_buf).unwrap_or(0) > 0 {
-            log_line("clone", err_buf.trim_end());
-            err_buf.clear();
-        }
-        child.wait()
-    }) {
-        Ok(status) if status.success() => {}
-        Ok(_) => {
-            log_line("clone", &format!("❌ Failed to clone repo for {}", pkg));
-            return Err(ReapError::CommandFailed("git clone failed".to_string()));
-        }
+    log_line("fetch", &format!("Fetching PKGBUILD for {}", pkgbase));
+    let clones = CloneCache::default();
+    let build_dir = match clones.sync(pkgbase, &repo_url) {
+        Ok(dir) => dir,
         Err(e) => {
             log_line(
-                "clone",
-                &format!("❌ Failed to run git clone for {}: {}", pkg, e),
+                "error",
+                &format!("Failed to update clone of {}: {}", pkgbase, e),
             );
-            return Err(ReapError::Io(e));
+            return Err(e.into());
+        }
+    };
+    if let Some(commits) = clones.commits_since_reviewed(pkgbase) {
+        log_line(
+            "fetch",
+            &format!("{} new commit(s) since last review", commits.len()),
+        );
+        for commit in commits {
+            log_line("fetch", &commit);
         }
     }
     let pkgb_path = build_dir.join("PKGBUILD");
-    // --- Diff ---
+    // --- Review ---
+    if let Err(e) = review_changes(&clones, pkgbase, opts) {
+        log_line("error", &e.to_string());
+        return Err(e);
+    }
     // --- Edit ---
-    if opts.insecure {
+    if opts.edit {
         log_line("edit", "Editing PKGBUILD");
         let editor = env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
         let status = Command::new(editor).arg(&pkgb_path).status();
@@ -924,21 +1526,113 @@ pub async fn install_aur_native(
             Err(e) => log_line("edit", &format!("Failed to launch editor: {}", e)),
         }
     }
-    // --- Dry Run ---
-    if opts.insecure {
-        log_line("dry-run", &format!("Would build and install: {}", pkg));
-        let _ = fs::remove_dir_all(&build_dir);
-        log_line("cleanup", &format!("Cleaned up {}", build_dir.display()));
-        return Ok(());
+    Ok(build_dir)
+}
+
+/// Build a package base prepared by [`prepare_aur_pkgbase`], on the host or in the chroot.
+///
+/// The built packages are published to the local repository and VCS sources are recorded
+/// for `--devel`. The chroot is expected to be set up already by [`ensure_chroot`].
+///
+/// # Errors
+///
+/// Returns an error if the build fails or does not produce every requested package.
+pub fn build_prepared(
+    pkgbase: &str,
+    build_dir: &Path,
+    pkgnames: &[String],
+    deps: &[PathBuf],
+    log: &LogPane,
+    opts: &InstallOptions,
+) -> Result<Vec<PathBuf>, ReapError> {
+    let packages = build_archives(pkgbase, build_dir, pkgnames, deps, log, opts)?;
+    let store = ArtifactStore::default();
+    publish_built(&package_list(build_dir, store.ensure()?)?, log);
+    // VCS packages are tracked by upstream commit for `--devel`
+    let srcinfo = fs::read_to_string(build_dir.join(".SRCINFO"))
+        .ok()
+        .and_then(|content| SrcInfo::parse(&content).ok());
+    if let Some(srcinfo) = srcinfo {
+        crate::devel::record_build(pkgbase, pkgnames, BuildOrigin::Aur, &srcinfo);
     }
+    Ok(packages)
+}
+
+/// Run makepkg for a checked-out package base and return the archives of `pkgnames`.
+///
+/// Nothing is published or recorded, so a build of an older commit leaves the local
+/// repository and the `--devel` record at the current version.
+///
+/// # Errors
+///
+/// Returns an error if the build fails or does not produce every requested package.
+fn build_archives(
+    pkgbase: &str,
+    build_dir: &Path,
+    pkgnames: &[String],
+    deps: &[PathBuf],
+    log: &LogPane,
+    opts: &InstallOptions,
+) -> Result<Vec<PathBuf>, ReapError> {
+    let log_line = |step: &str, msg: &str| aur_log(log, step, msg);
     // --- Build ---
-    log_line("build", &format!("Running makepkg for {}", pkg));
+    let store = ArtifactStore::default();
+    let pkgdest = store.ensure()?;
+    if chroot_enabled(opts) {
+        log_line("build", &format!("Building {} in a clean chroot", pkgbase));
+        let chroot = Chroot::default();
+        let deps = chroot_deps(build_dir, deps);
+        if let Err(e) = chroot.build(build_dir, pkgdest, &deps) {
+            log_line(
+                "build",
+                &format!("❌ Chroot build failed for {}: {}", pkgbase, e),
+            );
+            return Err(e.into());
+        }
+    } else {
+        run_makepkg(pkgbase, build_dir, pkgdest, log)?;
+    }
+    // Only the requested members of a split package are installed
+    let packages = built_packages(build_dir, pkgdest, pkgnames)?;
+    if packages.len() != pkgnames.len() {
+        log_line(
+            "error",
+            &format!("makepkg did not produce all of: {}", pkgnames.join(" ")),
+        );
+        return Err(ReapError::CommandFailed(format!(
+            "{} does not build {}",
+            pkgbase,
+            pkgnames.join(" ")
+        )));
+    }
+    log_line("build", &format!("Built {}", pkgnames.join(" ")));
+    Ok(packages)
+}
+
+/// Run `makepkg -s` for a package base on the host, writing the archives to `pkgdest`.
+///
+/// makepkg never prompts, since builds may run in parallel; its output goes to the log.
+///
+/// # Errors
+///
+/// Returns an error if makepkg cannot be run or fails.
+pub fn run_makepkg(
+    pkgbase: &str,
+    build_dir: &Path,
+    pkgdest: &Path,
+    log: &LogPane,
+) -> Result<(), ReapError> {
+    use std::process::{Command, Stdio};
+    let log_line = |step: &str, msg: &str| aur_log(log, step, msg);
+    log_line("build", &format!("Running makepkg for {}", pkgbase));
     let mut makepkg_cmd = Command::new("makepkg");
+    // -f: a kept archive of the same version is rebuilt rather than treated as an error
     makepkg_cmd
-        .arg("-si")
+        .arg("-s")
+        .arg("-f")
         .arg("--noconfirm")
-        .arg("--needed")
-        .current_dir(&build_dir)
+        .env("PKGDEST", pkgdest)
+        .current_dir(build_dir)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
     match makepkg_cmd.spawn().and_then(|mut child| {
@@ -958,48 +1652,709 @@ pub async fn install_aur_native(
         }
         child.wait()
     }) {
-        Ok(status) if status.success() => {
-            log_line("install", &format!("✅ {} installed successfully!", pkg));
-        }
+        Ok(status) if status.success() => Ok(()),
         Ok(_) => {
-            log_line("install", &format!("❌ makepkg failed for {}", pkg));
-            return Err(ReapError::CommandFailed("makepkg failed".to_string()));
+            log_line("build", &format!("❌ makepkg failed for {}", pkgbase));
+            Err(ReapError::CommandFailed("makepkg failed".to_string()))
         }
         Err(e) => {
             log_line(
-                "install",
-                &format!("❌ Failed to run makepkg for {}: {}", pkg, e),
+                "build",
+                &format!("❌ Failed to run makepkg for {}: {}", pkgbase, e),
             );
-            return Err(ReapError::Io(e));
+            Err(ReapError::Io(e))
         }
     }
-    let _ = fs::remove_dir_all(&build_dir);
-    log_line("cleanup", &format!("Cleaned up {}", build_dir.display()));
+}
+
+/// Log one step of an AUR build to the pane and, colorized, to the console
+fn aur_log(log: &LogPane, step: &str, msg: &str) {
+    use owo_colors::OwoColorize;
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S");
+    let entry = format!("[{}][reap][aur][{}] {}", now, step, msg);
+    log.push(&entry);
+    // Also print colorized output to console
+    match step {
+        "fetch" => println!("{} {}", "📥".bright_blue(), msg.bright_white()),
+        "build" => println!("{} {}", "🔨".bright_yellow(), msg.bright_white()),
+        "install" => println!("{} {}", "📦".bright_green(), msg.bright_white()),
+        "deps" => println!("{} {}", "🔗".bright_cyan(), msg.bright_white()),
+        "error" => println!("{} {}", "❌".bright_red(), msg.bright_red()),
+        "success" => println!("{} {}", "✅".bright_green(), msg.bright_green()),
+        _ => println!("{} {}", "ℹ️".bright_blue(), msg.bright_white()),
+    }
+}
+
+/// Whether to build in a clean chroot: `--chroot` or the active profile's `chroot_build`
+pub fn chroot_enabled(opts: &InstallOptions) -> bool {
+    opts.chroot
+        || ProfileManager::new()
+            .get_active_profile()
+            .map(|p| p.chroot_build.unwrap_or(false))
+            .unwrap_or(false)
+}
+
+/// Create or refresh the build chroot if chroot builds are enabled.
+///
+/// Refreshing runs a full `pacman -Syu` inside the chroot, so it happens once per
+/// transaction before anything is built rather than once per package base.
+///
+/// # Errors
+///
+/// Returns an error if the chroot cannot be created or updated.
+pub fn ensure_chroot(opts: &InstallOptions) -> Result<(), ReapError> {
+    if chroot_enabled(opts) && !opts.dry_run {
+        Chroot::default().ensure()?;
+    }
     Ok(())
 }
 
+/// Whether to remove make-only dependencies unasked: `--remove-make` or the active profile's
+/// `remove_make_deps`
+pub fn remove_make_enabled(opts: &InstallOptions) -> bool {
+    opts.remove_make
+        || ProfileManager::new()
+            .get_active_profile()
+            .map(|p| p.remove_make_deps.unwrap_or(false))
+            .unwrap_or(false)
+}
+
+/// Offer to remove the recorded make-only dependencies after a successful transaction.
+///
+/// Only packages still installed as dependencies and not required by anything else are
+/// offered; the rest are dropped from the record.
+pub fn remove_make_deps(opts: &InstallOptions) {
+    let record = MakeDeps::default();
+    let recorded = record.all();
+    if recorded.is_empty() {
+        return;
+    }
+    let Ok(local) = PacmanDb::system().local() else {
+        return;
+    };
+    let removable: Vec<String> = recorded
+        .iter()
+        .filter_map(|name| local.get(name))
+        .filter(|pkg| pkg.reason == InstallReason::Dependency)
+        .filter(|pkg| {
+            !local.iter().any(|other| {
+                !recorded.contains(&other.name)
+                    && other
+                        .depends
+                        .iter()
+                        .any(|dep| pkg.satisfies(&Dependency::parse(dep)))
+            })
+        })
+        .map(|pkg| pkg.name.clone())
+        .collect();
+    let kept: Vec<String> = recorded
+        .iter()
+        .filter(|name| !removable.contains(name))
+        .cloned()
+        .collect();
+    if let Err(e) = record.forget(&kept) {
+        eprintln!("[reap] Could not update {}: {}", record.path().display(), e);
+    }
+    if removable.is_empty() {
+        return;
+    }
+    let remove = remove_make_enabled(opts)
+        || (!opts.noconfirm
+            && InteractiveManager::confirm_action(
+                &format!(
+                    "Remove {} make-only dependencies ({})?",
+                    removable.len(),
+                    removable.join(" ")
+                ),
+                true,
+            ));
+    if !remove {
+        println!(
+            "[reap] Keeping make-only dependencies: {}",
+            removable.join(" ")
+        );
+        return;
+    }
+    let args = ["-Rns"]
+        .into_iter()
+        .chain(removable.iter().map(String::as_str));
+    match pacman::run(args) {
+        Ok(()) => {
+            println!(
+                "[reap] Removed make-only dependencies: {}",
+                removable.join(" ")
+            );
+            if let Err(e) = record.forget(&removable) {
+                eprintln!("[reap] Could not update {}: {}", record.path().display(), e);
+            }
+        }
+        Err(e) => eprintln!("[reap] Could not remove make-only dependencies: {}", e),
+    }
+}
+
+/// Concurrent builds: the active profile's `parallel_jobs`, else the configured `parallel`
+pub fn build_jobs(opts: &InstallOptions) -> usize {
+    ProfileManager::new()
+        .get_active_profile()
+        .ok()
+        .and_then(|p| p.parallel_jobs)
+        .unwrap_or(opts.max_parallel)
+        .max(1)
+}
+
+/// Archives to install into the chroot before building: `deps` plus the local repo's
+/// builds of any dependency the package's .SRCINFO names
+pub fn chroot_deps(build_dir: &Path, deps: &[PathBuf]) -> Vec<PathBuf> {
+    let mut archives = deps.to_vec();
+    let srcinfo = fs::read_to_string(build_dir.join(".SRCINFO"))
+        .ok()
+        .and_then(|content| SrcInfo::parse(&content).ok());
+    let repo = LocalRepo::default();
+    for dep in srcinfo.map(|s| s.build_depends()).unwrap_or_default() {
+        let name = Dependency::parse(&dep).name;
+        if let Some(build) = repo.newest(&name).filter(|b| !archives.contains(&b.path)) {
+            archives.push(build.path);
+        }
+    }
+    archives
+}
+
+/// Add freshly built archives to the local repo; a failure only warns
+pub fn publish_built(packages: &[PathBuf], log: &LogPane) {
+    let repo = LocalRepo::default();
+    match repo.add(packages) {
+        Ok(()) => log.push(&format!(
+            "[reap][repo] Added {} archive(s) to {}",
+            packages.len(),
+            repo.db_path().display()
+        )),
+        Err(e) => {
+            log.push(&format!("[reap][repo] Could not update local repo: {}", e));
+            eprintln!("[reap] Warning: could not update local repo: {}", e);
+        }
+    }
+}
+
+/// `reap repo list|prune|serve`
+///
+/// # Errors
+///
+/// Returns an error if pruning or serving fails.
+pub fn handle_repo(cmd: &RepoCmd) -> Result<(), LocalRepoError> {
+    use owo_colors::OwoColorize;
+    let repo = LocalRepo::default();
+    match cmd {
+        RepoCmd::List => {
+            let packages = repo.packages();
+            if packages.is_empty() {
+                println!(
+                    "[reap] The local repo at {} is empty.",
+                    repo.root().display()
+                );
+                return Ok(());
+            }
+            for package in &packages {
+                println!(
+                    "{} {} {}",
+                    package.name.bright_white(),
+                    package.version.bright_green(),
+                    package.arch.dimmed()
+                );
+            }
+            let conf = std::fs::read_to_string("/etc/pacman.conf").unwrap_or_default();
+            if !repo.is_configured(&conf) {
+                println!("\n[reap] To install from it with pacman, add to /etc/pacman.conf:");
+                let server = format!("file://{}", repo.root().display());
+                println!("{}", repo.pacman_conf_snippet(&server));
+            }
+        }
+        RepoCmd::Prune { keep } => {
+            let removed = repo.prune(*keep)?;
+            for package in &removed {
+                println!("[reap] Removed {} {}", package.name, package.version);
+            }
+            println!("[reap] Pruned {} old build(s).", removed.len());
+        }
+        RepoCmd::Serve { bind } => repo.serve(bind)?,
+    }
+    Ok(())
+}
+
+/// Install built archives with a single `pacman -U`; the archives themselves are kept.
+///
+/// # Errors
+///
+/// Returns an error if pacman cannot be run or fails.
+pub fn install_built(packages: &[PathBuf], log: &LogPane) -> Result<(), ReapError> {
+    use owo_colors::OwoColorize;
+    if packages.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<String> = packages
+        .iter()
+        .filter_map(|p| aur::built_package_name(p))
+        .collect();
+    log.push(&format!("[reap][install] pacman -U {}", names.join(" ")));
+    println!(
+        "{} Installing {}",
+        "📦".bright_green(),
+        names.join(" ").bright_white()
+    );
+    let mut args: Vec<&OsStr> = ["-U", "--noconfirm", "--needed"].map(OsStr::new).to_vec();
+    args.extend(packages.iter().map(|p| p.as_os_str()));
+    if let Err(e) = pacman::run(args) {
+        log.push(&format!(
+            "[reap][install] pacman -U failed for {}",
+            names.join(" ")
+        ));
+        println!("{} pacman -U failed", "❌".bright_red());
+        return Err(e);
+    }
+    println!(
+        "{} {} installed successfully!",
+        "✅".bright_green(),
+        names.join(" ").bright_green()
+    );
+    Ok(())
+}
+
+/// Every archive `makepkg --packagelist` reports for `build_dir` that exists in `pkgdest`
+///
+/// # Errors
+///
+/// Returns an error if makepkg fails.
+pub fn package_list(build_dir: &Path, pkgdest: &Path) -> Result<Vec<PathBuf>, ReapError> {
+    let output = Command::new("makepkg")
+        .arg("--packagelist")
+        .env("PKGDEST", pkgdest)
+        .current_dir(build_dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(ReapError::CommandFailed(
+            "makepkg --packagelist failed".to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(PathBuf::from)
+        .filter(|path| path.exists())
+        .collect())
+}
+
+/// Archives built in `build_dir` that belong to the given split package members
+///
+/// # Errors
+///
+/// Returns an error if makepkg fails.
+pub fn built_packages(
+    build_dir: &Path,
+    pkgdest: &Path,
+    pkgnames: &[String],
+) -> Result<Vec<PathBuf>, ReapError> {
+    Ok(package_list(build_dir, pkgdest)?
+        .into_iter()
+        .filter(|path| aur::built_package_name(path).is_some_and(|name| pkgnames.contains(&name)))
+        .collect())
+}
+
 /// Recursively resolve all missing dependencies for a list of packages (AUR + repo)
 /// Hybrid dependency resolver: tap > AUR > system
-pub async fn handle_cli(cli: &Cli) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// Where a `--downgrade` version was found
+enum DowngradeSource {
+    Archive(RepoPackage),
+    /// An AUR commit whose `.SRCINFO` has the requested version
+    Commit {
+        pkgbase: String,
+        commit: String,
+        version: String,
+    },
+}
+
+/// Install version `VER` of `PKG` for `--downgrade PKG=VER`.
+///
+/// pacman's cache is searched first, then the archives kept from reap's builds and finally
+/// the AUR git history, whose matching commit is built. The downgrade is recorded in the
+/// history and the package can then be added to the ignored list so upgrades leave it.
+pub async fn handle_downgrade(spec: &str, opts: &InstallOptions) -> InstallOutcome {
+    let start = Instant::now();
+    let Some((pkg, version)) = spec
+        .split_once('=')
+        .filter(|(p, v)| !p.is_empty() && !v.is_empty())
+    else {
+        eprintln!("[reap] --downgrade expects PKG=VER, got '{}'", spec);
+        return InstallOutcome::failed(spec, None, start.elapsed(), "expected PKG=VER");
+    };
+    let current = pacman::get_version(pkg);
+    let log = LogPane::default();
+    let found = match locate_downgrade(pkg, version).await {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("[reap] Cannot downgrade {}: {}", pkg, e);
+            return InstallOutcome::failed(pkg, None, start.elapsed(), e);
+        }
+    };
+    let source = match &found {
+        DowngradeSource::Archive(archive) if archive.path.starts_with(PACMAN_CACHE) => {
+            Source::Pacman
+        }
+        _ => Source::Aur,
+    };
+    if opts.dry_run {
+        match &found {
+            DowngradeSource::Archive(archive) => {
+                println!("[reap] Would install {}", archive.path.display())
+            }
+            DowngradeSource::Commit {
+                pkgbase, commit, ..
+            } => println!("[reap] Would build {} at {}", pkgbase, commit),
+        }
+        return InstallOutcome::from_result(
+            pkg,
+            source,
+            start.elapsed(),
+            true,
+            Ok::<(), String>(()),
+        );
+    }
+
+    let result = match found {
+        DowngradeSource::Archive(archive) => Ok(archive),
+        DowngradeSource::Commit {
+            pkgbase,
+            commit,
+            version,
+        } => build_aur_commit(&pkgbase, &commit, &version, pkg, &log, opts),
+    }
+    .and_then(|archive| {
+        install_built(std::slice::from_ref(&archive.path), &log)?;
+        Ok(archive)
+    });
+    let archive = match result {
+        Ok(archive) => archive,
+        Err(e) => {
+            eprintln!("[reap] Downgrade of {} failed: {}", pkg, e);
+            return InstallOutcome::failed(pkg, Some(source), start.elapsed(), e);
+        }
+    };
+    println!(
+        "[reap] Downgraded {} from {} to {}.",
+        pkg,
+        current.as_deref().unwrap_or("nothing"),
+        archive.version
+    );
+    if let Err(e) = HistoryManager::new().record_rollback(pkg, &archive, current) {
+        eprintln!("[reap] Could not record the downgrade of {}: {}", pkg, e);
+    }
+    offer_ignore(pkg, opts);
+    InstallOutcome::installed(pkg, source, start.elapsed())
+}
+
+/// Find `version` of `pkg` in pacman's cache, the kept archives or the AUR git history
+async fn locate_downgrade(pkg: &str, version: &str) -> Result<DowngradeSource, ReapError> {
+    let dirs = [PathBuf::from(PACMAN_CACHE), ArtifactStore::default_root()];
+    let cached = find_archives(pkg, &dirs)
+        .into_iter()
+        .find(|a| vercmp(&a.version, version).is_eq());
+    if let Some(archive) = cached {
+        println!(
+            "[reap] Found {} {} in {}",
+            pkg,
+            archive.version,
+            archive.path.display()
+        );
+        return Ok(DowngradeSource::Archive(archive));
+    }
+
+    println!(
+        "[reap] Searching the AUR history of {} for {}...",
+        pkg, version
+    );
+    let client = crate::aur_rpc::client();
+    let pkgbase = aur::group_by_pkgbase(&client, &[pkg])
+        .await
+        .map_err(|e| ReapError::CommandFailed(format!("AUR lookup failed: {}", e)))?
+        .into_iter()
+        .next()
+        .map(|(pkgbase, _)| pkgbase)
+        .unwrap_or_else(|| pkg.to_string());
+    let clones = CloneCache::default();
+    clones.sync(&pkgbase, &client.clone_url(&pkgbase))?;
+    let (commit, version) = clones.find_version(&pkgbase, version).ok_or_else(|| {
+        ReapError::CommandFailed(format!(
+            "no cached archive and no AUR commit of {} has version {}",
+            pkgbase, version
+        ))
+    })?;
+    Ok(DowngradeSource::Commit {
+        pkgbase,
+        commit,
+        version,
+    })
+}
+
+/// Build `pkg` from its AUR clone at `commit`, then return the clone to where it was
+fn build_aur_commit(
+    pkgbase: &str,
+    commit: &str,
+    version: &str,
+    pkg: &str,
+    log: &LogPane,
+    opts: &InstallOptions,
+) -> Result<RepoPackage, ReapError> {
+    let clones = CloneCache::default();
+    println!(
+        "[reap] {} {} was published in commit {}",
+        pkgbase, version, commit
+    );
+    if let Some(pkgbuild) = clones.file_at(pkgbase, commit, "PKGBUILD") {
+        println!("{}", pkgbuild);
+    }
+    let confirmed = opts.noconfirm
+        || InteractiveManager::confirm_action(
+            &format!("Build {} {} from this PKGBUILD?", pkgbase, version),
+            false,
+        );
+    if !confirmed {
+        return Err(ReapError::ReviewDeclined(pkgbase.to_string()));
+    }
+    ensure_chroot(opts)?;
+    let previous = clones.checkout(pkgbase, commit)?;
+    let built = build_archives(
+        pkgbase,
+        &clones.path(pkgbase),
+        &[pkg.to_string()],
+        &[],
+        log,
+        opts,
+    );
+    clones.checkout(pkgbase, &previous)?;
+    built?
+        .first()
+        .and_then(|path| RepoPackage::from_path(path))
+        .ok_or_else(|| ReapError::CommandFailed(format!("no archive built for {}", pkg)))
+}
+
+/// Offer to add a downgraded `pkg` to the ignored list
+fn offer_ignore(pkg: &str, opts: &InstallOptions) {
+    if ReapConfig::load().is_ignored(pkg) {
+        return;
+    }
+    if opts.noconfirm {
+        println!(
+            "[reap] {} will be upgraded again unless it is added to ignored_packages.",
+            pkg
+        );
+        return;
+    }
+    let prompt = format!("Add {} to the ignored packages so upgrades skip it?", pkg);
+    if InteractiveManager::confirm_action(&prompt, true) {
+        match crate::config::ignore_package(pkg) {
+            Ok(()) => println!("[reap] {} is now ignored during upgrades.", pkg),
+            Err(e) => eprintln!("[reap] Could not update the ignored packages: {}", e),
+        }
+    }
+}
+
+/// Journal a transaction of `steps` before it runs.
+///
+/// Every package a step will replace is snapshotted with the [`HistoryManager`] and its
+/// current version recorded, so `reap recover` can roll the completed steps back.
+///
+/// # Errors
+///
+/// Returns an error if an earlier transaction was interrupted or the journal cannot be
+/// written.
+pub fn begin_transaction(
+    command: &str,
+    targets: Vec<String>,
+    mut steps: Vec<JournalStep>,
+) -> Result<(Journal, Transaction), ReapError> {
+    let journal = Journal::default();
+    if let Some(open) = journal.pending()? {
+        return Err(JournalError::Interrupted(open.id).into());
+    }
+    let db = PacmanDb::system();
+    let mut history = HistoryManager::new();
+    for step in &mut steps {
+        let source = if step.label == "repo" {
+            Source::Pacman
+        } else {
+            Source::Aur
+        };
+        for pkg in &step.packages {
+            let previous = db.installed(pkg).map(|p| p.version);
+            let snapshot = previous
+                .as_ref()
+                .map(|version| history.create_snapshot(pkg, version, &source));
+            if let Some(Err(e)) = snapshot {
+                eprintln!("[reap] Could not snapshot {}: {}", pkg, e);
+            }
+            step.previous.insert(pkg.clone(), previous);
+        }
+    }
+    let tx = Transaction::new(command, targets, steps);
+    journal.begin(&tx)?;
+    Ok((journal, tx))
+}
+
+/// Record the status of the step labelled `label`, warning if the journal cannot be written
+pub fn mark_step(journal: &Journal, tx: &mut Transaction, label: &str, status: StepStatus) {
+    let Some(index) = tx.step(label) else {
+        return;
+    };
+    if let Err(e) = journal.mark(tx, index, status) {
+        eprintln!(
+            "[reap] Could not update {}: {}",
+            journal.path().display(),
+            e
+        );
+    }
+}
+
+/// Close the open transaction if every step finished.
+///
+/// A transaction with a failed or unfinished step stays in the journal so `reap recover`
+/// can resume it or roll the finished steps back.
+pub fn finish_transaction(journal: &Journal) {
+    match journal.pending() {
+        Ok(Some(tx)) if !tx.is_complete() => {
+            println!(
+                "[reap] Transaction {} did not complete; run `reap recover` to resume or roll it back.",
+                tx.id
+            );
+            return;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("[reap] Could not read {}: {}", journal.path().display(), e);
+            return;
+        }
+    }
+    if let Err(e) = journal.finish() {
+        eprintln!("[reap] Could not close {}: {}", journal.path().display(), e);
+    }
+}
+
+/// Show an interrupted transaction and resume it, roll it back or discard it.
+///
+/// Resuming installs the packages of every step that did not finish. Rolling back returns
+/// the packages of finished steps to their snapshotted versions and removes the ones the
+/// transaction newly installed.
+///
+/// # Errors
+///
+/// Returns an error if the journal cannot be read or removed, or the removal fails.
+pub async fn handle_recover(opts: &InstallOptions) -> Result<Vec<InstallOutcome>, ReapError> {
+    let journal = Journal::default();
+    let Some(tx) = journal.pending()? else {
+        println!("[reap] No interrupted transaction.");
+        return Ok(Vec::new());
+    };
+    tx.print();
+    let choices = [
+        "Resume the remaining steps".to_string(),
+        "Roll back the completed steps".to_string(),
+        "Discard the journal".to_string(),
+    ];
+    match InteractiveManager::new().select_from_list(&choices, "How should it be recovered?") {
+        Some(0) => {
+            // The targets are solved again, so finished steps are satisfied and dependencies
+            // are installed as such; older journals only have the unfinished packages
+            let targets = if tx.targets.is_empty() {
+                tx.remaining().flat_map(|s| s.packages.clone()).collect()
+            } else {
+                tx.targets.clone()
+            };
+            journal.finish()?;
+            Ok(handle_install_parallel(targets, opts).await)
+        }
+        Some(1) => {
+            rollback_transaction(&tx)?;
+            journal.finish()?;
+            Ok(Vec::new())
+        }
+        Some(2) => {
+            journal.finish()?;
+            println!("[reap] Discarded transaction {}.", tx.id);
+            Ok(Vec::new())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Undo the completed steps of `tx`, newest first
+fn rollback_transaction(tx: &Transaction) -> Result<(), ReapError> {
+    let mut history = HistoryManager::new();
+    let mut added = Vec::new();
+    for step in tx.completed().collect::<Vec<_>>().into_iter().rev() {
+        for (pkg, previous) in &step.previous {
+            match previous {
+                Some(version) => {
+                    if let Err(e) = history.restore_version(pkg, version) {
+                        eprintln!("[reap] Could not roll back {}: {}", pkg, e);
+                    }
+                }
+                None => added.push(pkg.clone()),
+            }
+        }
+    }
+    if !added.is_empty() {
+        println!("[reap] Removing newly installed: {}", added.join(" "));
+        pacman::run(std::iter::once("-R").chain(added.iter().map(String::as_str)))?;
+    }
+    Ok(())
+}
+
+pub async fn handle_cli(
+    cli: &Cli,
+) -> Result<Vec<InstallOutcome>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut outcomes = Vec::new();
     // Define or remove gpg_cmd if not needed
     match &cli.command {
         Commands::Install {
             pkg,
             repo,
             binary_only,
-            .. // Remove or ignore unused variable: diff
+            diff,
         } => {
-            let source = detect_source(pkg, repo.as_deref(), *binary_only).unwrap_or(Source::Aur);
-            let task = InstallTask::new(
-                pkg.to_string(),
-                source.clone(),
-            );
+            let source = detect_source(pkg, repo.as_deref(), *binary_only)
+                .await
+                .unwrap_or(Source::Aur);
+            let task = InstallTask::new(pkg.to_string(), source.clone());
             let log_pane = tui::LogPane::default();
             let backend = cli.backend.as_str();
             let try_pacman = backend == "pacman" || backend == "auto";
-            let mut tried_pacman = false;
-            let mut pacman_success = false;
-            if try_pacman {
+            let start = Instant::now();
+            let mut opts = InstallOptions::from_cli(cli);
+            // The review gate shows the full diff before building
+            opts.diff |= *diff;
+            let backup = (!opts.dry_run).then(|| backup_package_state(pkg));
+            if let Some(Err(e)) = backup {
+                eprintln!("[backup] Warning: Failed to backup package state: {}", e);
+            }
+            let mut pacman_outcome = None;
+            let local = match &task.source {
+                Source::BinaryRepo(repo) if repo == LOCAL_REPO_NAME => local_build(&task.pkg).await,
+                _ => None,
+            };
+            if let Some(build) = local {
+                println!(
+                    "[reap] Installing {} {} from the local repo...",
+                    task.pkg, build.version
+                );
+                let result = if opts.dry_run {
+                    Ok(())
+                } else {
+                    install_built(&[build.path], &log_pane)
+                };
+                pacman_outcome = Some(InstallOutcome::from_result(
+                    &task.pkg,
+                    task.source.clone(),
+                    start.elapsed(),
+                    opts.dry_run,
+                    result,
+                ));
+            } else if try_pacman {
                 // Try native pacman install first
                 let status = std::process::Command::new("pacman")
                     .arg("-Si")
@@ -1011,74 +2366,60 @@ pub async fn handle_cli(cli: &Cli) -> Result<(), Box<dyn std::error::Error + Sen
                             "[reap] Installing {} from system repo via pacman...",
                             task.pkg
                         );
-                        pacman::install(&task.pkg);
-                        pacman_success = true;
-                        tried_pacman = true;
+                        let result = pacman::install(&task.pkg);
+                        pacman_outcome = Some(InstallOutcome::from_result(
+                            &task.pkg,
+                            Source::Pacman,
+                            start.elapsed(),
+                            false,
+                            result,
+                        ));
                     }
                 }
             }
-            if !pacman_success && (backend == "aur" || backend == "auto") {
-                // Fallback to AUR install
-                println!("[reap] Installing {} from AUR...", task.pkg);
-                let opts = InstallOptions {
-                    insecure: false,
-                    gpg_keyserver: None,
-                    fast_mode: false,
-                    strict_signatures: false,
-                    max_parallel: 4,
-                };
-                install_aur_native(&task.pkg, &log_pane, &opts)
-                    .await
-                    .unwrap_or_else(|e| {
+            let outcome = match pacman_outcome {
+                Some(outcome) => outcome,
+                None if backend == "aur" || backend == "auto" => {
+                    // Fallback to AUR install
+                    println!("[reap] Installing {} from AUR...", task.pkg);
+                    let result = install_aur_native(&task.pkg, &opts).await;
+                    if let Err(e) = &result {
                         println!("[reap] Failed to install {}: {:?}", task.pkg, e);
-                    });
-            } else if !pacman_success && !tried_pacman {
-                eprintln!("[reap] Package '{}' not found in repos or AUR.", task.pkg);
-            }
-        }
-        Commands::Upgrade { parallel: _ } => {
-            let config = crate::config::ReapConfig::load();
-            let installed = crate::pacman::list_installed_aur();
-            let mut to_upgrade: Vec<String> = Vec::new();
-            for pkg in installed {
-                if config.is_ignored(&pkg) {
-                    println!("[reap] Skipping ignored package: {}", pkg);
-                    continue;
-                }
-                if let Ok(remote) = crate::aur::fetch_package_info(&pkg) {
-                    let local_ver = crate::pacman::get_version(&pkg);
-                    if local_ver.as_deref() != Some(&remote.version) {
-                        to_upgrade.push(pkg.to_string());
                     }
+                    InstallOutcome::from_result(
+                        &task.pkg,
+                        Source::Aur,
+                        start.elapsed(),
+                        opts.dry_run,
+                        result,
+                    )
+                }
+                None => {
+                    eprintln!("[reap] Package '{}' not found in repos or AUR.", task.pkg);
+                    InstallOutcome::failed(&task.pkg, None, start.elapsed(), "not found")
                 }
-            }
-            if to_upgrade.is_empty() {
-                println!("[reap] All AUR packages up to date.");
-                return Ok(());
-            }
-            println!("[reap] Upgrading: {:?}", to_upgrade);
-            let log_pane = tui::LogPane::default();
-            let opts = InstallOptions {
-                insecure: false,
-                gpg_keyserver: None,
-                fast_mode: false,
-                strict_signatures: false,
-                max_parallel: 4,
             };
-            for pkg in to_upgrade {
-                install_aur_native(&pkg, &log_pane, &opts)
-                    .await
-                    .unwrap_or_else(|e| {
-                        println!("[reap] Failed to upgrade {}: {:?}", pkg, e);
-                    });
-            }
+            outcomes.push(outcome);
         }
-        Commands::Orphan { remove, all } => handle_orphan(*remove, *all),
-        Commands::Remove { pkgs } => {
-            for pkg in pkgs {
-                aur::uninstall(pkg);
-            }
+        Commands::Upgrade { parallel } => {
+            outcomes.extend(handle_upgrade(*parallel, &InstallOptions::from_cli(cli)).await);
         }
+        Commands::Rollback { pkg } => handle_rollback(pkg),
+        Commands::Mark { pkgs, asdeps, .. } => {
+            let (reason, label) = if *asdeps {
+                (InstallReason::Dependency, "installed as dependencies")
+            } else {
+                (InstallReason::Explicit, "explicitly installed")
+            };
+            pacman::set_install_reason(pkgs, reason)?;
+            println!("[reap] Marked {} as {}.", pkgs.join(" "), label);
+        }
+        Commands::Recover => {
+            let opts = InstallOptions::from_cli(cli);
+            outcomes.extend(handle_recover(&opts).await?);
+        }
+        Commands::Orphan { remove, all } => handle_orphan(*remove, *all),
+        Commands::Remove { pkgs } => handle_removal(pkgs, &InstallOptions::from_cli(cli)),
         Commands::Local { pkgs } => {
             for pkg in pkgs {
                 aur::install_local(pkg);
@@ -1093,8 +2434,7 @@ pub async fn handle_cli(cli: &Cli) -> Result<(), Box<dyn std::error::Error + Sen
             }
         }
         Commands::UpgradeAll => {
-            upgrade_all().await?;
-            println!("[reap] Upgrade all succeeded");
+            outcomes.extend(handle_upgrade_all(&InstallOptions::from_cli(cli)).await);
         }
         Commands::FlatpakUpgrade => {
             // Removed gpg_cmd usage as it's not needed for flatpak upgrade
@@ -1145,16 +2485,11 @@ pub async fn handle_cli(cli: &Cli) -> Result<(), Box<dyn std::error::Error + Sen
             Ok(_) => println!("[reap] Config backup complete."),
             Err(e) => eprintln!("[reap] Config backup failed: {}", e),
         },
-        Commands::Doctor => {
-            let result = crate::utils::doctor_report();
-            match result {
-                Ok(report) => println!("[reap] Doctor report:\n{}", report),
-                Err(e) => eprintln!("[reap] Doctor error: {}", e),
-            }
-        }
+        Commands::Repo { cmd } => handle_repo(cmd)?,
+        Commands::Doctor => handle_doctor(),
         _ => return Err(anyhow!("Not yet implemented").into()),
     }
-    Ok(())
+    Ok(outcomes)
 }
 
 /// Enhanced install function with profile and trust integration
@@ -1176,7 +2511,7 @@ pub async fn install_with_priority_enhanced(
     log.push(&format!("[reap][profile] Using profile: {}", profile.name));
 
     // Compute trust score
-    let source = detect_source(pkg, None, false).unwrap_or(Source::Aur);
+    let source = detect_source(pkg, None, false).await.unwrap_or(Source::Aur);
     let trust_score = trust_engine.compute_trust_score(pkg, &source).await;
     let trust_badge = trust_engine.display_trust_badge(trust_score.overall_score);
 
@@ -1210,7 +2545,7 @@ pub async fn install_with_priority_enhanced(
 
     let global_config = GlobalConfig::load();
     if let Some((source, tap_name, prio, tap_obj)) =
-        resolve_package_source(pkg, None, &global_config)
+        resolve_package_source(pkg, None, &global_config).await
     {
         // Prepare hook context
         let ctx = HookContext {
@@ -1356,20 +2691,23 @@ pub async fn install_with_priority_enhanced(
             }
             Source::Pacman => {
                 log.push(&format!("[reap][pacman] Installing {} from repo", pkg));
-                pacman::install(pkg);
+                if let Err(e) = pacman::install(pkg) {
+                    log.push(&format!("[reap][pacman] Failed to install {}: {}", pkg, e));
+                    return;
+                }
                 log.push(&format!("[✓] Installed {} from Pacman", pkg));
             }
             Source::Aur => {
                 println!("{} Building {} from AUR source...", "🔨".bright_yellow(), pkg.bright_white());
                 log.push(&format!("[reap][aur] Installing {} from AUR", pkg));
-                let opts = InstallOptions {
-                    insecure: false,
-                    gpg_keyserver: None,
-                    fast_mode: false,
-                    strict_signatures: false,
-                    max_parallel: 4,
-                };
-                let _ = install_aur_native(pkg, &log, &opts).await;
+                if let Err(e) = aur::install(vec![pkg], opts).await {
+                    log.push(&format!("[reap][aur] Failed to install {}: {}", pkg, e));
+                    println!("{} Failed to install {}: {}", "❌".bright_red(), pkg, e);
+                    return;
+                }
+                if opts.dry_run {
+                    return;
+                }
                 println!("{} Successfully installed {} from AUR!", "✅".bright_green(), pkg.bright_white().bold());
                 log.push(&format!("[✓] Installed {} from AUR", pkg));
             }
@@ -0,0 +1,271 @@
+// Upstream commits of VCS packages, to upgrade -git packages whose source moved
+use crate::solver::BuildOrigin;
+use crate::srcinfo::SrcInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// A git source of a package base that follows a branch or the remote HEAD
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VcsSource {
+    pub url: String,
+    /// Branch named by `#branch=`, `None` for the remote HEAD
+    pub branch: Option<String>,
+}
+
+impl VcsSource {
+    /// Parse a .SRCINFO `source` entry such as `name::git+https://host/repo.git#branch=main`.
+    ///
+    /// Returns `None` for anything that is not git, and for git sources pinned to a tag or
+    /// commit, since those cannot move.
+    pub fn parse(source: &str) -> Option<Self> {
+        let location = source
+            .split_once("::")
+            .map_or(source, |(_, location)| location);
+        let (location, fragment) = match location.split_once('#') {
+            Some((location, fragment)) => (location, Some(fragment)),
+            None => (location, None),
+        };
+        let url = match location.strip_prefix("git+") {
+            Some(url) => url,
+            None if location.starts_with("git://") => location,
+            None => return None,
+        };
+        // `?signed` asks makepkg to verify the commit; it is not part of the URL
+        let url = url.split_once('?').map_or(url, |(url, _)| url);
+        let branch = match fragment.and_then(|f| f.split_once('=')) {
+            Some(("branch", branch)) => Some(branch.to_string()),
+            Some(_) => return None,
+            None => None,
+        };
+        Some(Self {
+            url: url.to_string(),
+            branch,
+        })
+    }
+
+    /// The ref `git ls-remote` is asked for
+    pub fn git_ref(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!("refs/heads/{}", branch),
+            None => "HEAD".to_string(),
+        }
+    }
+
+    /// Current upstream commit of the source, `None` if the remote cannot be reached
+    pub fn ls_remote(&self) -> Option<String> {
+        let output = Command::new("git")
+            .args(["ls-remote", &self.url, &self.git_ref()])
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .next()
+            .map(str::to_string)
+    }
+}
+
+/// The git sources of `srcinfo` that can move, for the host architecture
+pub fn vcs_sources(srcinfo: &SrcInfo) -> Vec<VcsSource> {
+    let mut sources: Vec<VcsSource> = Vec::new();
+    for source in srcinfo
+        .source
+        .host()
+        .iter()
+        .filter_map(|s| VcsSource::parse(s))
+    {
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    sources
+}
+
+/// A source and the commit it was at when the package was built
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedSource {
+    pub source: VcsSource,
+    pub commit: String,
+}
+
+/// A VCS package base as it was last built
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevelPackage {
+    pub pkgnames: Vec<String>,
+    pub origin: BuildOrigin,
+    pub sources: Vec<TrackedSource>,
+}
+
+/// Serializes read-modify-write cycles of builds running in parallel
+static RECORD: Mutex<()> = Mutex::new(());
+
+/// Record of the upstream commits VCS packages were built from.
+///
+/// The AUR pkgver of a `-git` package only changes when its maintainer bumps it, so
+/// `--devel` instead compares these commits with what `git ls-remote` reports now.
+#[derive(Debug, Clone)]
+pub struct DevelDb {
+    path: PathBuf,
+}
+
+impl DevelDb {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.local/share/reap/devel.json`
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("reap/devel.json")
+    }
+
+    #[allow(dead_code)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every package base by name; a missing or unreadable record is empty
+    pub fn load(&self) -> BTreeMap<String, DevelPackage> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record a build of `pkgbase`; members built earlier stay recorded
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be written.
+    pub fn record(&self, pkgbase: &str, package: DevelPackage) -> std::io::Result<()> {
+        let _guard = RECORD.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.load();
+        if package.sources.is_empty() {
+            // The package base no longer builds from a VCS source
+            entries.remove(pkgbase);
+        } else {
+            let mut package = package;
+            if let Some(previous) = entries.remove(pkgbase) {
+                for name in previous.pkgnames {
+                    if !package.pkgnames.contains(&name) {
+                        package.pkgnames.push(name);
+                    }
+                }
+            }
+            entries.insert(pkgbase.to_string(), package);
+        }
+        self.save(&entries)
+    }
+
+    /// Package bases with an installed member whose upstream moved since the build.
+    ///
+    /// `head` gives the current commit of a source; sources it returns `None` for are
+    /// treated as unchanged. Each returned package lists only its installed members and
+    /// carries the new commits.
+    pub fn moved<I, H>(&self, is_installed: I, head: H) -> Vec<(String, DevelPackage)>
+    where
+        I: Fn(&str) -> bool,
+        H: Fn(&VcsSource) -> Option<String> + Sync,
+    {
+        let packages: Vec<(String, DevelPackage)> = self
+            .load()
+            .into_iter()
+            .filter_map(|(pkgbase, mut package)| {
+                package.pkgnames.retain(|name| is_installed(name));
+                (!package.pkgnames.is_empty()).then_some((pkgbase, package))
+            })
+            .collect();
+        // Each check is a network round trip, so they all run at once
+        let heads: Vec<Vec<Option<String>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = packages
+                .iter()
+                .map(|(_, package)| {
+                    let head = &head;
+                    scope.spawn(move || package.sources.iter().map(|t| head(&t.source)).collect())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+        let mut moved = Vec::new();
+        for ((pkgbase, mut package), heads) in packages.into_iter().zip(heads) {
+            let mut changed = false;
+            for (tracked, head) in package.sources.iter_mut().zip(heads) {
+                if let Some(head) = head.filter(|head| *head != tracked.commit) {
+                    tracked.commit = head;
+                    changed = true;
+                }
+            }
+            if changed {
+                moved.push((pkgbase, package));
+            }
+        }
+        moved
+    }
+
+    fn save(&self, entries: &BTreeMap<String, DevelPackage>) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(entries).map_err(std::io::Error::other)?;
+        std::fs::write(&self.path, content)
+    }
+}
+
+impl Default for DevelDb {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
+
+/// Record the current upstream commits of a package base that was just built.
+///
+/// Package bases without a VCS source are dropped from the record. When a source cannot
+/// be reached the earlier record is kept. Failures are printed, since the build itself
+/// succeeded.
+pub fn record_build(pkgbase: &str, pkgnames: &[String], origin: BuildOrigin, srcinfo: &SrcInfo) {
+    let mut sources = Vec::new();
+    for source in vcs_sources(srcinfo) {
+        match source.ls_remote() {
+            Some(commit) => sources.push(TrackedSource { source, commit }),
+            None => {
+                eprintln!("[reap] Could not reach {} to record its commit", source.url);
+                return;
+            }
+        }
+    }
+    let package = DevelPackage {
+        pkgnames: pkgnames.to_vec(),
+        origin,
+        sources,
+    };
+    if let Err(e) = DevelDb::default().record(pkgbase, package) {
+        eprintln!("[reap] Could not record VCS sources of {}: {}", pkgbase, e);
+    }
+}
+
+/// Installed VCS packages whose upstream moved, checked with `git ls-remote`
+pub fn check(installed: &[String]) -> Vec<(String, DevelPackage)> {
+    println!("[reap] Checking upstream commits of VCS packages...");
+    DevelDb::default().moved(
+        |name| installed.iter().any(|i| i == name),
+        |source| {
+            let head = source.ls_remote();
+            if head.is_none() {
+                eprintln!("[reap] Could not reach {}", source.url);
+            }
+            head
+        },
+    )
+}
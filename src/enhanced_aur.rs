@@ -1,3 +1,5 @@
+use crate::srcinfo::SrcInfo;
+use crate::version::{Dependency, Version};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -57,19 +59,17 @@ impl EnhancedAurManager {
         }
     }
 
-    /// Manually retrieve PKGBUILD from AUR
+    /// Manually retrieve package metadata (.SRCINFO) from AUR
     pub async fn fetch_pkgbuild(&mut self, package: &str) -> Result<PkgbuildInfo> {
-        println!("[aur] Fetching PKGBUILD for {}", package);
+        println!("[aur] Fetching .SRCINFO for {}", package);
 
-        // Download PKGBUILD
-        let pkgbuild_url = format!(
-            "https://aur.archlinux.org/cgit/aur.git/plain/PKGBUILD?h={}",
-            package
-        );
-        let pkgbuild_content = reqwest::get(&pkgbuild_url).await?.text().await?;
+        // Download .SRCINFO
+        let srcinfo_content = crate::aur_rpc::client()
+            .fetch_file(package, ".SRCINFO")
+            .await?;
 
-        // Parse PKGBUILD
-        let pkgbuild_info = self.parse_pkgbuild(package, &pkgbuild_content)?;
+        // Parse .SRCINFO
+        let pkgbuild_info = self.parse_srcinfo(package, &srcinfo_content)?;
 
         // Cache it
         self.pkgbuild_cache
@@ -82,88 +82,33 @@ impl EnhancedAurManager {
         Ok(pkgbuild_info)
     }
 
-    /// Parse PKGBUILD content into structured info
-    pub fn parse_pkgbuild(&self, package: &str, content: &str) -> Result<PkgbuildInfo> {
-        let mut info = PkgbuildInfo {
-            package: package.to_string(),
-            version: String::new(),
-            description: String::new(),
-            dependencies: Vec::new(),
-            make_dependencies: Vec::new(),
-            conflicts: Vec::new(),
-            provides: Vec::new(),
-            source_files: Vec::new(),
-            integrity_checks: Vec::new(),
-        };
-
-        let mut in_array = false;
-        let mut current_array = String::new();
-        let mut array_content = String::new();
-
-        for line in content.lines() {
-            let trimmed = line.trim();
-
-            if trimmed.starts_with("pkgver=") {
-                info.version = self.extract_value(trimmed, "pkgver=");
-            } else if trimmed.starts_with("pkgdesc=") {
-                info.description = self.extract_value(trimmed, "pkgdesc=");
-            } else if trimmed.starts_with("depends=(") {
-                if trimmed.ends_with(')') {
-                    info.dependencies = self.parse_array_line(trimmed, "depends=(");
-                } else {
-                    in_array = true;
-                    current_array = "depends".to_string();
-                    array_content = trimmed.trim_start_matches("depends=(").to_string();
-                }
-            } else if trimmed.starts_with("makedepends=(") {
-                if trimmed.ends_with(')') {
-                    info.make_dependencies = self.parse_array_line(trimmed, "makedepends=(");
-                } else {
-                    in_array = true;
-                    current_array = "makedepends".to_string();
-                    array_content = trimmed.trim_start_matches("makedepends=(").to_string();
-                }
-            } else if in_array {
-                if trimmed.ends_with(')') {
-                    array_content.push(' ');
-                    array_content.push_str(trimmed.trim_end_matches(')'));
-                    let parsed = self.parse_array_content(&array_content);
-                    match current_array.as_str() {
-                        "depends" => info.dependencies = parsed,
-                        "makedepends" => info.make_dependencies = parsed,
-                        _ => {}
-                    }
-                    in_array = false;
-                    current_array.clear();
-                    array_content.clear();
-                } else {
-                    array_content.push(' ');
-                    array_content.push_str(trimmed);
-                }
-            }
-        }
-
-        Ok(info)
-    }
+    /// Parse .SRCINFO content into structured info for `package` on the host architecture
+    pub fn parse_srcinfo(&self, package: &str, content: &str) -> Result<PkgbuildInfo> {
+        let srcinfo = SrcInfo::parse(content)?;
+        let member = srcinfo.package(package);
 
-    fn extract_value(&self, line: &str, prefix: &str) -> String {
-        line.trim_start_matches(prefix)
-            .trim_matches('"')
-            .trim_matches('\'')
-            .to_string()
-    }
-
-    fn parse_array_line(&self, line: &str, prefix: &str) -> Vec<String> {
-        let content = line.trim_start_matches(prefix).trim_end_matches(')');
-        self.parse_array_content(content)
-    }
+        let mut make_dependencies = srcinfo.makedepends.host();
+        make_dependencies.extend(srcinfo.checkdepends.host());
+        let integrity_checks = srcinfo
+            .checksums
+            .values()
+            .flat_map(|sums| sums.host())
+            .collect();
 
-    fn parse_array_content(&self, content: &str) -> Vec<String> {
-        content
-            .split_whitespace()
-            .filter(|s| !s.is_empty())
-            .map(|s| s.trim_matches('"').trim_matches('\'').to_string())
-            .collect()
+        Ok(PkgbuildInfo {
+            package: package.to_string(),
+            version: srcinfo.full_version(),
+            description: member
+                .and_then(|p| p.pkgdesc.clone())
+                .or_else(|| srcinfo.pkgdesc.clone())
+                .unwrap_or_default(),
+            dependencies: member.map_or_else(|| srcinfo.depends.host(), |p| p.depends.host()),
+            make_dependencies,
+            conflicts: member.map_or_else(|| srcinfo.conflicts.host(), |p| p.conflicts.host()),
+            provides: member.map_or_else(|| srcinfo.provides.host(), |p| p.provides.host()),
+            source_files: srcinfo.source.host(),
+            integrity_checks,
+        })
     }
 
     /// Advanced dependency resolution with conflict detection
@@ -334,95 +279,36 @@ impl EnhancedAurManager {
 
         // Check if installed version conflicts with required versions
         for dep in &pkgbuild.dependencies {
-            if let Some((dep_name, version_req)) = self.parse_version_constraint(dep) {
-                if let Some(installed_version) = self.get_installed_version(&dep_name) {
-                    if !self.version_satisfies(&installed_version, &version_req) {
-                        conflicts.push(DependencyConflict {
-                            package: package.to_string(),
-                            conflicting_with: dep_name,
-                            conflict_type: ConflictType::VersionConflict(
-                                installed_version,
-                                version_req,
-                            ),
-                            resolution: Some("Upgrade or downgrade dependency".to_string()),
-                        });
-                    }
-                }
+            let dep = Dependency::parse(dep);
+            let Some((op, wanted)) = &dep.constraint else {
+                continue;
+            };
+            let Some(installed_version) = self.get_installed_version(&dep.name) else {
+                continue;
+            };
+            if !dep.satisfied_by(&Version::parse(&installed_version)) {
+                conflicts.push(DependencyConflict {
+                    package: package.to_string(),
+                    conflicting_with: dep.name.clone(),
+                    conflict_type: ConflictType::VersionConflict(
+                        installed_version,
+                        format!("{}{}", op.as_str(), wanted),
+                    ),
+                    resolution: Some("Upgrade or downgrade dependency".to_string()),
+                });
             }
         }
 
         conflicts
     }
 
-    fn parse_version_constraint(&self, dep: &str) -> Option<(String, String)> {
-        if dep.contains(">=") {
-            let parts: Vec<&str> = dep.split(">=").collect();
-            if parts.len() == 2 {
-                return Some((parts[0].to_string(), format!(">={}", parts[1])));
-            }
-        } else if dep.contains("<=") {
-            let parts: Vec<&str> = dep.split("<=").collect();
-            if parts.len() == 2 {
-                return Some((parts[0].to_string(), format!("<={}", parts[1])));
-            }
-        } else if dep.contains('=') && !dep.contains(">=") && !dep.contains("<=") {
-            let parts: Vec<&str> = dep.split('=').collect();
-            if parts.len() == 2 {
-                return Some((parts[0].to_string(), format!("={}", parts[1])));
-            }
-        }
-        None
-    }
-
     fn get_installed_version(&self, package: &str) -> Option<String> {
-        let output = Command::new("pacman").args(["-Qi", package]).output();
-
-        if let Ok(output) = output {
-            if !output.status.success() {
-                return None;
-            }
-
-            let info = String::from_utf8_lossy(&output.stdout);
-            for line in info.lines() {
-                if line.starts_with("Version") {
-                    return line.split_whitespace().nth(2).map(|s| s.to_string());
-                }
-            }
-        }
-        None
-    }
-
-    fn version_satisfies(&self, installed: &str, requirement: &str) -> bool {
-        // Simple version comparison - would need proper semver parsing for production
-        if requirement.starts_with(">=") {
-            let req_version = requirement.trim_start_matches(">=");
-            installed >= req_version
-        } else if requirement.starts_with("<=") {
-            let req_version = requirement.trim_start_matches("<=");
-            installed <= req_version
-        } else if requirement.starts_with('=') {
-            let req_version = requirement.trim_start_matches('=');
-            installed == req_version
-        } else {
-            true // No constraint
-        }
+        crate::pacman::get_version(package)
     }
 
     fn clean_dependency_name(&self, dep: &str) -> String {
         // Remove version constraints like >=1.0, <2.0, etc.
-        dep.split_whitespace()
-            .next()
-            .unwrap_or(dep)
-            .split(">=")
-            .next()
-            .unwrap_or(dep)
-            .split("<=")
-            .next()
-            .unwrap_or(dep)
-            .split('=')
-            .next()
-            .unwrap_or(dep)
-            .to_string()
+        Dependency::parse(dep).name
     }
 }
 
@@ -102,6 +102,7 @@ pub fn is_flatpak_available() -> bool {
 /// use crate::flatpak;
 /// flatpak::install("com.example.App");
 /// ```
+#[allow(dead_code)]
 pub fn install(pkg: &str) {
     if !is_flatpak_available() {
         eprintln!("[reap] Error: Flatpak is not installed. Install with: sudo pacman -S flatpak");
@@ -1,8 +1,10 @@
+use crate::pacman_db::{InstallReason, PackageDb, PacmanDb};
 use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct DependencyNode {
     pub package: String,
@@ -35,7 +37,61 @@ impl GraphVisualizer {
         }
     }
 
+    /// Graph of every installed package, with explicitly installed packages as roots.
+    ///
+    /// Edges go to each installed package satisfying a `depends` or `optdepends` entry, so
+    /// `dependents` is the reverse-dependency index of the local database.
+    pub fn from_local(local: &PackageDb) -> Self {
+        let mut nodes: HashMap<String, DependencyNode> = local
+            .iter()
+            .map(|pkg| {
+                let node = DependencyNode {
+                    package: pkg.name.clone(),
+                    version: pkg.version.clone(),
+                    source: crate::core::Source::Pacman,
+                    trust_score: None,
+                    dependencies: Vec::new(),
+                    dependents: Vec::new(),
+                    optional: false,
+                    make_only: false,
+                };
+                (pkg.name.clone(), node)
+            })
+            .collect();
+        for (pkg, dependencies) in local.dependency_edges() {
+            for dep in &dependencies {
+                if let Some(dep_node) = nodes.get_mut(dep) {
+                    dep_node.dependents.push(pkg.clone());
+                }
+            }
+            if let Some(node) = nodes.get_mut(&pkg) {
+                node.dependencies = dependencies.into_iter().collect();
+            }
+        }
+        let root_packages = local
+            .iter()
+            .filter(|pkg| pkg.reason == InstallReason::Explicit)
+            .map(|pkg| pkg.name.clone())
+            .collect();
+        Self {
+            graph: DependencyGraph {
+                nodes,
+                root_packages,
+            },
+        }
+    }
+
+    /// Packages with a direct edge to `package`
+    pub fn dependents_of(&self, package: &str) -> &[String] {
+        self.graph
+            .nodes
+            .get(package)
+            .map(|node| node.dependents.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Build dependency graph for a package
+    #[allow(dead_code)]
     pub async fn build_graph(&mut self, packages: &[String]) -> Result<()> {
         let mut visited = HashSet::new();
 
@@ -98,6 +154,7 @@ impl GraphVisualizer {
     }
 
     /// Generate ASCII art dependency tree
+    #[allow(dead_code)]
     pub fn print_tree(&self, package: &str, max_depth: Option<usize>) {
         println!("\n🌳 Dependency Tree for {}", package);
         println!("{}", "=".repeat(60));
@@ -168,6 +225,7 @@ impl GraphVisualizer {
     }
 
     /// Generate DOT format for Graphviz
+    #[allow(dead_code)]
     pub fn export_dot(&self, output_path: &PathBuf) -> Result<()> {
         let mut dot = String::from("digraph dependencies {\n");
         dot.push_str("  rankdir=TB;\n");
@@ -209,6 +267,7 @@ impl GraphVisualizer {
     }
 
     /// Detect circular dependencies
+    #[allow(dead_code)]
     pub fn find_cycles(&self) -> Vec<Vec<String>> {
         let mut cycles = Vec::new();
         let mut visited = HashSet::new();
@@ -261,79 +320,63 @@ impl GraphVisualizer {
     }
 
     /// Find packages that would be orphaned if a package is removed
+    #[allow(dead_code)]
     pub fn find_orphans_if_removed(&self, package: &str) -> Vec<String> {
-        let mut orphans = Vec::new();
+        self.find_orphans_if_all_removed(&[package.to_string()])
+    }
 
-        if let Some(node) = self.graph.nodes.get(package) {
-            for dep in &node.dependencies {
-                if let Some(dep_node) = self.graph.nodes.get(dep) {
-                    // Check if this dependency would become orphaned
-                    let other_dependents: Vec<_> = dep_node
-                        .dependents
-                        .iter()
-                        .filter(|&d| d != package)
-                        .collect();
-
-                    if other_dependents.is_empty() && !self.graph.root_packages.contains(dep) {
-                        orphans.push(dep.clone());
-                        // Recursively find orphans of this orphan
-                        let recursive_orphans = self.find_orphans_if_removed(dep);
-                        orphans.extend(recursive_orphans);
-                    }
-                }
+    /// Dependencies of `packages` that no root outside them reaches any more, sorted
+    pub fn find_orphans_if_all_removed(&self, packages: &[String]) -> Vec<String> {
+        let removed: HashSet<&str> = packages.iter().map(String::as_str).collect();
+        let dependencies = |pkg: &str| {
+            self.graph
+                .nodes
+                .get(pkg)
+                .into_iter()
+                .flat_map(|node| node.dependencies.iter().map(String::as_str))
+        };
+
+        // Everything still reachable from the remaining roots
+        let mut needed = HashSet::new();
+        let mut queue: Vec<&str> = self
+            .graph
+            .root_packages
+            .iter()
+            .map(String::as_str)
+            .collect();
+        while let Some(pkg) = queue.pop() {
+            if !removed.contains(pkg) && needed.insert(pkg) {
+                queue.extend(dependencies(pkg));
             }
         }
 
-        // Remove duplicates
-        orphans.sort();
-        orphans.dedup();
-        orphans
+        let mut orphans = BTreeSet::new();
+        let mut queue: Vec<&str> = packages.iter().flat_map(|p| dependencies(p)).collect();
+        while let Some(pkg) = queue.pop() {
+            let orphaned = self.graph.nodes.contains_key(pkg)
+                && !needed.contains(pkg)
+                && !removed.contains(pkg);
+            if orphaned && orphans.insert(pkg.to_string()) {
+                queue.extend(dependencies(pkg));
+            }
+        }
+        orphans.into_iter().collect()
     }
 
     async fn get_package_dependencies(&self, pkg: &str) -> Result<(Vec<String>, Vec<String>)> {
         // Try AUR first
-        let pkgbuild = crate::aur::get_pkgbuild_preview(pkg);
-        if !pkgbuild.contains("not found") {
-            let (depends, makedepends, _, _, _) = crate::utils::resolve_deps(&pkgbuild);
+        if let Ok(srcinfo) = crate::aur::fetch_srcinfo(pkg).await {
+            let (depends, makedepends, _, _, _) = crate::utils::resolve_deps(&srcinfo, pkg);
             return Ok((depends, makedepends));
         }
 
-        // Fall back to pacman
-        let output = std::process::Command::new("pacman")
-            .args(["-Si", pkg])
-            .output()?;
-
-        if output.status.success() {
-            let info = String::from_utf8_lossy(&output.stdout);
-            let mut depends = Vec::new();
-            let mut makedepends = Vec::new();
-
-            for line in info.lines() {
-                if line.starts_with("Depends On") {
-                    depends = line
-                        .split(':')
-                        .nth(1)
-                        .unwrap_or("")
-                        .split_whitespace()
-                        .filter(|s| *s != "None")
-                        .map(|s| s.to_string())
-                        .collect();
-                } else if line.starts_with("Make Deps") {
-                    makedepends = line
-                        .split(':')
-                        .nth(1)
-                        .unwrap_or("")
-                        .split_whitespace()
-                        .filter(|s| *s != "None")
-                        .map(|s| s.to_string())
-                        .collect();
-                }
-            }
-
-            Ok((depends, makedepends))
-        } else {
-            Ok((Vec::new(), Vec::new()))
-        }
+        // Fall back to the sync databases
+        Ok(PacmanDb::system()
+            .sync_all()
+            .iter()
+            .find_map(|db| db.get(pkg))
+            .map(|p| (p.depends.clone(), p.makedepends.clone()))
+            .unwrap_or_default())
     }
 
     async fn get_package_version(&self, pkg: &str) -> Option<String> {
@@ -1,7 +1,12 @@
+use crate::artifacts::ArtifactStore;
+use crate::local_repo::{PACMAN_CACHE, RepoPackage, find_archives};
+use crate::pacman_db::PacmanDb;
+use crate::version::vercmp;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -15,6 +20,9 @@ pub struct PackageSnapshot {
     pub dependencies: Vec<String>,
     pub trust_score: Option<f32>,
     pub backup_path: PathBuf,
+    /// Version replaced when this entry records a rollback
+    #[serde(default)]
+    pub rollback_from: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +33,8 @@ pub struct InstallationHistory {
 
 pub struct HistoryManager {
     history_dir: PathBuf,
+    /// Where archives of earlier versions are looked up, in order
+    archive_dirs: Vec<PathBuf>,
     package_histories: HashMap<String, InstallationHistory>,
 }
 
@@ -33,10 +43,21 @@ impl HistoryManager {
         let history_dir = dirs::data_dir()
             .unwrap_or_else(|| PathBuf::from("/tmp"))
             .join("reap/history");
+        Self::with_dirs(
+            history_dir,
+            vec![ArtifactStore::default_root(), PathBuf::from(PACMAN_CACHE)],
+        )
+    }
+
+    /// A manager keeping its history in `history_dir` and rolling back to archives found in
+    /// `archive_dirs`
+    pub fn with_dirs(history_dir: impl Into<PathBuf>, archive_dirs: Vec<PathBuf>) -> Self {
+        let history_dir = history_dir.into();
         let _ = fs::create_dir_all(&history_dir);
 
         Self {
             history_dir,
+            archive_dirs,
             package_histories: HashMap::new(),
         }
     }
@@ -56,7 +77,7 @@ impl HistoryManager {
         fs::create_dir_all(&backup_dir)?;
 
         // Get currently installed files
-        let installed_files = self.get_installed_files(pkg)?;
+        let installed_files = self.get_installed_files(pkg);
 
         // Backup current installation
         self.backup_package_files(pkg, &backup_dir)?;
@@ -70,16 +91,11 @@ impl HistoryManager {
             dependencies: self.get_package_dependencies(pkg),
             trust_score: None, // Will be filled by trust engine
             backup_path: backup_dir,
+            rollback_from: None,
         };
 
         // Update history
-        let history = self
-            .package_histories
-            .entry(pkg.to_string())
-            .or_insert_with(|| InstallationHistory {
-                snapshots: Vec::new(),
-                current_version: None,
-            });
+        let history = self.history_mut(pkg);
         history.snapshots.push(snapshot.clone());
         history.current_version = Some(version.to_string());
 
@@ -87,35 +103,90 @@ impl HistoryManager {
         Ok(snapshot)
     }
 
-    /// Rollback to a specific version
-    pub fn rollback_to_version(&mut self, pkg: &str, target_version: &str) -> Result<()> {
-        let history = self
-            .package_histories
-            .get(pkg)
-            .ok_or_else(|| anyhow::anyhow!("No history found for package: {}", pkg))?;
+    /// Archives of `pkg` that can be rolled back to, newest first, skipping `installed`
+    pub fn rollback_candidates(&self, pkg: &str, installed: Option<&str>) -> Vec<RepoPackage> {
+        find_archives(pkg, &self.archive_dirs)
+            .into_iter()
+            .filter(|archive| installed.is_none_or(|v| !vercmp(&archive.version, v).is_eq()))
+            .collect()
+    }
 
-        let snapshot = history
-            .snapshots
-            .iter()
-            .find(|s| s.version == target_version)
-            .ok_or_else(|| anyhow::anyhow!("Version {} not found in history", target_version))?;
+    /// Rollback to a specific version and pin it so upgrades leave it alone
+    pub fn rollback_to_version(&mut self, pkg: &str, target_version: &str) -> Result<()> {
+        self.restore_version(pkg, target_version)?;
+        if !crate::utils::is_pinned(pkg) {
+            crate::utils::pin_package(pkg).map_err(|e| anyhow::anyhow!(e))?;
+            println!("📌 Pinned {}", pkg);
+        }
+        Ok(())
+    }
 
-        // Restore files from backup
-        self.restore_from_snapshot(snapshot)?;
+    /// Reinstall the archive of `target_version` and record it as a rollback entry
+    pub fn restore_version(&mut self, pkg: &str, target_version: &str) -> Result<()> {
+        let archive = find_archives(pkg, &self.archive_dirs)
+            .into_iter()
+            .find(|a| vercmp(&a.version, target_version).is_eq())
+            .ok_or_else(|| {
+                anyhow::anyhow!("No archive of {} {} is kept or cached", pkg, target_version)
+            })?;
+        let current = crate::pacman::get_version(pkg);
 
-        // Update package database
-        self.update_package_database(pkg, target_version)?;
+        println!(
+            "🔄 Reinstalling {} {} from {}",
+            pkg,
+            archive.version,
+            archive.path.display()
+        );
+        crate::pacman::run([OsStr::new("-U"), archive.path.as_os_str()])?;
 
-        println!("🔄 Rolled back {} to version {}", pkg, target_version);
+        self.record_rollback(pkg, &archive, current)?;
+        println!("🔄 Rolled back {} to version {}", pkg, archive.version);
         Ok(())
     }
 
+    /// Add a history entry for a rollback to `archive` from version `from`
+    pub fn record_rollback(
+        &mut self,
+        pkg: &str,
+        archive: &RepoPackage,
+        from: Option<String>,
+    ) -> Result<()> {
+        let history = self.history_mut(pkg);
+        // Keep the source of the earlier install of this version when there is one
+        let source = history
+            .snapshots
+            .iter()
+            .rev()
+            .find(|s| vercmp(&s.version, &archive.version).is_eq())
+            .map(|s| s.source.clone());
+        let source = source.unwrap_or(if archive.path.starts_with(PACMAN_CACHE) {
+            crate::core::Source::Pacman
+        } else {
+            crate::core::Source::Aur
+        });
+        let snapshot = PackageSnapshot {
+            package: pkg.to_string(),
+            version: archive.version.clone(),
+            source,
+            installed_at: Utc::now(),
+            installed_files: self.get_installed_files(pkg),
+            dependencies: self.get_package_dependencies(pkg),
+            trust_score: None,
+            backup_path: archive.path.clone(),
+            rollback_from: from,
+        };
+        let history = self.history_mut(pkg);
+        history.snapshots.push(snapshot);
+        history.current_version = Some(archive.version.clone());
+        self.save_history(pkg)
+    }
+
     /// Show package history with interactive selection
-    pub fn show_history(&self, pkg: &str) -> Result<()> {
-        let history = self
-            .package_histories
-            .get(pkg)
-            .ok_or_else(|| anyhow::anyhow!("No history found for package: {}", pkg))?;
+    pub fn show_history(&mut self, pkg: &str) -> Result<()> {
+        let history = self.history_mut(pkg).clone();
+        if history.snapshots.is_empty() {
+            return Err(anyhow::anyhow!("No history found for package: {}", pkg));
+        }
 
         println!("\n📊 History for {}", pkg);
         println!(
@@ -130,50 +201,37 @@ impl HistoryManager {
                 .map(|score| format!(" {}", self.get_trust_badge(score)))
                 .unwrap_or_default();
 
+            let rollback = snapshot
+                .rollback_from
+                .as_ref()
+                .map(|from| format!(" (rollback from {})", from))
+                .unwrap_or_default();
+
             println!(
-                "  {}: {} ({}){} - {}",
+                "  {}: {} ({}){} - {}{}",
                 i + 1,
                 snapshot.version,
                 snapshot.source.label(),
                 trust_badge,
-                snapshot.installed_at.format("%Y-%m-%d %H:%M")
+                snapshot.installed_at.format("%Y-%m-%d %H:%M"),
+                rollback
             );
         }
         Ok(())
     }
 
-    fn get_installed_files(&self, pkg: &str) -> Result<Vec<String>> {
-        let output = std::process::Command::new("pacman")
-            .args(["-Ql", pkg])
-            .output()?;
-
-        if !output.status.success() {
-            return Ok(Vec::new());
-        }
-
-        let files = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .filter_map(|line| {
-                let parts: Vec<&str> = line.splitn(2, ' ').collect();
-                if parts.len() == 2 {
-                    Some(parts[1].to_string())
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        Ok(files)
+    /// Files of the installed `pkg` as absolute paths, like `pacman -Ql`
+    fn get_installed_files(&self, pkg: &str) -> Vec<String> {
+        PacmanDb::system()
+            .installed_with_files(pkg)
+            .map(|p| p.files.iter().map(|f| format!("/{}", f)).collect())
+            .unwrap_or_default()
     }
 
     fn backup_package_files(&self, pkg: &str, backup_dir: &Path) -> Result<()> {
-        // Create package info backup
-        let output = std::process::Command::new("pacman")
-            .args(["-Qi", pkg])
-            .output()?;
-
-        if output.status.success() {
-            fs::write(backup_dir.join("pacman-info.txt"), &output.stdout)?;
+        // Keep the package's local database entry
+        if let Some(entry) = PacmanDb::system().local_entry(pkg) {
+            fs::copy(entry.join("desc"), backup_dir.join("desc"))?;
         }
 
         // Backup key files if they exist
@@ -194,46 +252,27 @@ impl HistoryManager {
         Ok(())
     }
 
-    fn restore_from_snapshot(&self, snapshot: &PackageSnapshot) -> Result<()> {
-        // This would implement the actual file restoration
-        // For now, we'll use pacman to reinstall the specific version
-        println!(
-            "🔄 Restoring {} version {} from backup...",
-            snapshot.package, snapshot.version
-        );
-
-        // In a real implementation, you'd restore the actual files
-        // and update the pacman database appropriately
-
-        Ok(())
-    }
-
-    fn update_package_database(&self, _pkg: &str, _version: &str) -> Result<()> {
-        // Update pacman database to reflect the rollback
-        // This is a complex operation that would need careful implementation
-        Ok(())
+    fn get_package_dependencies(&self, pkg: &str) -> Vec<String> {
+        PacmanDb::system()
+            .installed(pkg)
+            .map(|p| p.depends)
+            .unwrap_or_default()
     }
 
-    fn get_package_dependencies(&self, pkg: &str) -> Vec<String> {
-        let output = std::process::Command::new("pacman")
-            .args(["-Qi", pkg])
-            .output();
-
-        if let Ok(output) = output {
-            let info = String::from_utf8_lossy(&output.stdout);
-            for line in info.lines() {
-                if line.starts_with("Depends On") {
-                    return line
-                        .split(':')
-                        .nth(1)
-                        .unwrap_or("")
-                        .split_whitespace()
-                        .map(|s| s.to_string())
-                        .collect();
-                }
-            }
-        }
-        Vec::new()
+    /// History of `pkg`, loaded from its history file on first use
+    fn history_mut(&mut self, pkg: &str) -> &mut InstallationHistory {
+        let history_file = self.history_dir.join(format!("{}.json", pkg));
+        self.package_histories
+            .entry(pkg.to_string())
+            .or_insert_with(|| {
+                fs::read_to_string(history_file)
+                    .ok()
+                    .and_then(|content| serde_json::from_str(&content).ok())
+                    .unwrap_or(InstallationHistory {
+                        snapshots: Vec::new(),
+                        current_version: None,
+                    })
+            })
     }
 
     fn save_history(&self, pkg: &str) -> Result<()> {
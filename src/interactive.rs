@@ -1,3 +1,4 @@
+use crate::aur_rpc::AurPackage;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -95,14 +96,14 @@ impl InteractiveManager {
         }
 
         // Fetch from AUR API
-        self.fetch_aur_info(package).await?;
+        let info = self.fetch_aur_info(package).await?;
 
         let rating = PackageRating {
             package: package.to_string(),
-            aur_votes: 0,
-            aur_popularity: 0.0,
+            aur_votes: info.as_ref().map(|i| i.num_votes).unwrap_or(0),
+            aur_popularity: info.as_ref().map(|i| i.popularity).unwrap_or(0.0),
             user_rating: None,
-            community_rating: self.calculate_community_rating(&()),
+            community_rating: self.calculate_community_rating(info.as_ref()),
             reviews: Vec::new(),
             last_updated: chrono::Utc::now(),
         };
@@ -115,17 +116,12 @@ impl InteractiveManager {
         Ok(rating)
     }
 
-    async fn fetch_aur_info(&self, package: &str) -> Result<()> {
-        let url = format!(
-            "https://aur.archlinux.org/rpc/?v=5&type=info&arg[]={}",
-            package
-        );
-        let resp = reqwest::get(&url).await?;
-        let _ = resp.text().await?;
-        Ok(())
+    async fn fetch_aur_info(&self, package: &str) -> Result<Option<AurPackage>> {
+        let results = crate::aur_rpc::client().info(&[package]).await?;
+        Ok(results.into_iter().next())
     }
 
-    fn calculate_community_rating(&self, _aur_info: &()) -> f64 {
+    fn calculate_community_rating(&self, _aur_info: Option<&AurPackage>) -> f64 {
         // Simplified stub
         3.5
     }
@@ -0,0 +1,212 @@
+// Write-ahead journal of multi-step install and upgrade transactions
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors produced while reading or writing the transaction journal
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("journal is corrupt: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("transaction {0} was interrupted; run `reap recover` first")]
+    Interrupted(String),
+}
+
+/// Progress of one journaled step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    Pending,
+    Done,
+    Failed(String),
+}
+
+/// One step of a transaction: a set of packages installed together
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalStep {
+    /// What the step installs, such as an AUR pkgbase or `repo`
+    pub label: String,
+    pub packages: Vec<String>,
+    /// Version of each package installed before the step, `None` if it was not installed
+    pub previous: BTreeMap<String, Option<String>>,
+    pub status: StepStatus,
+}
+
+impl JournalStep {
+    /// A pending step; `previous` is filled in by the caller before the journal is written
+    pub fn new(label: &str, packages: Vec<String>) -> Self {
+        Self {
+            label: label.to_string(),
+            packages,
+            previous: BTreeMap::new(),
+            status: StepStatus::Pending,
+        }
+    }
+}
+
+/// A planned transaction and how far it got
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub id: String,
+    /// The operation, such as `install` or `upgrade`
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    /// Packages that were asked for; the other packages of the steps are dependencies
+    #[serde(default)]
+    pub targets: Vec<String>,
+    pub steps: Vec<JournalStep>,
+}
+
+impl Transaction {
+    pub fn new(command: &str, targets: Vec<String>, steps: Vec<JournalStep>) -> Self {
+        let started_at = Utc::now();
+        Self {
+            id: started_at.format("%Y%m%d%H%M%S").to_string(),
+            command: command.to_string(),
+            started_at,
+            targets,
+            steps,
+        }
+    }
+
+    /// Index of the step labelled `label`
+    pub fn step(&self, label: &str) -> Option<usize> {
+        self.steps.iter().position(|s| s.label == label)
+    }
+
+    /// Steps that finished, in the order they were planned
+    pub fn completed(&self) -> impl Iterator<Item = &JournalStep> {
+        self.steps.iter().filter(|s| s.status == StepStatus::Done)
+    }
+
+    /// Steps that never finished, including failed ones
+    pub fn remaining(&self) -> impl Iterator<Item = &JournalStep> {
+        self.steps.iter().filter(|s| s.status != StepStatus::Done)
+    }
+
+    /// Whether every step finished
+    pub fn is_complete(&self) -> bool {
+        self.remaining().next().is_none()
+    }
+
+    /// Print the steps with their status
+    pub fn print(&self) {
+        println!(
+            "[reap] Transaction {} ({}) started {}:",
+            self.id,
+            self.command,
+            self.started_at.format("%Y-%m-%d %H:%M")
+        );
+        for step in &self.steps {
+            let status = match &step.status {
+                StepStatus::Pending => "pending".to_string(),
+                StepStatus::Done => "done".to_string(),
+                StepStatus::Failed(e) => format!("failed: {}", e),
+            };
+            println!("  {} [{}] {}", step.label, status, step.packages.join(" "));
+        }
+    }
+}
+
+/// The journal file holding at most one open transaction.
+///
+/// The plan is written before the first step runs and rewritten as each step finishes, so
+/// a transaction still on disk at startup was interrupted. Writes go through a temporary
+/// file and a rename so a crash never leaves a half-written journal.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.local/share/reap/journal.json`
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("reap/journal.json")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The interrupted transaction, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal exists but cannot be read.
+    pub fn pending(&self) -> Result<Option<Transaction>, JournalError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&self.path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    /// Write `tx` as the open transaction before any of its steps run
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if another transaction was interrupted or the journal cannot be written.
+    pub fn begin(&self, tx: &Transaction) -> Result<(), JournalError> {
+        if let Some(open) = self.pending()? {
+            return Err(JournalError::Interrupted(open.id));
+        }
+        self.write(tx)
+    }
+
+    /// Record the status of step `index` of `tx`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal cannot be written.
+    pub fn mark(
+        &self,
+        tx: &mut Transaction,
+        index: usize,
+        status: StepStatus,
+    ) -> Result<(), JournalError> {
+        if let Some(step) = tx.steps.get_mut(index) {
+            step.status = status;
+        }
+        self.write(tx)
+    }
+
+    /// Close the open transaction
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal exists but cannot be removed.
+    pub fn finish(&self) -> Result<(), JournalError> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&self, tx: &Transaction) -> Result<(), JournalError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(serde_json::to_string_pretty(tx)?.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
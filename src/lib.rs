@@ -1,9 +1,14 @@
 pub mod analytics;
+pub mod artifacts;
 pub mod aur;
+pub mod aur_rpc;
 pub mod backend;
+pub mod chroot;
 pub mod cli;
+pub mod clone_cache;
 pub mod config;
 pub mod core;
+pub mod devel;
 pub mod enhanced_aur;
 pub mod flatpak;
 pub mod gpg;
@@ -11,12 +16,21 @@ pub mod graph;
 pub mod history;
 pub mod hooks;
 pub mod interactive;
+pub mod journal;
+pub mod local_repo;
+pub mod make_deps;
 pub mod pacman;
+pub mod pacman_db;
 pub mod profiles;
+pub mod scheduler;
+pub mod solver;
+pub mod srcinfo;
 pub mod tap;
 pub mod trust;
 pub mod tui;
+pub mod upgrade;
 pub mod utils;
+pub mod version;
 
 pub use crate::aur::SearchResult;
 pub use crate::aur::get_deps;
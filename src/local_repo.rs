@@ -0,0 +1,311 @@
+// Local pacman repository of the packages reap has built
+use crate::artifacts::{self, ArtifactStore};
+use crate::config::GlobalConfig;
+use crate::version::Version;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+/// Name of the repository database and of its pacman.conf section
+pub const LOCAL_REPO_NAME: &str = "reap-local";
+
+/// pacman's package cache, searched for older versions after reap's own builds
+pub const PACMAN_CACHE: &str = "/var/cache/pacman/pkg";
+
+/// Errors produced while maintaining or serving the local repository
+#[derive(Debug, Error)]
+pub enum LocalRepoError {
+    #[error("{command} failed: {stderr}")]
+    Command { command: String, stderr: String },
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A package archive kept in the local repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoPackage {
+    pub name: String,
+    /// Full `[epoch:]pkgver-pkgrel` version
+    pub version: String,
+    pub arch: String,
+    pub path: PathBuf,
+}
+
+impl RepoPackage {
+    /// Parse an archive name such as `foo-1:2.0-1-x86_64.pkg.tar.zst`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if !artifacts::is_package_archive(path) {
+            return None;
+        }
+        let file_name = path.file_name()?.to_str()?;
+        let stem = &file_name[..file_name.find(".pkg.tar")?];
+        // name-pkgver-pkgrel-arch; the name itself may contain dashes
+        let mut parts = stem.rsplitn(4, '-');
+        let (arch, pkgrel, pkgver) = (parts.next()?, parts.next()?, parts.next()?);
+        Some(RepoPackage {
+            name: parts.next()?.to_string(),
+            version: format!("{}-{}", pkgver, pkgrel),
+            arch: arch.to_string(),
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+/// A `repo-add` database next to the archives it indexes.
+///
+/// The default repository lives in reap's `PKGDEST`, so every build is already in place
+/// and only has to be added to the database. With a signing key (`repo_sign_key` in
+/// reap.toml) archives and the database are signed, so other machines can require it.
+#[derive(Debug, Clone)]
+pub struct LocalRepo {
+    root: PathBuf,
+    name: String,
+    sign_key: Option<String>,
+}
+
+impl LocalRepo {
+    pub fn new(root: impl Into<PathBuf>, name: &str) -> Self {
+        Self {
+            root: root.into(),
+            name: name.to_string(),
+            sign_key: None,
+        }
+    }
+
+    /// Sign archives and the database with the GPG key `key`
+    pub fn with_sign_key(mut self, key: Option<String>) -> Self {
+        self.sign_key = key;
+        self
+    }
+
+    pub fn sign_key(&self) -> Option<&str> {
+        self.sign_key.as_deref()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `<root>/<name>.db.tar.gz`
+    pub fn db_path(&self) -> PathBuf {
+        self.root.join(format!("{}.db.tar.gz", self.name))
+    }
+
+    /// Add archives to the database, copying in any that live outside the repository.
+    ///
+    /// With a signing key, archives without a signature are signed and so is the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be copied, signing fails or `repo-add` fails.
+    pub fn add(&self, packages: &[PathBuf]) -> Result<(), LocalRepoError> {
+        if packages.is_empty() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.root)?;
+        let mut files = Vec::new();
+        for package in packages {
+            let Some(file_name) = package.file_name() else {
+                continue;
+            };
+            let dest = self.root.join(file_name);
+            if package.parent() != Some(self.root.as_path()) {
+                std::fs::copy(package, &dest)?;
+                let sig = signature_of(package);
+                if sig.exists() {
+                    std::fs::copy(&sig, signature_of(&dest))?;
+                }
+            }
+            files.push(dest);
+        }
+        let mut repo_add = Command::new("repo-add");
+        repo_add.arg("--quiet");
+        if let Some(key) = &self.sign_key {
+            for file in files.iter().filter(|f| !signature_of(f).exists()) {
+                run(Command::new("gpg")
+                    .args(["--batch", "--detach-sign", "--use-agent", "--no-armor"])
+                    .args(["--local-user", key, "--output"])
+                    .arg(signature_of(file))
+                    .arg(file))?;
+            }
+            repo_add.args(["--sign", "--key", key]);
+        }
+        run(repo_add.arg(self.db_path()).args(&files))
+    }
+
+    /// Every kept archive, sorted by name and then oldest to newest version
+    pub fn packages(&self) -> Vec<RepoPackage> {
+        let mut packages: Vec<RepoPackage> = ArtifactStore::new(&self.root)
+            .list()
+            .iter()
+            .filter_map(|path| RepoPackage::from_path(path))
+            .collect();
+        packages.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| Version::parse(&a.version).cmp(&Version::parse(&b.version)))
+        });
+        packages
+    }
+
+    /// Newest kept build of `pkgname`
+    pub fn newest(&self, pkgname: &str) -> Option<RepoPackage> {
+        self.packages().into_iter().rfind(|p| p.name == pkgname)
+    }
+
+    /// Delete all but the newest `keep` builds of each package (at least one is kept).
+    ///
+    /// The database always points at the newest build, so it stays valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an archive cannot be removed.
+    pub fn prune(&self, keep: usize) -> Result<Vec<RepoPackage>, LocalRepoError> {
+        let mut by_name: BTreeMap<String, Vec<RepoPackage>> = BTreeMap::new();
+        for package in self.packages() {
+            by_name
+                .entry(package.name.clone())
+                .or_default()
+                .push(package);
+        }
+        let mut removed = Vec::new();
+        for mut builds in by_name.into_values() {
+            let stale = builds.len().saturating_sub(keep.max(1));
+            for package in builds.drain(..stale) {
+                std::fs::remove_file(&package.path)?;
+                let sig = signature_of(&package.path);
+                if sig.exists() {
+                    std::fs::remove_file(sig)?;
+                }
+                removed.push(package);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Whether pacman.conf contents have a section for this repository
+    pub fn is_configured(&self, pacman_conf: &str) -> bool {
+        let section = format!("[{}]", self.name);
+        pacman_conf.lines().any(|line| line.trim() == section)
+    }
+
+    /// pacman.conf section that uses this repository from `server`
+    ///
+    /// Signatures are required when the repository is signed; otherwise pacman has to
+    /// trust whatever `server` sends.
+    pub fn pacman_conf_snippet(&self, server: &str) -> String {
+        let sig_level = match self.sign_key {
+            Some(_) => "Required",
+            None => "Optional TrustAll",
+        };
+        format!(
+            "[{}]\nSigLevel = {}\nServer = {}\n",
+            self.name, sig_level, server
+        )
+    }
+
+    /// Serve the repository directory over plain HTTP until the process is stopped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bind` cannot be listened on.
+    pub fn serve(&self, bind: &str) -> Result<(), LocalRepoError> {
+        let listener = TcpListener::bind(bind)?;
+        println!("[reap] Serving {} on http://{}", self.root.display(), bind);
+        println!("[reap] Add this to pacman.conf on other machines:");
+        println!("{}", self.pacman_conf_snippet(&format!("http://{}", bind)));
+        match self.sign_key() {
+            Some(key) => println!(
+                "[reap] Then trust the signing key there:\n  pacman-key --recv-keys {0}\n  pacman-key --lsign-key {0}",
+                key
+            ),
+            None => eprintln!(
+                "[reap] WARNING: this repository is unsigned and served over plain HTTP, so \
+                 anyone on the network can feed unsigned packages to the machines using it. \
+                 Set repo_sign_key in reap.toml to sign builds and require signatures."
+            ),
+        }
+        for stream in listener.incoming().flatten() {
+            let root = self.root.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = serve_file(stream, &root) {
+                    eprintln!("[reap][repo] Request failed: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Archives of `pkgname` in `dirs`, newest version first with one archive per version.
+///
+/// A version found in several directories is taken from the earliest one.
+pub fn find_archives(pkgname: &str, dirs: &[PathBuf]) -> Vec<RepoPackage> {
+    let mut found: Vec<RepoPackage> = Vec::new();
+    for dir in dirs {
+        for path in ArtifactStore::new(dir).list() {
+            let package = RepoPackage::from_path(&path)
+                .filter(|p| p.name == pkgname)
+                .filter(|p| !found.iter().any(|f| f.version == p.version));
+            found.extend(package);
+        }
+    }
+    found.sort_by(|a, b| Version::parse(&b.version).cmp(&Version::parse(&a.version)));
+    found
+}
+
+impl Default for LocalRepo {
+    fn default() -> Self {
+        Self::new(ArtifactStore::default_root(), LOCAL_REPO_NAME)
+            .with_sign_key(GlobalConfig::load().repo_sign_key)
+    }
+}
+
+/// Run a command, turning a failure into an error with its stderr
+fn run(command: &mut Command) -> Result<(), LocalRepoError> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(LocalRepoError::Command {
+            command: command.get_program().to_string_lossy().to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn signature_of(package: &Path) -> PathBuf {
+    let mut sig = package.as_os_str().to_owned();
+    sig.push(".sig");
+    PathBuf::from(sig)
+}
+
+/// Answer one `GET /<file>` request with a file from `root`
+fn serve_file(mut stream: TcpStream, root: &Path) -> std::io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let name = target.trim_start_matches('/');
+    // Only plain file names inside the repository are served
+    let path = (!name.is_empty() && !name.contains('/') && !name.starts_with('.'))
+        .then(|| root.join(name))
+        .filter(|path| path.is_file());
+    match (method, path) {
+        ("GET" | "HEAD", Some(path)) => {
+            let body = std::fs::read(&path)?;
+            write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            )?;
+            if method == "GET" {
+                stream.write_all(&body)?;
+            }
+        }
+        _ => stream.write_all(b"HTTP/1.0 404 Not Found\r\nContent-Length: 0\r\n\r\n")?,
+    }
+    stream.flush()
+}
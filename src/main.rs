@@ -1,19 +1,35 @@
+mod artifacts;
 mod aur;
+mod aur_rpc;
 mod backend;
+mod chroot;
 mod cli;
+mod clone_cache;
 mod config;
 mod core;
+mod devel;
 mod enhanced_aur;
 mod flatpak;
 mod gpg;
+mod graph;
+mod history;
 mod hooks;
 mod interactive;
+mod journal;
+mod local_repo;
+mod make_deps;
 mod pacman;
+mod pacman_db;
 mod profiles;
+mod scheduler;
+mod solver;
+mod srcinfo;
 mod tap;
 mod trust;
 mod tui;
+mod upgrade;
 mod utils;
+mod version;
 
 use crate::backend::Backend;
 use crate::cli::Commands;
@@ -31,6 +47,7 @@ async fn test_parallel_runners() {
         &["yay".to_string(), "zsh".to_string()],
         config.clone(),
         log.clone(),
+        &crate::core::InstallOptions::default(),
     )
     .await;
     parallel_upgrade(
@@ -58,13 +75,15 @@ async fn main() {
     #[cfg(debug_assertions)]
     tokio::spawn(test_parallel_runners());
     let cli = Cli::parse();
+    let options = core::InstallOptions::from_cli(&cli);
 
     // Handle pacman-style flags first (-Sy, -Syu, -S <pkg>, etc.)
     if let Some(packages) = &cli.sync {
         if cli.refresh && cli.upgrade {
             // -Syu: refresh database and upgrade all
             println!("🔄 Refreshing package database and upgrading all packages...");
-            core::handle_upgrade_all();
+            core::handle_sync_db();
+            finish(&core::handle_upgrade_all(&options).await);
             return;
         } else if cli.refresh {
             // -Sy: refresh database only
@@ -72,36 +91,43 @@ async fn main() {
             core::handle_sync_db();
             if !packages.is_empty() {
                 // -Sy <pkg>: refresh then install packages
+                let mut outcomes = Vec::new();
                 for pkg in packages {
                     let config = std::sync::Arc::new(config::ReapConfig::load());
                     let log = std::sync::Arc::new(tui::LogPane::default());
-                    let options = core::InstallOptions::default();
-                    core::install_with_priority(pkg, config, true, log, &options).await;
+                    outcomes
+                        .push(core::install_with_priority(pkg, config, true, log, &options).await);
                 }
+                finish(&outcomes);
             }
             return;
         } else if cli.upgrade {
             // -Su: upgrade packages
-            core::handle_upgrade_all();
+            finish(&core::handle_upgrade_all(&options).await);
             return;
         } else if !packages.is_empty() {
             // -S <pkg>: install packages
+            let mut outcomes = Vec::new();
             for pkg in packages {
                 let config = std::sync::Arc::new(config::ReapConfig::load());
                 let log = std::sync::Arc::new(tui::LogPane::default());
-                let options = core::InstallOptions::default();
-                core::install_with_priority(pkg, config, true, log, &options).await;
+                outcomes.push(core::install_with_priority(pkg, config, true, log, &options).await);
             }
+            finish(&outcomes);
             return;
         }
     }
 
+    if let Some(spec) = &cli.downgrade {
+        // --downgrade PKG=VER
+        let outcome = core::handle_downgrade(spec, &options).await;
+        finish(&[outcome]);
+        return;
+    }
+
     if let Some(packages) = &cli.remove {
         // -R <pkg>: remove packages
-        let interactive = crate::interactive::InteractiveManager::new();
-        if interactive.confirm_removal(packages) {
-            core::handle_removal(packages);
-        }
+        core::handle_removal(packages, &options);
         return;
     }
 
@@ -118,10 +144,13 @@ async fn main() {
     }
 
     // All install/upgrade flows use Reap's own async/parallel logic (no yay/paru fallback)
-    if let Err(e) = core::handle_cli(&cli).await {
-        eprintln!("[reap] CLI error: {e}");
-        std::process::exit(1);
-    }
+    let mut outcomes = match core::handle_cli(&cli).await {
+        Ok(outcomes) => outcomes,
+        Err(e) => {
+            eprintln!("[reap] CLI error: {e}");
+            std::process::exit(1);
+        }
+    };
     let config = config::ReapConfig::load();
     println!(
         "[main] Loaded config with parallel level: {}",
@@ -132,10 +161,11 @@ async fn main() {
             // Use the backend trait's audit method
             let backend = backend::AurBackend::new();
             tokio::spawn(async move {
-                backend.audit(&pkg).await;
+                if let Err(e) = backend.audit(&pkg).await {
+                    eprintln!("[reap] Audit failed for {}: {}", pkg, e);
+                }
             });
         }
-        Commands::Rollback { pkg } => core::handle_rollback(&pkg),
         Commands::SyncDb => println!("Syncing pacman database..."),
         Commands::Pin { pkg } => {
             if let Err(e) = crate::utils::pin_package(&pkg) {
@@ -197,8 +227,9 @@ async fn main() {
             let trust_engine = trust::TrustEngine::new();
             match cmd {
                 cli::TrustCmd::Score { pkg } => {
-                    let source =
-                        core::detect_source(&pkg, None, false).unwrap_or(core::Source::Aur);
+                    let source = core::detect_source(&pkg, None, false)
+                        .await
+                        .unwrap_or(core::Source::Aur);
                     let trust_score = trust_engine.compute_trust_score(&pkg, &source).await;
                     let badge = trust_engine.display_trust_badge(trust_score.overall_score);
                     println!(
@@ -221,37 +252,6 @@ async fn main() {
                 }
             }
         }
-        Commands::Install {
-            pkg,
-            repo: _,
-            binary_only: _,
-            diff,
-        } => {
-            let config = std::sync::Arc::new(config::ReapConfig::load());
-            let log = std::sync::Arc::new(tui::LogPane::default());
-
-            if diff {
-                // Show PKGBUILD diff before install
-                core::show_pkgbuild_diff(&pkg);
-
-                if !interactive::InteractiveManager::confirm_action(
-                    "Continue with installation?",
-                    true,
-                ) {
-                    return;
-                }
-            }
-
-            // Backup package state before install
-            if let Err(e) = core::backup_package_state(&pkg) {
-                eprintln!("[backup] Warning: Failed to backup package state: {}", e);
-            }
-
-            // Use priority-based install
-            let options = core::InstallOptions::default();
-            core::install_with_priority(&pkg, config, true, log, &options).await;
-        }
-
         Commands::Rate {
             pkg,
             rating,
@@ -315,33 +315,24 @@ async fn main() {
                     "[batch] Installing {} packages in parallel",
                     pkgs.len()
                 ));
-                core::parallel_install(&pkgs, config, log).await;
+                outcomes.extend(core::parallel_install(&pkgs, config, log, &options).await);
             } else {
                 for pkg in pkgs {
                     log.push(&format!("[batch] Installing {}", pkg));
-                    let options = core::InstallOptions::default();
-                    core::install_with_priority(&pkg, config.clone(), true, log.clone(), &options)
-                        .await;
+                    let outcome = core::install_with_priority(
+                        &pkg,
+                        config.clone(),
+                        true,
+                        log.clone(),
+                        &options,
+                    )
+                    .await;
+                    outcomes.push(outcome);
                 }
             }
         }
-        Commands::Remove { pkgs } => {
-            let interactive = interactive::InteractiveManager::new();
-            if interactive.confirm_removal(&pkgs) {
-                core::handle_removal(&pkgs);
-            }
-        }
-        Commands::Local { pkgs } => {
-            core::handle_local_install(&pkgs);
-        }
-        Commands::Search { terms } => {
-            core::handle_search(&terms);
-        }
         Commands::Update => {
-            core::handle_update();
-        }
-        Commands::Upgrade { parallel } => {
-            core::handle_upgrade(parallel);
+            core::handle_update().await;
         }
         Commands::ParallelUpgrade { pkgs } => {
             let config = std::sync::Arc::new(config::ReapConfig::load());
@@ -351,13 +342,7 @@ async fn main() {
                 "[parallel] Upgrading {} packages in parallel",
                 pkgs.len()
             ));
-            core::parallel_upgrade(&pkgs, config, log).await;
-        }
-        Commands::UpgradeAll => {
-            core::handle_upgrade_all();
-        }
-        Commands::FlatpakUpgrade => {
-            println!("Upgrading Flatpak packages...");
+            outcomes.extend(core::parallel_upgrade(&pkgs, config, log).await);
         }
         Commands::Clean => {
             core::handle_clean();
@@ -367,9 +352,6 @@ async fn main() {
                 Err(e) => eprintln!("[clean] Error: {}", e),
             }
         }
-        Commands::Doctor => {
-            core::handle_doctor();
-        }
         Commands::Perf { cmd } => match cmd {
             cli::PerfCmd::WarmCache => {
                 println!("[perf] Warming cache with popular packages...");
@@ -542,60 +524,31 @@ async fn main() {
                 println!("Auditing Flatpak package: {}", pkg);
             }
         },
-        Commands::Tap { cmd } => match cmd {
-            cli::TapCmd::Add {
-                name,
-                url,
-                priority,
-            } => {
-                crate::tap::add_or_update_tap(&name, &url, Some(priority as u8), true);
-            }
-            cli::TapCmd::Remove { name } => {
-                crate::tap::remove_tap(&name);
-            }
-            cli::TapCmd::Enable { name } => {
-                crate::tap::set_tap_enabled(&name, true);
-            }
-            cli::TapCmd::Disable { name } => {
-                crate::tap::set_tap_enabled(&name, false);
-            }
-            cli::TapCmd::Update => {
-                crate::tap::sync_taps();
-            }
-            cli::TapCmd::Sync => {
-                crate::tap::sync_taps();
-            }
-            cli::TapCmd::List => {
-                crate::tap::list_taps();
-            }
-        },
-        Commands::Completion { shell } => {
-            println!("Generating completion for shell: {}", shell);
-        }
-        Commands::Backup => {
-            println!("Backing up configuration...");
-        }
-        Commands::Orphan { remove, all } => {
-            core::handle_orphan(remove, all);
-        }
-        Commands::Config { cmd } => match cmd {
-            cli::ConfigCmd::Show => {
-                let config = config::ReapConfig::load();
-                println!("Current configuration: {:#?}", config);
-            }
-            cli::ConfigCmd::Set { key, value } => {
-                crate::config::set_config_key(&key, &value);
-            }
-            cli::ConfigCmd::Get { key } => {
-                if let Some(val) = crate::config::get_config_key(&key) {
-                    println!("{} = {}", key, val);
-                } else {
-                    println!("Key '{}' not found in config.", key);
-                }
-            }
-            cli::ConfigCmd::Reset => {
-                crate::config::reset_config();
-            }
-        },
+        // Handled by core::handle_cli
+        Commands::Install { .. }
+        | Commands::Upgrade { .. }
+        | Commands::UpgradeAll
+        | Commands::Remove { .. }
+        | Commands::Local { .. }
+        | Commands::Search { .. }
+        | Commands::FlatpakUpgrade
+        | Commands::Doctor
+        | Commands::Orphan { .. }
+        | Commands::Tap { .. }
+        | Commands::Config { .. }
+        | Commands::Completion { .. }
+        | Commands::Backup
+        | Commands::Repo { .. }
+        | Commands::Recover
+        | Commands::Rollback { .. }
+        | Commands::Mark { .. } => {}
+    }
+    finish(&outcomes);
+}
+
+/// Print the run summary and exit non-zero if any package failed
+fn finish(outcomes: &[core::InstallOutcome]) {
+    if core::print_summary(outcomes) {
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,88 @@
+// Make-only dependencies installed for builds, kept until they are removed
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Record of the packages reap installed only to build something.
+///
+/// Each entry maps what was being built to the make-only dependencies installed for it, so
+/// they can be offered for removal after that transaction or any later one.
+#[derive(Debug, Clone)]
+pub struct MakeDeps {
+    path: PathBuf,
+}
+
+impl MakeDeps {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.local/share/reap/makedepends.json`
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("reap/makedepends.json")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every entry; a missing or unreadable record is empty
+    pub fn load(&self) -> BTreeMap<String, BTreeSet<String>> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Every recorded package, sorted and without duplicates
+    pub fn all(&self) -> Vec<String> {
+        let all: BTreeSet<String> = self.load().into_values().flatten().collect();
+        all.into_iter().collect()
+    }
+
+    /// Record `deps` as installed to build `built_for`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be written.
+    pub fn record(&self, built_for: &str, deps: &[String]) -> std::io::Result<()> {
+        if deps.is_empty() {
+            return Ok(());
+        }
+        let mut entries = self.load();
+        entries
+            .entry(built_for.to_string())
+            .or_default()
+            .extend(deps.iter().cloned());
+        self.save(&entries)
+    }
+
+    /// Drop `deps` from every entry, removing entries left empty
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be written.
+    pub fn forget(&self, deps: &[String]) -> std::io::Result<()> {
+        let mut entries = self.load();
+        for recorded in entries.values_mut() {
+            recorded.retain(|dep| !deps.contains(dep));
+        }
+        entries.retain(|_, recorded| !recorded.is_empty());
+        self.save(&entries)
+    }
+
+    fn save(&self, entries: &BTreeMap<String, BTreeSet<String>>) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(entries).map_err(std::io::Error::other)?;
+        std::fs::write(&self.path, content)
+    }
+}
+
+impl Default for MakeDeps {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
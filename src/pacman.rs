@@ -1,60 +1,209 @@
 // Pacman repo logic
+use crate::core::ReapError;
+use crate::interactive::InteractiveManager;
+use crate::pacman_db::{InstallReason, PacmanDb};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
 use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// pacman's database lock, present while a transaction is running
+pub const DB_LOCK: &str = "/var/lib/pacman/db.lck";
+
+/// How long to wait for another pacman to release the database lock
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Held by reap for the duration of each of its own pacman transactions
+static TRANSACTION: Mutex<()> = Mutex::new(());
+
+/// A process holding pacman's database lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub command: String,
+}
+
+impl fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pid {})", self.command, self.pid)
+    }
+}
+
+/// Queue behind reap's other transactions, then wait for any other pacman to release the
+/// database lock.
+///
+/// While waiting a countdown is shown and, on a terminal, the user is offered the process
+/// holding the lock. Keep the guard for one pacman transaction, so concurrent tasks run
+/// their transactions one after another instead of failing on `db.lck`.
+///
+/// # Errors
+///
+/// Returns [`ReapError::DatabaseLocked`] if the lock is still held after [`LOCK_TIMEOUT`].
+pub fn lock_transaction() -> Result<MutexGuard<'static, ()>, ReapError> {
+    let guard = TRANSACTION.lock().unwrap_or_else(|e| e.into_inner());
+    wait_for_lock(Path::new(DB_LOCK), LOCK_TIMEOUT)?;
+    Ok(guard)
+}
+
+/// Run `sudo pacman <args>` as one serialised reap transaction.
+///
+/// Every reap call that changes the pacman database goes through here.
+///
+/// # Errors
+///
+/// Returns an error if the lock is not released in time, pacman cannot be run or exits
+/// unsuccessfully.
+pub fn run<I, S>(args: I) -> Result<(), ReapError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let args: Vec<OsString> = args.into_iter().map(|a| a.as_ref().to_owned()).collect();
+    let _transaction = lock_transaction()?;
+    let status = Command::new("sudo").arg("pacman").args(&args).status()?;
+    if !status.success() {
+        let args: Vec<_> = args.iter().map(|a| a.to_string_lossy()).collect();
+        return Err(ReapError::CommandFailed(format!(
+            "pacman {} exited with {}",
+            args.join(" "),
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Block until `lock` is gone, counting down to `timeout`
+fn wait_for_lock(lock: &Path, timeout: Duration) -> Result<(), ReapError> {
+    if !lock.exists() {
+        return Ok(());
+    }
+    println!(
+        "[pacman] {} exists: another pacman transaction is running.",
+        lock.display()
+    );
+    if std::io::stdin().is_terminal()
+        && InteractiveManager::confirm_action("Show the process holding the lock?", false)
+    {
+        println!("[pacman] {}", describe_owner(lock));
+    }
+    let deadline = Instant::now() + timeout;
+    while lock.exists() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            println!();
+            return Err(ReapError::DatabaseLocked(describe_owner(lock)));
+        }
+        print!(
+            "\r[pacman] Waiting for the database lock... {:>3}s ",
+            remaining.as_secs()
+        );
+        let _ = std::io::stdout().flush();
+        std::thread::sleep(remaining.min(Duration::from_millis(500)));
+    }
+    println!("\r[pacman] Database lock released.            ");
+    Ok(())
+}
+
+fn describe_owner(lock: &Path) -> String {
+    match lock_owner(Path::new("/proc"), lock) {
+        Some(owner) => format!("locked by {}", owner),
+        None => format!(
+            "no running process holds it; if it is stale remove it with: sudo rm {}",
+            lock.display()
+        ),
+    }
+}
+
+/// The process under `proc_root` (normally `/proc`) that has `lock` open.
+///
+/// Another user's file descriptors are not readable, so a running `pacman` is reported
+/// when no descriptor matches.
+pub fn lock_owner(proc_root: &Path, lock: &Path) -> Option<LockOwner> {
+    let mut pacman = None;
+    for entry in std::fs::read_dir(proc_root).ok()?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        let command = std::fs::read_to_string(entry.path().join("comm"))
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let holds_lock = std::fs::read_dir(entry.path().join("fd"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| target == lock));
+        if holds_lock {
+            return Some(LockOwner { pid, command });
+        }
+        if command == "pacman" && pacman.is_none() {
+            pacman = Some(LockOwner { pid, command });
+        }
+    }
+    pacman
+}
 
 /// Install a package from the official repositories using pacman
-pub fn install(package: &str) {
+///
+/// # Errors
+///
+/// Returns an error if pacman cannot be run or exits unsuccessfully.
+pub fn install(package: &str) -> Result<(), ReapError> {
     println!("[pacman] Installing package: {}", package);
-    let status = Command::new("sudo")
-        .arg("pacman")
-        .arg("-S")
-        .arg("--noconfirm")
-        .arg(package)
-        .status();
-    if let Ok(s) = status {
-        if s.success() {
-            println!("[pacman] {} installed successfully!", package);
-        } else {
-            eprintln!("[pacman] pacman failed for {}", package);
-        }
-    } else {
-        eprintln!("[pacman] failed to run pacman for {}", package);
+    if let Err(e) = run(["-S", "--noconfirm", package]) {
+        eprintln!("[pacman] pacman failed for {}", package);
+        return Err(e);
     }
+    println!("[pacman] {} installed successfully!", package);
+    Ok(())
 }
 
+/// Change the install reason of installed packages (`pacman -D`)
+///
+/// # Errors
+///
+/// Returns an error if the database is locked or pacman fails.
+pub fn set_install_reason(pkgs: &[String], reason: InstallReason) -> Result<(), ReapError> {
+    if pkgs.is_empty() {
+        return Ok(());
+    }
+    let flag = match reason {
+        InstallReason::Explicit => "--asexplicit",
+        InstallReason::Dependency => "--asdeps",
+    };
+    run(["-D", flag]
+        .into_iter()
+        .chain(pkgs.iter().map(String::as_str)))
+}
+
+#[allow(dead_code)]
 pub fn is_installed(pkg: &str) -> bool {
-    Command::new("pacman")
-        .arg("-Q")
-        .arg(pkg)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    PacmanDb::system().installed(pkg).is_some()
 }
 
+/// Installed version of `pkg`, read from the local database
 pub fn get_version(pkg: &str) -> Option<String> {
-    let output = Command::new("pacman").arg("-Qi").arg(pkg).output().ok()?;
-    if !output.status.success() {
-        return None;
-    }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        if line.starts_with("Version") {
-            return line.split_whitespace().nth(1).map(|s| s.to_string());
-        }
-    }
-    None
+    PacmanDb::system().installed(pkg).map(|p| p.version)
 }
 
 pub fn list_installed_aur() -> Vec<String> {
-    let output = std::process::Command::new("pacman").arg("-Qm").output();
-    if let Ok(out) = output {
-        let stdout = String::from_utf8_lossy(&out.stdout);
-        stdout
-            .lines()
-            .filter_map(|line| line.split_whitespace().next())
-            .map(|s| s.to_string())
-            .collect()
-    } else {
-        Vec::new()
+    list_foreign_versions()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Installed foreign packages (`pacman -Qm`) as `(name, version)` pairs
+pub fn list_foreign_versions() -> Vec<(String, String)> {
+    match PacmanDb::system().foreign() {
+        Ok(packages) => packages.into_iter().map(|p| (p.name, p.version)).collect(),
+        Err(e) => {
+            eprintln!("[pacman] Failed to read the local database: {}", e);
+            Vec::new()
+        }
     }
 }
 
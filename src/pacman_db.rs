@@ -0,0 +1,426 @@
+// Read-only parser for pacman's local and sync databases
+use crate::solver::provides_dep;
+use crate::version::{Dependency, Version};
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// pacman's default `DBPath`
+pub const DEFAULT_DB_PATH: &str = "/var/lib/pacman";
+
+/// Errors produced while reading a pacman database
+#[derive(Debug, Error)]
+pub enum PacmanDbError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0} has no %NAME% or %VERSION%")]
+    Incomplete(String),
+}
+
+/// Why a package is installed (`%REASON%` in the local database)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InstallReason {
+    #[default]
+    Explicit,
+    Dependency,
+}
+
+/// One package entry of a local or sync database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbPackage {
+    pub name: String,
+    /// Full `[epoch:]pkgver-pkgrel` version
+    pub version: String,
+    pub base: Option<String>,
+    pub desc: String,
+    pub provides: Vec<String>,
+    pub depends: Vec<String>,
+    /// Only recorded in sync databases
+    pub makedepends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub replaces: Vec<String>,
+    /// Always [`InstallReason::Explicit`] for sync packages
+    pub reason: InstallReason,
+    /// Installed size in bytes
+    pub installed_size: u64,
+    /// Installed paths relative to `/`, directories ending in `/` (local database only)
+    pub files: Vec<String>,
+}
+
+impl DbPackage {
+    /// Build a package from the sections of a `desc` (and optionally `files`) entry
+    fn from_sections(sections: &HashMap<String, Vec<String>>) -> Option<Self> {
+        let first = |key: &str| sections.get(key).and_then(|v| v.first()).cloned();
+        let list = |key: &str| sections.get(key).cloned().unwrap_or_default();
+        let size = first("%SIZE%").or_else(|| first("%ISIZE%"));
+        Some(DbPackage {
+            name: first("%NAME%")?,
+            version: first("%VERSION%")?,
+            base: first("%BASE%"),
+            desc: first("%DESC%").unwrap_or_default(),
+            provides: list("%PROVIDES%"),
+            depends: list("%DEPENDS%"),
+            makedepends: list("%MAKEDEPENDS%"),
+            optdepends: list("%OPTDEPENDS%"),
+            conflicts: list("%CONFLICTS%"),
+            replaces: list("%REPLACES%"),
+            reason: match first("%REASON%").as_deref() {
+                Some("1") => InstallReason::Dependency,
+                _ => InstallReason::Explicit,
+            },
+            installed_size: size.and_then(|s| s.parse().ok()).unwrap_or(0),
+            files: list("%FILES%"),
+        })
+    }
+
+    /// Whether this package satisfies `dep` by name or through `provides`
+    pub fn satisfies(&self, dep: &Dependency) -> bool {
+        provides_dep(
+            &self.name,
+            &Version::parse(&self.version),
+            &self.provides,
+            dep,
+        )
+    }
+}
+
+/// The packages of one database, keyed by name
+#[derive(Debug, Clone, Default)]
+pub struct PackageDb {
+    /// Repository name, `None` for the local database
+    pub repo: Option<String>,
+    packages: BTreeMap<String, DbPackage>,
+}
+
+impl PackageDb {
+    pub fn get(&self, name: &str) -> Option<&DbPackage> {
+        self.packages.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.packages.contains_key(name)
+    }
+
+    /// Packages sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = &DbPackage> {
+        self.packages.values()
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    /// Packages satisfying `dep`, by name or through `provides`
+    pub fn providers(&self, dep: &Dependency) -> Vec<&DbPackage> {
+        self.iter().filter(|pkg| pkg.satisfies(dep)).collect()
+    }
+
+    /// Package owning `path` (absolute or relative to `/`); needs files to have been loaded
+    #[allow(dead_code)]
+    pub fn owner(&self, path: &str) -> Option<&DbPackage> {
+        let path = path.trim_start_matches('/');
+        self.iter().find(|pkg| pkg.files.iter().any(|f| f == path))
+    }
+
+    /// For every package, the installed packages satisfying its `depends` and `optdepends`
+    pub fn dependency_edges(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut by_name: HashMap<String, Vec<&DbPackage>> = HashMap::new();
+        for pkg in self.iter() {
+            by_name.entry(pkg.name.clone()).or_default().push(pkg);
+            for provided in &pkg.provides {
+                let provided = Dependency::parse(provided).name;
+                by_name.entry(provided).or_default().push(pkg);
+            }
+        }
+        self.iter()
+            .map(|pkg| {
+                let satisfiers = pkg
+                    .depends
+                    .iter()
+                    .chain(&pkg.optdepends)
+                    .map(|dep| Dependency::parse(dep))
+                    .flat_map(|dep| {
+                        let providers = by_name.get(&dep.name).into_iter().flatten();
+                        providers
+                            .filter(move |p| p.satisfies(&dep))
+                            .map(|p| p.name.clone())
+                    })
+                    .filter(|name| *name != pkg.name)
+                    .collect();
+                (pkg.name.clone(), satisfiers)
+            })
+            .collect()
+    }
+
+    /// Names of `roots` and everything they need, following `depends` and `optdepends`
+    /// through installed providers until nothing new is reached
+    pub fn required_by(&self, roots: &[String]) -> BTreeSet<String> {
+        let edges = self.dependency_edges();
+        let mut reached: BTreeSet<String> = BTreeSet::new();
+        let mut queue: Vec<&String> = roots.iter().filter(|name| self.contains(name)).collect();
+        while let Some(name) = queue.pop() {
+            if reached.insert(name.clone()) {
+                queue.extend(edges.get(name).into_iter().flatten());
+            }
+        }
+        reached
+    }
+
+    /// Packages installed as dependencies that nothing explicitly installed or in `keep`
+    /// needs any more, directly or through other orphans, sorted by name
+    ///
+    /// Optional dependencies of a needed package are needed too, as with `pacman -Qdt`.
+    pub fn orphans(&self, keep: &[String]) -> Vec<&DbPackage> {
+        let roots: Vec<String> = self
+            .iter()
+            .filter(|pkg| pkg.reason == InstallReason::Explicit || keep.contains(&pkg.name))
+            .map(|pkg| pkg.name.clone())
+            .collect();
+        let needed = self.required_by(&roots);
+        self.iter()
+            .filter(|pkg| !needed.contains(&pkg.name))
+            .collect()
+    }
+
+    fn insert(&mut self, package: DbPackage) {
+        self.packages.insert(package.name.clone(), package);
+    }
+}
+
+/// The databases under a pacman `DBPath`: `local/<pkg>/desc` and `sync/<repo>.db`.
+///
+/// Reading them directly replaces one `pacman -Q`/`-S` process per query. The path is
+/// configurable so fixture databases can be used.
+#[derive(Debug, Clone)]
+pub struct PacmanDb {
+    db_path: PathBuf,
+    repos: Vec<String>,
+}
+
+impl PacmanDb {
+    /// Databases under `db_path`, with the sync repositories in priority order
+    pub fn new(db_path: impl Into<PathBuf>, repos: Vec<String>) -> Self {
+        Self {
+            db_path: db_path.into(),
+            repos,
+        }
+    }
+
+    /// The system databases, with the repositories configured in `/etc/pacman.conf`
+    pub fn system() -> Self {
+        let conf = std::fs::read_to_string("/etc/pacman.conf").unwrap_or_default();
+        Self::new(DEFAULT_DB_PATH, repos_from_conf(&conf))
+    }
+
+    /// Every installed package, without file lists
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the local database cannot be read.
+    pub fn local(&self) -> Result<PackageDb, PacmanDbError> {
+        self.read_local(false)
+    }
+
+    /// Every installed package with its file list
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the local database cannot be read.
+    #[allow(dead_code)]
+    pub fn local_with_files(&self) -> Result<PackageDb, PacmanDbError> {
+        self.read_local(true)
+    }
+
+    /// The installed package `name`, reading only its own entry
+    pub fn installed(&self, name: &str) -> Option<DbPackage> {
+        read_local_entry(&self.local_entry(name)?, false).ok()
+    }
+
+    /// The installed package `name` with its file list (`pacman -Ql`)
+    pub fn installed_with_files(&self, name: &str) -> Option<DbPackage> {
+        read_local_entry(&self.local_entry(name)?, true).ok()
+    }
+
+    /// Directory of the installed package `name` in the local database
+    pub fn local_entry(&self, name: &str) -> Option<PathBuf> {
+        let prefix = format!("{}-", name);
+        // Entries are `<name>-<pkgver>-<pkgrel>` and neither version part contains a `-`
+        std::fs::read_dir(self.db_path.join("local"))
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_prefix(&prefix))
+                    .is_some_and(|version| version.matches('-').count() == 1)
+            })
+    }
+
+    /// The packages of sync repository `repo`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `sync/<repo>.db` cannot be read or has a malformed entry.
+    pub fn sync(&self, repo: &str) -> Result<PackageDb, PacmanDbError> {
+        let path = self.sync_path(repo);
+        let mut db = PackageDb {
+            repo: Some(repo.to_string()),
+            ..Default::default()
+        };
+        let mut archive = tar::Archive::new(open_db_archive(&path)?);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let entry_path = entry.path()?.to_string_lossy().to_string();
+            if !entry_path.ends_with("/desc") {
+                continue;
+            }
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            let package = DbPackage::from_sections(&parse_sections(&content))
+                .ok_or(PacmanDbError::Incomplete(entry_path))?;
+            db.insert(package);
+        }
+        Ok(db)
+    }
+
+    /// Sync repository `repo`, parsed again only when the database file changes (`-Sy`)
+    pub fn sync_cached(&self, repo: &str) -> Option<Arc<PackageDb>> {
+        type Cache = HashMap<PathBuf, (SystemTime, Arc<PackageDb>)>;
+        static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+        let path = self.sync_path(repo);
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let mut cache = CACHE
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let fresh = cache
+            .get(&path)
+            .filter(|(cached_at, _)| *cached_at == modified);
+        if let Some((_, db)) = fresh {
+            return Some(Arc::clone(db));
+        }
+        let db = Arc::new(self.sync(repo).ok()?);
+        cache.insert(path, (modified, Arc::clone(&db)));
+        Some(db)
+    }
+
+    /// Every readable sync repository, in priority order
+    pub fn sync_all(&self) -> Vec<Arc<PackageDb>> {
+        self.repos
+            .iter()
+            .filter_map(|repo| self.sync_cached(repo))
+            .collect()
+    }
+
+    /// Sync repository that has a package named `name`, in priority order
+    pub fn sync_repo_of(&self, name: &str) -> Option<String> {
+        self.sync_all()
+            .iter()
+            .find(|db| db.contains(name))
+            .and_then(|db| db.repo.clone())
+    }
+
+    /// Installed packages found in no sync repository (`pacman -Qm`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the local database cannot be read.
+    pub fn foreign(&self) -> Result<Vec<DbPackage>, PacmanDbError> {
+        let sync = self.sync_all();
+        Ok(self
+            .local()?
+            .iter()
+            .filter(|pkg| !sync.iter().any(|db| db.contains(&pkg.name)))
+            .cloned()
+            .collect())
+    }
+
+    fn sync_path(&self, repo: &str) -> PathBuf {
+        self.db_path.join("sync").join(format!("{}.db", repo))
+    }
+
+    fn read_local(&self, files: bool) -> Result<PackageDb, PacmanDbError> {
+        let mut db = PackageDb::default();
+        for entry in std::fs::read_dir(self.db_path.join("local"))?.flatten() {
+            if entry.path().join("desc").is_file() {
+                db.insert(read_local_entry(&entry.path(), files)?);
+            }
+        }
+        Ok(db)
+    }
+}
+
+impl Default for PacmanDb {
+    fn default() -> Self {
+        Self::system()
+    }
+}
+
+/// Repository sections of a pacman.conf, in order
+pub fn repos_from_conf(conf: &str) -> Vec<String> {
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix('[')?.strip_suffix(']'))
+        .filter(|section| *section != "options")
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse `%SECTION%` headers followed by one value per line, ended by a blank line
+fn parse_sections(content: &str) -> HashMap<String, Vec<String>> {
+    let mut sections: HashMap<String, Vec<String>> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        if line.is_empty() {
+            current = None;
+        } else if line.starts_with('%') && line.ends_with('%') && line.len() > 1 {
+            sections.entry(line.to_string()).or_default();
+            current = Some(line.to_string());
+        } else if let Some(section) = &current {
+            sections
+                .entry(section.clone())
+                .or_default()
+                .push(line.to_string());
+        }
+    }
+    sections
+}
+
+/// Read `desc`, plus `depends` from older databases and `files` when wanted
+fn read_local_entry(dir: &Path, files: bool) -> Result<DbPackage, PacmanDbError> {
+    let mut content = std::fs::read_to_string(dir.join("desc"))?;
+    let extra: &[&str] = if files {
+        &["depends", "files"]
+    } else {
+        &["depends"]
+    };
+    for name in extra {
+        if let Ok(more) = std::fs::read_to_string(dir.join(name)) {
+            content.push('\n');
+            content.push_str(&more);
+        }
+    }
+    DbPackage::from_sections(&parse_sections(&content))
+        .ok_or_else(|| PacmanDbError::Incomplete(dir.display().to_string()))
+}
+
+/// Open a sync database, which is a tar archive that is usually gzip-compressed
+fn open_db_archive(path: &Path) -> Result<Box<dyn Read>, PacmanDbError> {
+    let data = std::fs::read(path)?;
+    if data.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(GzDecoder::new(std::io::Cursor::new(data))))
+    } else {
+        Ok(Box::new(std::io::Cursor::new(data)))
+    }
+}
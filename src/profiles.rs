@@ -1,5 +1,6 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -14,6 +15,21 @@ pub struct ProfileConfig {
     pub fast_mode: Option<bool>,
     pub strict_signatures: Option<bool>,
     pub auto_resolve_deps: Option<bool>,
+    /// Accept changes to AUR packages without the review prompt
+    #[serde(default)]
+    pub skip_review: Option<bool>,
+    /// Build AUR and tap packages in a clean devtools chroot
+    #[serde(default)]
+    pub chroot_build: Option<bool>,
+    /// Remove make-only dependencies after a successful build without asking
+    #[serde(default)]
+    pub remove_make_deps: Option<bool>,
+    /// Packages never removed as orphans, along with everything they depend on
+    #[serde(default)]
+    pub keep_packages: Vec<String>,
+    /// Preferred package per virtual dependency, e.g. `java-runtime = "jdk21-openjdk"`
+    #[serde(default)]
+    pub provider_preferences: HashMap<String, String>,
 }
 
 impl Default for ProfileConfig {
@@ -28,6 +44,11 @@ impl Default for ProfileConfig {
             fast_mode: Some(false),
             strict_signatures: Some(false),
             auto_resolve_deps: Some(true),
+            skip_review: Some(false),
+            chroot_build: Some(false),
+            remove_make_deps: Some(false),
+            keep_packages: vec![],
+            provider_preferences: HashMap::new(),
         }
     }
 }
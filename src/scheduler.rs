@@ -0,0 +1,205 @@
+// Dependency-aware parallel scheduling of planned builds
+use crate::core::OutcomeStatus;
+use crate::solver::{BuildPlan, PlannedBuild};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Builds and installs the package bases of a plan for the [`Scheduler`]
+pub trait BuildExecutor: Sync {
+    /// Build one package base and return the archives to install.
+    ///
+    /// `deps` holds the archives of every earlier build it needs, for builds that cannot
+    /// see the host's installed packages (chroot builds).
+    fn build(&self, build: &PlannedBuild, deps: &[PathBuf]) -> Result<Vec<PathBuf>, String>;
+
+    /// Install built archives in one transaction; calls never overlap
+    fn install(&self, archives: &[PathBuf]) -> Result<(), String>;
+}
+
+/// How one planned build ended
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    pub pkgbase: String,
+    pub pkgnames: Vec<String>,
+    pub version: String,
+    pub status: OutcomeStatus,
+    /// Time spent building (zero when skipped)
+    pub duration: Duration,
+}
+
+/// Runs the builds of a [`BuildPlan`] concurrently while respecting their dependencies.
+///
+/// Up to `jobs` builds run at once, each starting as soon as the builds it needs are done.
+/// Built archives go through a single install queue that installs whatever is waiting in
+/// one transaction. When a build or install fails, everything depending on it is skipped.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    jobs: usize,
+    install_before_dependents: bool,
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    Pending,
+    Running,
+    /// Built and waiting for the install queue
+    Queued,
+    Installing,
+    Installed,
+    Failed(String),
+    Skipped(String),
+}
+
+enum Event {
+    Built(usize, Result<Vec<PathBuf>, String>, Duration),
+    Installed(Vec<usize>, Result<(), String>),
+}
+
+impl Scheduler {
+    /// `install_before_dependents` is set for host builds, which need their dependencies
+    /// installed; chroot builds have them injected and only wait for the build.
+    pub fn new(jobs: usize, install_before_dependents: bool) -> Self {
+        Self {
+            jobs: jobs.max(1),
+            install_before_dependents,
+        }
+    }
+
+    /// Build and install every package base of `plan`, returning a report per build in
+    /// plan order
+    pub fn run(&self, plan: &BuildPlan, executor: &dyn BuildExecutor) -> Vec<BuildReport> {
+        let builds = &plan.builds;
+        let needs: Vec<Vec<usize>> = (0..builds.len())
+            .map(|i| {
+                (0..i)
+                    .filter(|&j| builds[i].build_needs(&builds[j]))
+                    .collect()
+            })
+            .collect();
+        let mut states = vec![State::Pending; builds.len()];
+        let mut archives: Vec<Vec<PathBuf>> = vec![Vec::new(); builds.len()];
+        let mut durations = vec![Duration::ZERO; builds.len()];
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::scope(|scope| {
+            let mut running = 0;
+            let mut installing = false;
+            loop {
+                for i in 0..builds.len() {
+                    if running >= self.jobs {
+                        break;
+                    }
+                    let ready = matches!(states[i], State::Pending)
+                        && needs[i].iter().all(|&j| self.satisfied(&states[j]));
+                    if !ready {
+                        continue;
+                    }
+                    let deps: Vec<PathBuf> = plan
+                        .needed_before(i)
+                        .into_iter()
+                        .flat_map(|j| archives[j].iter().cloned())
+                        .collect();
+                    states[i] = State::Running;
+                    running += 1;
+                    let (tx, build) = (tx.clone(), &builds[i]);
+                    scope.spawn(move || {
+                        let start = Instant::now();
+                        let result = executor.build(build, &deps);
+                        let _ = tx.send(Event::Built(i, result, start.elapsed()));
+                    });
+                }
+
+                let queued: Vec<usize> = (0..builds.len())
+                    .filter(|&i| matches!(states[i], State::Queued))
+                    .collect();
+                if !installing && !queued.is_empty() {
+                    installing = true;
+                    let batch: Vec<PathBuf> = queued
+                        .iter()
+                        .flat_map(|&i| archives[i].iter().cloned())
+                        .collect();
+                    for &i in &queued {
+                        states[i] = State::Installing;
+                    }
+                    let tx = tx.clone();
+                    scope.spawn(move || {
+                        let result = executor.install(&batch);
+                        let _ = tx.send(Event::Installed(queued, result));
+                    });
+                }
+
+                if running == 0 && !installing {
+                    break;
+                }
+                match rx.recv() {
+                    Ok(Event::Built(i, result, duration)) => {
+                        running -= 1;
+                        durations[i] = duration;
+                        states[i] = match result {
+                            Ok(packages) => {
+                                archives[i] = packages;
+                                State::Queued
+                            }
+                            Err(e) => State::Failed(e),
+                        };
+                    }
+                    Ok(Event::Installed(batch, result)) => {
+                        installing = false;
+                        for i in batch {
+                            states[i] = match &result {
+                                Ok(()) => State::Installed,
+                                Err(e) => State::Failed(format!("install failed: {}", e)),
+                            };
+                        }
+                    }
+                    Err(_) => break,
+                }
+                skip_dependents(plan, &needs, &mut states);
+            }
+        });
+
+        builds
+            .iter()
+            .zip(states)
+            .zip(durations)
+            .map(|((build, state), duration)| BuildReport {
+                pkgbase: build.pkgbase.clone(),
+                pkgnames: build.pkgnames.clone(),
+                version: build.version.clone(),
+                status: match state {
+                    State::Installed => OutcomeStatus::Installed,
+                    State::Skipped(reason) => OutcomeStatus::Skipped(reason),
+                    State::Failed(reason) => OutcomeStatus::Failed(reason),
+                    _ => OutcomeStatus::Failed("not built".to_string()),
+                },
+                duration,
+            })
+            .collect()
+    }
+
+    /// Whether a dependency in `state` lets its dependents start building
+    fn satisfied(&self, state: &State) -> bool {
+        match state {
+            State::Installed => true,
+            State::Queued | State::Installing => !self.install_before_dependents,
+            _ => false,
+        }
+    }
+}
+
+/// Skip every pending build that needs a failed or skipped one. Builds only need earlier
+/// builds, so one pass in plan order reaches transitive dependents.
+fn skip_dependents(plan: &BuildPlan, needs: &[Vec<usize>], states: &mut [State]) {
+    for i in 0..states.len() {
+        if !matches!(states[i], State::Pending) {
+            continue;
+        }
+        let blocked = needs[i]
+            .iter()
+            .find(|&&j| matches!(states[j], State::Failed(_) | State::Skipped(_)));
+        if let Some(&j) = blocked {
+            states[i] = State::Skipped(format!("needs {}", plan.builds[j].pkgbase));
+        }
+    }
+}
@@ -0,0 +1,764 @@
+// Recursive dependency solver producing an ordered build plan
+use crate::aur_rpc::{self, AurPackage, SearchBy};
+use crate::pacman_db::{PackageDb, PacmanDb};
+use crate::srcinfo::{SrcInfo, SrcInfoPackage};
+use crate::version::{DepOp, Dependency, Version};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Errors produced while solving dependencies
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SolveError {
+    #[error("could not resolve dependency '{dep}'{}", required_by_suffix(.required_by))]
+    Unresolvable {
+        dep: String,
+        required_by: Option<String>,
+    },
+    #[error("dependency cycle detected: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+    #[error("no provider selected for '{0}'")]
+    NoProviderSelected(String),
+}
+
+fn required_by_suffix(parent: &Option<String>) -> String {
+    parent
+        .as_ref()
+        .map(|p| format!(" (required by {})", p))
+        .unwrap_or_default()
+}
+
+/// Where a package that has to be built comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildOrigin {
+    Aur,
+    Tap(String),
+}
+
+/// A package providing a dependency that has to be built from source
+#[derive(Debug, Clone)]
+pub struct BuildCandidate {
+    pub pkgname: String,
+    pub origin: BuildOrigin,
+    pub srcinfo: SrcInfo,
+}
+
+/// One package base to build, with the split members that should be installed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedBuild {
+    pub pkgbase: String,
+    pub pkgnames: Vec<String>,
+    pub version: String,
+    pub origin: BuildOrigin,
+    pub srcinfo: SrcInfo,
+}
+
+impl PlannedBuild {
+    /// Whether building this package base needs a package built by `other` installed first
+    pub fn build_needs(&self, other: &PlannedBuild) -> bool {
+        self.srcinfo.build_depends().iter().any(|dep| {
+            let dep = Dependency::parse(dep);
+            other.pkgnames.iter().any(|name| {
+                *name == dep.name
+                    || other.srcinfo.package(name).is_some_and(|pkg| {
+                        pkg.provides
+                            .host()
+                            .iter()
+                            .any(|p| Dependency::parse(p).name == dep.name)
+                    })
+            })
+        })
+    }
+}
+
+/// Result of solving a set of targets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildPlan {
+    /// The requested targets, as given
+    pub targets: Vec<String>,
+    /// Packages to install from the sync repositories
+    pub repo: Vec<String>,
+    /// Package bases to build, dependencies before dependents
+    pub builds: Vec<PlannedBuild>,
+    /// Packages only needed to build (makedepends/checkdepends), removable afterwards
+    pub make_only: Vec<String>,
+}
+
+impl BuildPlan {
+    pub fn is_empty(&self) -> bool {
+        self.repo.is_empty() && self.builds.is_empty()
+    }
+
+    /// Whether `pkgname` was explicitly requested rather than pulled in as a dependency
+    pub fn is_target(&self, pkgname: &str) -> bool {
+        self.targets
+            .iter()
+            .any(|t| Dependency::parse(t).name == pkgname)
+    }
+
+    /// Indices of the earlier builds that `builds[index]` needs installed, directly or through
+    /// another needed build
+    pub fn needed_before(&self, index: usize) -> Vec<usize> {
+        let mut needed = Vec::new();
+        let mut queue = vec![index];
+        while let Some(current) = queue.pop() {
+            for (i, earlier) in self.builds[..current].iter().enumerate() {
+                if !needed.contains(&i) && self.builds[current].build_needs(earlier) {
+                    needed.push(i);
+                    queue.push(i);
+                }
+            }
+        }
+        needed.sort_unstable();
+        needed
+    }
+
+    /// Print the plan grouped by repo installs, builds and make-only dependencies
+    pub fn print(&self) {
+        if !self.repo.is_empty() {
+            println!(
+                "{} Repo packages ({}):",
+                "📦".bright_cyan(),
+                self.repo.len()
+            );
+            for pkg in &self.repo {
+                println!("    {}", pkg.bright_white());
+            }
+        }
+        if !self.builds.is_empty() {
+            println!(
+                "{} Build order ({}):",
+                "🔨".bright_yellow(),
+                self.builds.len()
+            );
+            for (idx, build) in self.builds.iter().enumerate() {
+                let origin = match &build.origin {
+                    BuildOrigin::Aur => "aur".to_string(),
+                    BuildOrigin::Tap(tap) => format!("tap:{}", tap),
+                };
+                println!(
+                    "    {}. {} {} [{}] -> {}",
+                    idx + 1,
+                    build.pkgbase.bright_white(),
+                    build.version.bright_green(),
+                    origin.dimmed(),
+                    build.pkgnames.join(" ")
+                );
+            }
+        }
+        if !self.make_only.is_empty() {
+            println!(
+                "{} Make-only dependencies ({}): {}",
+                "🧹".bright_blue(),
+                self.make_only.len(),
+                self.make_only.join(" ").dimmed()
+            );
+        }
+    }
+}
+
+/// Package lookups used by the solver, abstracted so plans can be computed against fixtures
+pub trait PackageLookup {
+    /// Whether an installed package (by name or `provides`) satisfies `dep`
+    fn is_installed(&self, dep: &Dependency) -> bool;
+    /// Sync repo packages satisfying `dep` by name or `provides`
+    fn repo_providers(&self, dep: &Dependency) -> Vec<String>;
+    /// Tap packages satisfying `dep`
+    fn tap_providers(&self, dep: &Dependency) -> Vec<BuildCandidate>;
+    /// AUR packages satisfying `dep` by name or `provides`
+    fn aur_providers(&self, dep: &Dependency) -> Vec<AurPackage>;
+    /// The .SRCINFO of an AUR package base
+    fn aur_srcinfo(&self, pkgbase: &str) -> Option<SrcInfo>;
+}
+
+/// A package that can satisfy a dependency
+#[derive(Debug, Clone)]
+pub enum Provider {
+    Repo(String),
+    Tap(BuildCandidate),
+    Aur(AurPackage),
+}
+
+impl Provider {
+    pub fn name(&self) -> &str {
+        match self {
+            Provider::Repo(name) => name,
+            Provider::Tap(candidate) => &candidate.pkgname,
+            Provider::Aur(pkg) => &pkg.name,
+        }
+    }
+
+    /// Name with its source, as shown when asking the user to choose
+    pub fn label(&self) -> String {
+        match self {
+            Provider::Repo(name) => format!("{} (repo)", name),
+            Provider::Tap(candidate) => match &candidate.origin {
+                BuildOrigin::Tap(tap) => format!("{} (tap:{})", candidate.pkgname, tap),
+                BuildOrigin::Aur => format!("{} (aur)", candidate.pkgname),
+            },
+            Provider::Aur(pkg) => format!("{} {} (aur)", pkg.name, pkg.version),
+        }
+    }
+}
+
+/// Picks one of several providers of a dependency
+pub trait ProviderChooser {
+    /// Index of the chosen provider, or `None` to abort
+    fn choose(&self, dep: &Dependency, providers: &[Provider]) -> Option<usize>;
+}
+
+/// Provider selection driven by the profile's preference map, prompting when still ambiguous
+#[derive(Debug, Clone, Default)]
+pub struct ProviderPolicy {
+    /// Dependency name -> preferred provider package
+    pub preferences: HashMap<String, String>,
+    /// Pick the first provider instead of prompting
+    pub noconfirm: bool,
+}
+
+impl ProviderPolicy {
+    pub fn from_profile(profile: &crate::profiles::ProfileConfig, noconfirm: bool) -> Self {
+        Self {
+            preferences: profile.provider_preferences.clone(),
+            noconfirm,
+        }
+    }
+
+    /// Policy for the active profile
+    pub fn active(noconfirm: bool) -> Self {
+        let profile = crate::profiles::ProfileManager::new()
+            .get_active_profile()
+            .unwrap_or_default();
+        Self::from_profile(&profile, noconfirm)
+    }
+}
+
+impl ProviderChooser for ProviderPolicy {
+    fn choose(&self, dep: &Dependency, providers: &[Provider]) -> Option<usize> {
+        let preferred = self
+            .preferences
+            .get(&dep.name)
+            .and_then(|want| providers.iter().position(|p| p.name() == want));
+        if preferred.is_some() {
+            return preferred;
+        }
+        // A package with the exact name wins over virtual providers, as in pacman
+        if let Some(idx) = providers.iter().position(|p| p.name() == dep.name) {
+            return Some(idx);
+        }
+        if self.noconfirm {
+            return Some(0);
+        }
+        let items: Vec<String> = providers.iter().map(Provider::label).collect();
+        crate::interactive::InteractiveManager::new().select_from_list(
+            &items,
+            &format!("There are {} providers available for {}:", items.len(), dep),
+        )
+    }
+}
+
+/// Whether a package called `name` at `version` with `provides` fulfils `dep`.
+///
+/// Like pacman, an unversioned `provides` entry never satisfies a versioned dependency.
+pub fn provides_dep(name: &str, version: &Version, provides: &[String], dep: &Dependency) -> bool {
+    if name == dep.name && dep.satisfied_by(version) {
+        return true;
+    }
+    provides.iter().any(|p| {
+        let provided = Dependency::parse(p);
+        if provided.name != dep.name {
+            return false;
+        }
+        match (&dep.constraint, &provided.constraint) {
+            (None, _) => true,
+            (Some(_), Some((DepOp::Eq, v))) => dep.satisfied_by(v),
+            _ => false,
+        }
+    })
+}
+
+fn member_satisfies(srcinfo: &SrcInfo, member: &SrcInfoPackage, dep: &Dependency) -> bool {
+    provides_dep(
+        &member.pkgname,
+        &srcinfo.version(),
+        &member.provides.host(),
+        dep,
+    )
+}
+
+enum NodeKind {
+    Repo(String),
+    Build(Box<PlannedBuild>),
+}
+
+struct Node {
+    kind: NodeKind,
+    /// Dependency edges as (node, needed at runtime)
+    edges: Vec<(usize, bool)>,
+}
+
+struct Pending {
+    dep: Dependency,
+    parent: Option<usize>,
+    runtime: bool,
+}
+
+struct Solver<'a, L: PackageLookup, C: ProviderChooser> {
+    lookup: &'a L,
+    chooser: &'a C,
+    nodes: Vec<Node>,
+    /// Dependency string -> node that satisfies it (`None` when already installed)
+    resolved: HashMap<String, Option<usize>>,
+    queue: VecDeque<Pending>,
+}
+
+/// Resolve `targets` recursively into a build plan.
+///
+/// Targets are looked up in the sync repos, taps and the AUR; dependencies are first checked
+/// against installed packages. When several packages provide a dependency, an installed one
+/// is preferred, otherwise `chooser` decides.
+///
+/// # Errors
+///
+/// Returns an error if a dependency cannot be satisfied or the build graph contains a cycle.
+pub fn resolve<L: PackageLookup, C: ProviderChooser>(
+    lookup: &L,
+    chooser: &C,
+    targets: &[String],
+) -> Result<BuildPlan, SolveError> {
+    let mut solver = Solver {
+        lookup,
+        chooser,
+        nodes: Vec::new(),
+        resolved: HashMap::new(),
+        queue: VecDeque::new(),
+    };
+    let mut roots = Vec::new();
+    for target in targets {
+        let dep = Dependency::parse(target);
+        let node = solver.resolve_target(&dep)?;
+        roots.push(node);
+    }
+    solver.drain()?;
+    solver.into_plan(targets, &roots)
+}
+
+impl<L: PackageLookup, C: ProviderChooser> Solver<'_, L, C> {
+    fn resolve_target(&mut self, dep: &Dependency) -> Result<usize, SolveError> {
+        if let Some(node) = self.find_planned(dep) {
+            return Ok(node);
+        }
+        self.add_provider(dep, None)
+    }
+
+    fn drain(&mut self) -> Result<(), SolveError> {
+        while let Some(Pending {
+            dep,
+            parent,
+            runtime,
+        }) = self.queue.pop_front()
+        {
+            let key = dep.to_string();
+            let node = match self.resolved.get(&key) {
+                Some(node) => *node,
+                None => {
+                    let node = self.resolve_dep(&dep, parent)?;
+                    self.resolved.insert(key, node);
+                    node
+                }
+            };
+            match (node, parent) {
+                // Split members depending on each other are built together
+                (Some(node), Some(parent)) if node != parent => {
+                    self.nodes[parent].edges.push((node, runtime));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_dep(
+        &mut self,
+        dep: &Dependency,
+        parent: Option<usize>,
+    ) -> Result<Option<usize>, SolveError> {
+        if let Some(node) = self.find_planned(dep) {
+            return Ok(Some(node));
+        }
+        if self.lookup.is_installed(dep) {
+            return Ok(None);
+        }
+        self.add_provider(dep, parent).map(Some)
+    }
+
+    /// Collect every provider of `dep`, pick one and add it to the plan
+    fn add_provider(
+        &mut self,
+        dep: &Dependency,
+        parent: Option<usize>,
+    ) -> Result<usize, SolveError> {
+        let mut providers: Vec<Provider> = self
+            .lookup
+            .repo_providers(dep)
+            .into_iter()
+            .map(Provider::Repo)
+            .collect();
+        providers.extend(
+            self.lookup
+                .tap_providers(dep)
+                .into_iter()
+                .map(Provider::Tap),
+        );
+        providers.extend(
+            self.lookup
+                .aur_providers(dep)
+                .into_iter()
+                .map(Provider::Aur),
+        );
+        if providers.is_empty() {
+            return Err(SolveError::Unresolvable {
+                dep: dep.to_string(),
+                required_by: parent.map(|p| self.node_name(p)),
+            });
+        }
+
+        let installed = providers
+            .iter()
+            .position(|p| self.lookup.is_installed(&Dependency::parse(p.name())));
+        let idx = match installed {
+            Some(idx) => idx,
+            None if providers.len() == 1 => 0,
+            None => self
+                .chooser
+                .choose(dep, &providers)
+                .filter(|&idx| idx < providers.len())
+                .ok_or_else(|| SolveError::NoProviderSelected(dep.to_string()))?,
+        };
+
+        match providers.swap_remove(idx) {
+            Provider::Repo(name) => Ok(self.add_repo(name)),
+            Provider::Tap(candidate) => Ok(self.add_build(candidate)),
+            Provider::Aur(pkg) => {
+                let srcinfo = self.lookup.aur_srcinfo(&pkg.package_base).ok_or_else(|| {
+                    SolveError::Unresolvable {
+                        dep: dep.to_string(),
+                        required_by: parent.map(|p| self.node_name(p)),
+                    }
+                })?;
+                Ok(self.add_build(BuildCandidate {
+                    pkgname: pkg.name,
+                    origin: BuildOrigin::Aur,
+                    srcinfo,
+                }))
+            }
+        }
+    }
+
+    /// A node already in the plan that satisfies `dep`, adding split members as needed
+    fn find_planned(&mut self, dep: &Dependency) -> Option<usize> {
+        let mut found = None;
+        for (idx, node) in self.nodes.iter().enumerate() {
+            match &node.kind {
+                NodeKind::Repo(name) if *name == dep.name => return Some(idx),
+                NodeKind::Build(build) => {
+                    if let Some(member) = build
+                        .srcinfo
+                        .packages
+                        .iter()
+                        .find(|m| member_satisfies(&build.srcinfo, m, dep))
+                    {
+                        found = Some((idx, member.pkgname.clone()));
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let (idx, pkgname) = found?;
+        self.add_member(idx, &pkgname);
+        Some(idx)
+    }
+
+    fn add_repo(&mut self, name: String) -> usize {
+        if let Some(idx) = self
+            .nodes
+            .iter()
+            .position(|n| matches!(&n.kind, NodeKind::Repo(existing) if *existing == name))
+        {
+            return idx;
+        }
+        self.nodes.push(Node {
+            kind: NodeKind::Repo(name),
+            edges: Vec::new(),
+        });
+        self.nodes.len() - 1
+    }
+
+    fn add_build(&mut self, candidate: BuildCandidate) -> usize {
+        let pkgbase = candidate.srcinfo.pkgbase.clone();
+        if let Some(idx) = self.nodes.iter().position(
+            |n| matches!(&n.kind, NodeKind::Build(existing) if existing.pkgbase == pkgbase),
+        ) {
+            self.add_member(idx, &candidate.pkgname);
+            return idx;
+        }
+
+        let srcinfo = candidate.srcinfo;
+        // Everything makepkg needs to build the base is only required at build time
+        let mut build_deps = srcinfo.depends.host();
+        build_deps.extend(srcinfo.makedepends.host());
+        build_deps.extend(srcinfo.checkdepends.host());
+
+        self.nodes.push(Node {
+            kind: NodeKind::Build(Box::new(PlannedBuild {
+                pkgbase,
+                pkgnames: Vec::new(),
+                version: srcinfo.full_version(),
+                origin: candidate.origin,
+                srcinfo,
+            })),
+            edges: Vec::new(),
+        });
+        let idx = self.nodes.len() - 1;
+        for dep in build_deps {
+            self.queue.push_back(Pending {
+                dep: Dependency::parse(&dep),
+                parent: Some(idx),
+                runtime: false,
+            });
+        }
+        self.add_member(idx, &candidate.pkgname);
+        idx
+    }
+
+    fn add_member(&mut self, idx: usize, pkgname: &str) {
+        let NodeKind::Build(build) = &mut self.nodes[idx].kind else {
+            return;
+        };
+        if build.pkgnames.iter().any(|n| n == pkgname) {
+            return;
+        }
+        build.pkgnames.push(pkgname.to_string());
+        let runtime_deps = build
+            .srcinfo
+            .package(pkgname)
+            .map(|m| m.depends.host())
+            .unwrap_or_else(|| build.srcinfo.depends.host());
+        for dep in runtime_deps {
+            self.queue.push_back(Pending {
+                dep: Dependency::parse(&dep),
+                parent: Some(idx),
+                runtime: true,
+            });
+        }
+    }
+
+    fn node_name(&self, idx: usize) -> String {
+        match &self.nodes[idx].kind {
+            NodeKind::Repo(name) => name.clone(),
+            NodeKind::Build(build) => build.pkgbase.clone(),
+        }
+    }
+
+    /// Depth-first topological sort of the build nodes; dependencies come first
+    fn build_order(&self, roots: &[usize]) -> Result<Vec<usize>, SolveError> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Active,
+            Done,
+        }
+        fn visit<L: PackageLookup, C: ProviderChooser>(
+            solver: &Solver<'_, L, C>,
+            idx: usize,
+            marks: &mut [Mark],
+            stack: &mut Vec<usize>,
+            order: &mut Vec<usize>,
+        ) -> Result<(), SolveError> {
+            match marks[idx] {
+                Mark::Done => return Ok(()),
+                Mark::Active => {
+                    let start = stack.iter().position(|&n| n == idx).unwrap_or(0);
+                    let mut cycle: Vec<String> = stack[start..]
+                        .iter()
+                        .map(|&n| solver.node_name(n))
+                        .collect();
+                    cycle.push(solver.node_name(idx));
+                    return Err(SolveError::Cycle(cycle));
+                }
+                Mark::New => {}
+            }
+            marks[idx] = Mark::Active;
+            stack.push(idx);
+            for &(dep, _) in &solver.nodes[idx].edges {
+                if matches!(solver.nodes[dep].kind, NodeKind::Build(_)) {
+                    visit(solver, dep, marks, stack, order)?;
+                }
+            }
+            stack.pop();
+            marks[idx] = Mark::Done;
+            order.push(idx);
+            Ok(())
+        }
+
+        let mut marks = vec![Mark::New; self.nodes.len()];
+        let mut order = Vec::new();
+        let mut stack = Vec::new();
+        for idx in roots.iter().copied().chain(0..self.nodes.len()) {
+            if matches!(self.nodes[idx].kind, NodeKind::Build(_)) {
+                visit(self, idx, &mut marks, &mut stack, &mut order)?;
+            }
+        }
+        Ok(order)
+    }
+
+    fn into_plan(self, targets: &[String], roots: &[usize]) -> Result<BuildPlan, SolveError> {
+        let order = self.build_order(roots)?;
+
+        // Everything reachable from a target over runtime edges stays installed
+        let mut runtime: HashSet<usize> = roots.iter().copied().collect();
+        let mut stack: Vec<usize> = roots.to_vec();
+        while let Some(idx) = stack.pop() {
+            for &(dep, needed) in &self.nodes[idx].edges {
+                if needed && runtime.insert(dep) {
+                    stack.push(dep);
+                }
+            }
+        }
+
+        let mut plan = BuildPlan {
+            targets: targets.to_vec(),
+            ..Default::default()
+        };
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if let NodeKind::Repo(name) = &node.kind {
+                plan.repo.push(name.clone());
+                if !runtime.contains(&idx) {
+                    plan.make_only.push(name.clone());
+                }
+            }
+        }
+        let mut nodes: Vec<Option<Node>> = self.nodes.into_iter().map(Some).collect();
+        for idx in order {
+            if let Some(Node {
+                kind: NodeKind::Build(build),
+                ..
+            }) = nodes[idx].take()
+            {
+                if !runtime.contains(&idx) {
+                    plan.make_only.extend(build.pkgnames.iter().cloned());
+                }
+                plan.builds.push(*build);
+            }
+        }
+        Ok(plan)
+    }
+}
+
+/// Lookups against the live system: the pacman databases, configured taps and the AUR RPC
+pub struct SystemLookup {
+    taps: Vec<crate::tap::Tap>,
+    db: PacmanDb,
+    local: OnceLock<PackageDb>,
+}
+
+impl SystemLookup {
+    pub fn new() -> Self {
+        Self {
+            taps: crate::tap::discover_taps(),
+            db: PacmanDb::system(),
+            local: OnceLock::new(),
+        }
+    }
+
+    /// Installed packages, read once per resolution
+    fn local(&self) -> &PackageDb {
+        self.local
+            .get_or_init(|| self.db.local().unwrap_or_default())
+    }
+}
+
+impl Default for SystemLookup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackageLookup for SystemLookup {
+    fn is_installed(&self, dep: &Dependency) -> bool {
+        !self.local().providers(dep).is_empty()
+    }
+
+    fn repo_providers(&self, dep: &Dependency) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for db in self.db.sync_all() {
+            for pkg in db.providers(dep) {
+                if !names.contains(&pkg.name) {
+                    names.push(pkg.name.clone());
+                }
+            }
+        }
+        names
+    }
+
+    fn tap_providers(&self, dep: &Dependency) -> Vec<BuildCandidate> {
+        let mut candidates = Vec::new();
+        for tap in self
+            .taps
+            .iter()
+            .filter(|t| crate::tap::tap_has_package(t, &dep.name))
+        {
+            let dir = crate::tap::ensure_tap_cloned(tap).join(&dep.name);
+            // Only a committed .SRCINFO is read: generating one would run the PKGBUILD
+            // before it has been reviewed, even on a dry run
+            let content = std::fs::read_to_string(dir.join(".SRCINFO")).ok();
+            let Some(srcinfo) = content.and_then(|c| SrcInfo::parse(&c).ok()) else {
+                eprintln!(
+                    "[reap] Tap {} has no usable .SRCINFO for {}; skipping it",
+                    tap.name, dep.name
+                );
+                continue;
+            };
+            let satisfied = srcinfo
+                .package(&dep.name)
+                .is_some_and(|member| member_satisfies(&srcinfo, member, dep));
+            if satisfied {
+                candidates.push(BuildCandidate {
+                    pkgname: dep.name.clone(),
+                    origin: BuildOrigin::Tap(tap.name.clone()),
+                    srcinfo,
+                });
+            }
+        }
+        candidates
+    }
+
+    fn aur_providers(&self, dep: &Dependency) -> Vec<AurPackage> {
+        let client = aur_rpc::client();
+        let mut names = vec![dep.name.clone()];
+        if let Ok(results) = client.search_blocking(SearchBy::Provides, &dep.name) {
+            names.extend(
+                results
+                    .into_iter()
+                    .map(|p| p.name)
+                    .filter(|n| *n != dep.name),
+            );
+        }
+        let mut providers: Vec<AurPackage> = client
+            .info_blocking(&names)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| provides_dep(&p.name, &Version::parse(&p.version), &p.provides, dep))
+            .collect();
+        // Most popular first, so noconfirm mode picks a sensible default
+        providers.sort_by(|a, b| b.popularity.total_cmp(&a.popularity));
+        providers
+    }
+
+    fn aur_srcinfo(&self, pkgbase: &str) -> Option<SrcInfo> {
+        crate::srcinfo::fetch_blocking(pkgbase).ok()
+    }
+}
@@ -0,0 +1,325 @@
+// .SRCINFO parsing for AUR package metadata
+use crate::aur_rpc::{self, RpcError};
+use crate::version::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Errors produced while fetching or parsing a .SRCINFO
+#[derive(Debug, Error)]
+pub enum SrcInfoError {
+    #[error("failed to fetch .SRCINFO: {0}")]
+    Fetch(#[from] RpcError),
+    #[error(".SRCINFO line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error(".SRCINFO has no pkgbase")]
+    MissingPkgbase,
+}
+
+/// Values of an array key, split into the arch-independent part and `key_<arch>` entries
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchList {
+    pub any: Vec<String>,
+    pub arch: BTreeMap<String, Vec<String>>,
+}
+
+impl ArchList {
+    /// All values that apply when building for `arch`
+    pub fn get(&self, arch: &str) -> Vec<String> {
+        let mut values = self.any.clone();
+        if let Some(extra) = self.arch.get(arch) {
+            values.extend(extra.iter().cloned());
+        }
+        values
+    }
+
+    /// Values for the architecture reap is running on
+    pub fn host(&self) -> Vec<String> {
+        self.get(host_arch())
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.any.is_empty() && self.arch.values().all(Vec::is_empty)
+    }
+
+    fn slot(&mut self, arch: Option<&str>) -> &mut Vec<String> {
+        match arch {
+            Some(arch) => self.arch.entry(arch.to_string()).or_default(),
+            None => &mut self.any,
+        }
+    }
+}
+
+/// A `pkgname` section of a .SRCINFO with base values already applied
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SrcInfoPackage {
+    pub pkgname: String,
+    pub pkgdesc: Option<String>,
+    pub arch: Vec<String>,
+    pub depends: ArchList,
+    pub optdepends: ArchList,
+    pub provides: ArchList,
+    pub conflicts: ArchList,
+    pub replaces: ArchList,
+}
+
+/// Parsed contents of a .SRCINFO file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SrcInfo {
+    pub pkgbase: String,
+    pub pkgver: String,
+    pub pkgrel: String,
+    pub epoch: Option<String>,
+    pub pkgdesc: Option<String>,
+    pub url: Option<String>,
+    pub arch: Vec<String>,
+    pub depends: ArchList,
+    pub makedepends: ArchList,
+    pub checkdepends: ArchList,
+    pub optdepends: ArchList,
+    pub provides: ArchList,
+    pub conflicts: ArchList,
+    pub replaces: ArchList,
+    pub source: ArchList,
+    /// Checksum arrays keyed by kind (`sha256sums`, `b2sums`, ...)
+    pub checksums: BTreeMap<String, ArchList>,
+    pub packages: Vec<SrcInfoPackage>,
+}
+
+const CHECKSUM_KEYS: &[&str] = &[
+    "cksums",
+    "md5sums",
+    "sha1sums",
+    "sha224sums",
+    "sha256sums",
+    "sha384sums",
+    "sha512sums",
+    "b2sums",
+];
+
+/// Architecture name as used in PKGBUILDs (`x86_64`, `aarch64`, ...)
+pub fn host_arch() -> &'static str {
+    std::env::consts::ARCH
+}
+
+impl SrcInfo {
+    /// Parse the text of a .SRCINFO file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for lines that are not `key = value` pairs or when `pkgbase` is missing.
+    pub fn parse(content: &str) -> Result<Self, SrcInfoError> {
+        let mut info = SrcInfo::default();
+        // Keys a split package has overridden; the first override replaces the inherited value
+        let mut overridden: Vec<String> = Vec::new();
+
+        for (idx, raw) in content.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(SrcInfoError::Parse {
+                    line: idx + 1,
+                    message: format!("expected `key = value`, got `{}`", line),
+                });
+            };
+            let key = key.trim();
+            let value = value.trim();
+
+            match key {
+                "pkgbase" => {
+                    info.pkgbase = value.to_string();
+                    continue;
+                }
+                "pkgname" => {
+                    info.packages.push(SrcInfoPackage {
+                        pkgname: value.to_string(),
+                        pkgdesc: info.pkgdesc.clone(),
+                        arch: info.arch.clone(),
+                        depends: info.depends.clone(),
+                        optdepends: info.optdepends.clone(),
+                        provides: info.provides.clone(),
+                        conflicts: info.conflicts.clone(),
+                        replaces: info.replaces.clone(),
+                    });
+                    overridden.clear();
+                    continue;
+                }
+                _ => {}
+            }
+            if info.pkgbase.is_empty() {
+                return Err(SrcInfoError::MissingPkgbase);
+            }
+
+            let (name, arch) = split_arch_key(key);
+            if let Some(pkg) = info.packages.last_mut() {
+                if !overridden.iter().any(|k| k == key) {
+                    overridden.push(key.to_string());
+                    clear_package_key(pkg, name, arch);
+                }
+                set_package_key(pkg, name, arch, value);
+            } else {
+                info.set_base_key(name, arch, value);
+            }
+        }
+
+        if info.pkgbase.is_empty() {
+            return Err(SrcInfoError::MissingPkgbase);
+        }
+        Ok(info)
+    }
+
+    fn set_base_key(&mut self, name: &str, arch: Option<&str>, value: &str) {
+        let list = match name {
+            "pkgver" => {
+                self.pkgver = value.to_string();
+                return;
+            }
+            "pkgrel" => {
+                self.pkgrel = value.to_string();
+                return;
+            }
+            "epoch" => {
+                self.epoch = Some(value.to_string());
+                return;
+            }
+            "pkgdesc" => {
+                self.pkgdesc = Some(value.to_string());
+                return;
+            }
+            "url" => {
+                self.url = Some(value.to_string());
+                return;
+            }
+            "arch" => {
+                push_value(&mut self.arch, value);
+                return;
+            }
+            "depends" => &mut self.depends,
+            "makedepends" => &mut self.makedepends,
+            "checkdepends" => &mut self.checkdepends,
+            "optdepends" => &mut self.optdepends,
+            "provides" => &mut self.provides,
+            "conflicts" => &mut self.conflicts,
+            "replaces" => &mut self.replaces,
+            "source" => &mut self.source,
+            kind if CHECKSUM_KEYS.contains(&kind) => {
+                self.checksums.entry(kind.to_string()).or_default()
+            }
+            // license, groups, backup, options, validpgpkeys, ... are not needed by reap
+            _ => return,
+        };
+        push_value(list.slot(arch), value);
+    }
+
+    /// Full `[epoch:]pkgver-pkgrel` version string
+    pub fn full_version(&self) -> String {
+        match &self.epoch {
+            Some(epoch) if epoch != "0" => format!("{}:{}-{}", epoch, self.pkgver, self.pkgrel),
+            _ => format!("{}-{}", self.pkgver, self.pkgrel),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn version(&self) -> Version {
+        Version::parse(&self.full_version())
+    }
+
+    /// Names of all packages built from this pkgbase
+    pub fn pkgnames(&self) -> Vec<&str> {
+        self.packages.iter().map(|p| p.pkgname.as_str()).collect()
+    }
+
+    pub fn package(&self, pkgname: &str) -> Option<&SrcInfoPackage> {
+        self.packages.iter().find(|p| p.pkgname == pkgname)
+    }
+
+    /// Runtime dependencies of member `pkgname` on the host, with its own overrides applied
+    pub fn depends_of(&self, pkgname: &str) -> Vec<String> {
+        match self.package(pkgname) {
+            Some(pkg) => pkg.depends.host(),
+            None => self.depends.host(),
+        }
+    }
+
+    /// Everything that must be installed to build on the host: depends, makedepends and checkdepends
+    pub fn build_depends(&self) -> Vec<String> {
+        let mut deps = self.depends.host();
+        deps.extend(self.makedepends.host());
+        deps.extend(self.checkdepends.host());
+        deps
+    }
+}
+
+fn split_arch_key(key: &str) -> (&str, Option<&str>) {
+    // Only array keys can carry an arch suffix; pkgver/pkgrel never contain '_'
+    match key.split_once('_') {
+        Some((name, arch)) if !arch.is_empty() => (name, Some(arch)),
+        _ => (key, None),
+    }
+}
+
+fn push_value(list: &mut Vec<String>, value: &str) {
+    // An empty value in a package section clears the inherited array
+    if !value.is_empty() {
+        list.push(value.to_string());
+    }
+}
+
+fn package_list<'a>(pkg: &'a mut SrcInfoPackage, name: &str) -> Option<&'a mut ArchList> {
+    match name {
+        "depends" => Some(&mut pkg.depends),
+        "optdepends" => Some(&mut pkg.optdepends),
+        "provides" => Some(&mut pkg.provides),
+        "conflicts" => Some(&mut pkg.conflicts),
+        "replaces" => Some(&mut pkg.replaces),
+        _ => None,
+    }
+}
+
+fn clear_package_key(pkg: &mut SrcInfoPackage, name: &str, arch: Option<&str>) {
+    match name {
+        "pkgdesc" => pkg.pkgdesc = None,
+        "arch" => pkg.arch.clear(),
+        _ => {
+            if let Some(list) = package_list(pkg, name) {
+                list.slot(arch).clear();
+            }
+        }
+    }
+}
+
+fn set_package_key(pkg: &mut SrcInfoPackage, name: &str, arch: Option<&str>, value: &str) {
+    match name {
+        "pkgdesc" => pkg.pkgdesc = Some(value.to_string()),
+        "arch" => push_value(&mut pkg.arch, value),
+        _ => {
+            if let Some(list) = package_list(pkg, name) {
+                push_value(list.slot(arch), value);
+            }
+        }
+    }
+}
+
+/// Fetch and parse the .SRCINFO of a package base from the AUR
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be fetched or parsed.
+#[allow(dead_code)]
+pub async fn fetch(pkgbase: &str) -> Result<SrcInfo, SrcInfoError> {
+    let content = aur_rpc::client().fetch_file(pkgbase, ".SRCINFO").await?;
+    SrcInfo::parse(&content)
+}
+
+/// Blocking variant of [`fetch`]
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be fetched or parsed.
+pub fn fetch_blocking(pkgbase: &str) -> Result<SrcInfo, SrcInfoError> {
+    let content = aur_rpc::client().fetch_file_blocking(pkgbase, ".SRCINFO")?;
+    SrcInfo::parse(&content)
+}
@@ -138,10 +138,7 @@ impl TrustEngine {
                 // Check AUR package signature
                 let output = Command::new("curl")
                     .arg("-s")
-                    .arg(format!(
-                        "https://aur.archlinux.org/cgit/aur.git/plain/PKGBUILD.sig?h={}",
-                        pkg
-                    ))
+                    .arg(crate::aur_rpc::client().file_url(pkg, "PKGBUILD.sig"))
                     .output()
                     .ok()?;
 
@@ -271,16 +268,13 @@ impl TrustEngine {
         match _source {
             crate::core::Source::Aur => {
                 // Query AUR API for vote count
-                let url = format!(
-                    "https://aur.archlinux.org/rpc/?v=5&type=info&arg[]={}",
-                    _pkg
-                );
-                if let Ok(resp) = reqwest::get(&url).await {
-                    if let Ok(json) = resp.json::<serde_json::Value>().await {
-                        return json["results"][0]["NumVotes"].as_u64().unwrap_or(0) as u32;
-                    }
-                }
-                0
+                crate::aur_rpc::client()
+                    .info(&[_pkg])
+                    .await
+                    .ok()
+                    .and_then(|results| results.into_iter().next())
+                    .map(|info| info.num_votes)
+                    .unwrap_or(0)
             }
             _ => 0,
         }
@@ -2,6 +2,7 @@
 
 use crate::aur;
 use crate::aur::SearchResult;
+use crate::backend::{BackendImpl, FlatpakBackend, PacmanBackend};
 use crate::core;
 use crate::profiles::ProfileManager;
 use crate::trust::{TrustEngine, TrustScore};
@@ -203,7 +204,11 @@ impl InstallQueue {
     }
 
     #[allow(dead_code)]
-    pub async fn process(&self, log_pane: Arc<LogPane>, backend: &str) {
+    pub async fn process(
+        &self,
+        log_pane: Arc<LogPane>,
+        backend: &str,
+    ) -> Vec<core::InstallOutcome> {
         let semaphore = Arc::new(Semaphore::new(4));
         let mut handles = Vec::new();
         let mut completed = 0;
@@ -213,25 +218,34 @@ impl InstallQueue {
             let log_pane_task = Arc::clone(&log_pane);
             let backend = backend.to_string();
             handles.push(tokio::spawn(async move {
-                match backend.as_str() {
-                    "pacman" => {
-                        let status = std::process::Command::new("pacman").status();
-                        match status {
-                            Ok(s) if s.success() => {
-                                log_pane_task.push("[tui] Pacman install succeeded.")
-                            }
-                            Ok(_) | Err(_) => log_pane_task.push("[tui] Pacman install failed."),
-                        }
-                    }
+                let start = Instant::now();
+                let result = match backend.as_str() {
+                    "pacman" => BackendImpl::Pacman(PacmanBackend).install(&task.pkg).await,
                     "flatpak" => {
-                        crate::flatpak::install(&task.pkg);
-                        log_pane_task.push("[tui] Flatpak install attempted.");
-                    }
-                    _ => {
-                        log_pane_task.push("[tui] Unknown backend.");
+                        BackendImpl::Flatpak(FlatpakBackend::new())
+                            .install(&task.pkg)
+                            .await
                     }
-                }
+                    _ => Err(core::ReapError::CommandFailed(format!(
+                        "unknown backend {}",
+                        backend
+                    ))),
+                };
                 drop(permit);
+                let outcome = result.unwrap_or_else(|e| {
+                    core::InstallOutcome::failed(
+                        &task.pkg,
+                        Some(task.source.clone()),
+                        start.elapsed(),
+                        e,
+                    )
+                });
+                match &outcome.status {
+                    core::OutcomeStatus::Failed(reason) => log_pane_task
+                        .push(&format!("[tui] Install of {} failed: {}", task.pkg, reason)),
+                    _ => log_pane_task.push(&format!("[tui] Installed {}.", task.pkg)),
+                }
+                outcome
             }));
             completed += 1;
             log_pane.push(&format!(
@@ -240,10 +254,14 @@ impl InstallQueue {
             ));
             sleep(Duration::from_millis(300)).await;
         }
+        let mut outcomes = Vec::new();
         for h in handles {
-            let _ = h.await;
+            if let Ok(outcome) = h.await {
+                outcomes.push(outcome);
+            }
         }
         log_pane.push("[reap][queue] All install tasks complete.");
+        outcomes
     }
 }
 
@@ -360,8 +378,17 @@ pub async fn launch_tui() {
     let trust_engine = TrustEngine::new();
     let profile_manager = ProfileManager::new();
     let mut build_progress = BuildProgress::new();
+    let pending_updates: Arc<Mutex<Option<Vec<aur::UpdateCandidate>>>> = Arc::new(Mutex::new(None));
+    {
+        let pending_updates = pending_updates.clone();
+        tokio::spawn(async move {
+            let updates = aur::get_outdated().await;
+            *pending_updates.lock().unwrap() = Some(updates);
+        });
+    }
 
     loop {
+        let update_count = pending_updates.lock().unwrap().as_ref().map(Vec::len);
         terminal
             .draw(|f| {
                 let size = f.size();
@@ -422,7 +449,7 @@ pub async fn launch_tui() {
                 }
 
                 // Enhanced bottom panel with real-time stats
-                render_bottom_panel(f, chunks[2], &installed, &trust_engine);
+                render_bottom_panel(f, chunks[2], &installed, update_count, &trust_engine);
 
                 // Status bar with current profile and system info
                 render_status_bar(f, chunks[3], &profile_manager);
@@ -767,6 +794,7 @@ fn render_bottom_panel_fixed(
     f: &mut Frame<'_>,
     area: ratatui::layout::Rect,
     installed: &HashMap<String, crate::core::Source>,
+    update_count: Option<usize>,
     _trust_engine: &TrustEngine,
 ) {
     let chunks = Layout::default()
@@ -780,9 +808,10 @@ fn render_bottom_panel_fixed(
 
     // System info
     let total_packages = installed.len();
+    let updates = update_count.map_or_else(|| "…".to_string(), |n| n.to_string());
     let system_info = format!(
-        "📦 Total: {}\n🔄 Updates: 0\n💾 Cache: 2.1GB",
-        total_packages
+        "📦 Total: {}\n🔄 Updates: {}\n💾 Cache: 2.1GB",
+        total_packages, updates
     );
     let info_paragraph =
         Paragraph::new(system_info).block(Block::default().borders(Borders::ALL).title("System"));
@@ -806,9 +835,10 @@ fn render_bottom_panel(
     f: &mut Frame<'_>,
     area: ratatui::layout::Rect,
     installed: &HashMap<String, crate::core::Source>,
+    update_count: Option<usize>,
     trust_engine: &TrustEngine,
 ) {
-    render_bottom_panel_fixed(f, area, installed, trust_engine);
+    render_bottom_panel_fixed(f, area, installed, update_count, trust_engine);
 }
 
 fn render_status_bar(
@@ -0,0 +1,492 @@
+// One upgrade plan across the sync repos, the AUR, taps and Flatpak
+use crate::core::{InstallOptions, InstallOutcome, OutcomeStatus, Source};
+use crate::pacman_db::{DbPackage, PacmanDb, PacmanDbError};
+use crate::solver::{BuildOrigin, PackageLookup, SystemLookup};
+use crate::version::{Dependency, vercmp};
+use owo_colors::OwoColorize;
+use std::cmp::Ordering;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Where a pending upgrade comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpgradeSource {
+    /// A sync repository such as `core`
+    Repo(String),
+    Aur,
+    Tap(String),
+    Flatpak,
+}
+
+impl UpgradeSource {
+    /// The backend's name in `backend_order`
+    pub fn backend(&self) -> &'static str {
+        match self {
+            UpgradeSource::Repo(_) => "pacman",
+            UpgradeSource::Aur => "aur",
+            UpgradeSource::Tap(_) => "tap",
+            UpgradeSource::Flatpak => "flatpak",
+        }
+    }
+
+    /// The source recorded in install outcomes
+    pub fn source(&self) -> Source {
+        match self {
+            UpgradeSource::Repo(_) => Source::Pacman,
+            UpgradeSource::Aur => Source::Aur,
+            UpgradeSource::Tap(tap) => Source::Custom(tap.clone()),
+            UpgradeSource::Flatpak => Source::Flatpak,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            UpgradeSource::Repo(repo) => repo.clone(),
+            UpgradeSource::Aur => "aur".to_string(),
+            UpgradeSource::Tap(tap) => format!("tap:{}", tap),
+            UpgradeSource::Flatpak => "flatpak".to_string(),
+        }
+    }
+}
+
+/// An installed package with a newer version available
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingUpgrade {
+    pub name: String,
+    pub source: UpgradeSource,
+    /// Installed version
+    pub from: String,
+    /// Available version
+    pub to: String,
+}
+
+impl PendingUpgrade {
+    pub fn new(name: &str, source: UpgradeSource, from: &str, to: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            source,
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+}
+
+/// Every pending upgrade of a system upgrade, ordered by backend.
+///
+/// Upgrades from a backend missing from `backend_order` are left out; pinned and ignored
+/// packages are held back with the reason.
+#[derive(Debug, Clone, Default)]
+pub struct UpgradePlan {
+    pub upgrades: Vec<PendingUpgrade>,
+    pub held: Vec<(PendingUpgrade, String)>,
+}
+
+impl UpgradePlan {
+    /// Plan `candidates` in `backend_order`, holding back those `hold` gives a reason for
+    pub fn new<F>(candidates: Vec<PendingUpgrade>, backend_order: &[String], hold: F) -> Self
+    where
+        F: Fn(&PendingUpgrade) -> Option<String>,
+    {
+        let rank = |upgrade: &PendingUpgrade| {
+            backend_order
+                .iter()
+                .position(|backend| backend == upgrade.source.backend())
+        };
+        let mut candidates: Vec<PendingUpgrade> = candidates
+            .into_iter()
+            .filter(|upgrade| rank(upgrade).is_some())
+            .collect();
+        candidates.sort_by(|a, b| {
+            rank(a)
+                .cmp(&rank(b))
+                .then_with(|| a.source.label().cmp(&b.source.label()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        let mut plan = UpgradePlan::default();
+        for upgrade in candidates {
+            match hold(&upgrade) {
+                Some(reason) => plan.held.push((upgrade, reason)),
+                None => plan.upgrades.push(upgrade),
+            }
+        }
+        plan
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.upgrades.is_empty()
+    }
+
+    /// Upgrades from `backend`, in plan order
+    pub fn for_backend<'a>(
+        &'a self,
+        backend: &'a str,
+    ) -> impl Iterator<Item = &'a PendingUpgrade> + 'a {
+        self.upgrades
+            .iter()
+            .filter(move |upgrade| upgrade.source.backend() == backend)
+    }
+
+    /// Print the upgrades grouped by source with their version change, then the held ones
+    pub fn print(&self) {
+        let mut current: Option<String> = None;
+        for upgrade in &self.upgrades {
+            let label = upgrade.source.label();
+            if current.as_ref() != Some(&label) {
+                let count = self
+                    .upgrades
+                    .iter()
+                    .filter(|u| u.source == upgrade.source)
+                    .count();
+                println!("{} {} ({}):", "📦".bright_cyan(), label.bold(), count);
+                current = Some(label);
+            }
+            println!(
+                "    {} {} → {}",
+                upgrade.name.bright_white(),
+                upgrade.from.red(),
+                upgrade.to.bright_green()
+            );
+        }
+        for (upgrade, reason) in &self.held {
+            println!(
+                "{} Holding back {} {} → {} ({})",
+                "⏭️".yellow(),
+                upgrade.name,
+                upgrade.from,
+                upgrade.to,
+                reason
+            );
+        }
+    }
+}
+
+/// Installed packages with a newer version in the first sync repository that has them
+///
+/// # Errors
+///
+/// Returns an error if the local database cannot be read.
+pub fn repo_upgrades(db: &PacmanDb) -> Result<Vec<PendingUpgrade>, PacmanDbError> {
+    let sync = db.sync_all();
+    let mut upgrades = Vec::new();
+    for pkg in db.local()?.iter() {
+        let Some(repo) = sync.iter().find(|repo| repo.contains(&pkg.name)) else {
+            continue;
+        };
+        let Some(newer) = repo.get(&pkg.name) else {
+            continue;
+        };
+        if vercmp(&newer.version, &pkg.version) == Ordering::Greater {
+            let source = UpgradeSource::Repo(repo.repo.clone().unwrap_or_default());
+            upgrades.push(PendingUpgrade::new(
+                &pkg.name,
+                source,
+                &pkg.version,
+                &newer.version,
+            ));
+        }
+    }
+    Ok(upgrades)
+}
+
+/// Foreign packages a tap has a newer version of, and the foreign packages no tap has
+///
+/// A package found in a tap is built from it, as the solver prefers taps, so only the rest
+/// are checked against the AUR.
+pub fn tap_upgrades(foreign: Vec<DbPackage>) -> (Vec<PendingUpgrade>, Vec<DbPackage>) {
+    let lookup = SystemLookup::new();
+    let mut upgrades = Vec::new();
+    let mut rest = Vec::new();
+    for pkg in foreign {
+        let Some(candidate) = lookup
+            .tap_providers(&Dependency::parse(&pkg.name))
+            .into_iter()
+            .next()
+        else {
+            rest.push(pkg);
+            continue;
+        };
+        let BuildOrigin::Tap(tap) = candidate.origin else {
+            rest.push(pkg);
+            continue;
+        };
+        let version = candidate.srcinfo.full_version();
+        if vercmp(&version, &pkg.version) == Ordering::Greater {
+            upgrades.push(PendingUpgrade::new(
+                &pkg.name,
+                UpgradeSource::Tap(tap),
+                &pkg.version,
+                &version,
+            ));
+        }
+    }
+    (upgrades, rest)
+}
+
+/// Foreign packages with a newer version on the AUR
+pub async fn aur_upgrades(foreign: &[DbPackage]) -> Vec<PendingUpgrade> {
+    let installed: Vec<(String, String)> = foreign
+        .iter()
+        .map(|pkg| (pkg.name.clone(), pkg.version.clone()))
+        .collect();
+    match crate::aur::check_updates_with(&crate::aur_rpc::client(), &installed).await {
+        Ok(candidates) => candidates
+            .into_iter()
+            .map(|c| PendingUpgrade::new(&c.name, UpgradeSource::Aur, &c.local, &c.remote))
+            .collect(),
+        Err(e) => {
+            eprintln!("[reap] AUR update check failed: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// VCS packages among `foreign` whose upstream commit moved since they were built.
+///
+/// Packages already in `queued` are left out; the new version is shown as the commit.
+pub fn devel_upgrades(foreign: &[DbPackage], queued: &[PendingUpgrade]) -> Vec<PendingUpgrade> {
+    let installed: Vec<String> = foreign.iter().map(|pkg| pkg.name.clone()).collect();
+    let mut upgrades = Vec::new();
+    for (_, package) in crate::devel::check(&installed) {
+        let source = match &package.origin {
+            BuildOrigin::Aur => UpgradeSource::Aur,
+            BuildOrigin::Tap(tap) => UpgradeSource::Tap(tap.clone()),
+        };
+        let commit = package.sources.first().map_or("", |t| t.commit.as_str());
+        let to = format!("git {}", &commit[..commit.len().min(7)]);
+        for name in &package.pkgnames {
+            if queued.iter().any(|u| u.name == *name) {
+                continue;
+            }
+            let from = foreign
+                .iter()
+                .find(|pkg| pkg.name == *name)
+                .map_or("", |pkg| pkg.version.as_str());
+            upgrades.push(PendingUpgrade::new(name, source.clone(), from, &to));
+        }
+    }
+    upgrades
+}
+
+/// Installed Flatpak applications with an update on their remote
+pub fn flatpak_upgrades() -> Vec<PendingUpgrade> {
+    if !crate::flatpak::is_flatpak_available() {
+        return Vec::new();
+    }
+    let columns = "--columns=application,version";
+    let list = |args: &[&str]| {
+        Command::new("flatpak")
+            .args(args)
+            .output()
+            .map(|out| String::from_utf8_lossy(&out.stdout).to_string())
+            .unwrap_or_default()
+    };
+    let installed = list(&["list", "--app", columns]);
+    let updates = list(&["remote-ls", "--updates", "--app", columns]);
+    parse_flatpak_upgrades(&installed, &updates)
+}
+
+/// Pair `flatpak remote-ls --updates` rows with the installed versions from `flatpak list`,
+/// both as tab-separated `application version` columns
+pub fn parse_flatpak_upgrades(installed: &str, updates: &str) -> Vec<PendingUpgrade> {
+    let row = |line: &str| {
+        let mut columns = line.split('\t').map(str::trim);
+        let app = columns.next().filter(|app| !app.is_empty())?;
+        Some((app.to_string(), columns.next().unwrap_or("").to_string()))
+    };
+    let installed: Vec<(String, String)> = installed.lines().filter_map(row).collect();
+    updates
+        .lines()
+        .filter_map(row)
+        .map(|(app, version)| {
+            let from = installed
+                .iter()
+                .find(|(name, _)| *name == app)
+                .map(|(_, v)| v.as_str())
+                .filter(|v| !v.is_empty())
+                .unwrap_or("installed");
+            let to = if version.is_empty() {
+                "latest"
+            } else {
+                &version
+            };
+            PendingUpgrade::new(&app, UpgradeSource::Flatpak, from, to)
+        })
+        .collect()
+}
+
+/// Check every backend in `backend_order` for upgrades
+///
+/// With `devel`, AUR and tap VCS packages whose upstream commit moved are queued as well.
+pub async fn collect(backend_order: &[String], devel: bool) -> Vec<PendingUpgrade> {
+    let enabled = |backend: &str| backend_order.iter().any(|b| b == backend);
+    let db = PacmanDb::system();
+    let mut upgrades = Vec::new();
+    if enabled("pacman") {
+        match repo_upgrades(&db) {
+            Ok(repo) => upgrades.extend(repo),
+            Err(e) => eprintln!("[reap] Could not read the local package database: {}", e),
+        }
+    }
+    let mut foreign = db.foreign().unwrap_or_default();
+    let installed = foreign.clone();
+    if enabled("tap") {
+        let (taps, rest) = tokio::task::spawn_blocking(move || tap_upgrades(foreign))
+            .await
+            .unwrap_or_default();
+        upgrades.extend(taps);
+        foreign = rest;
+    }
+    if enabled("aur") {
+        upgrades.extend(aur_upgrades(&foreign).await);
+    }
+    if devel && (enabled("aur") || enabled("tap")) {
+        let queued = upgrades.clone();
+        let moved = tokio::task::spawn_blocking(move || devel_upgrades(&installed, &queued))
+            .await
+            .unwrap_or_default();
+        upgrades.extend(moved);
+    }
+    if enabled("flatpak") {
+        upgrades.extend(flatpak_upgrades());
+    }
+    upgrades
+}
+
+/// Run `plan`: the repo upgrade first, then the AUR and tap builds in dependency order,
+/// then Flatpak.
+///
+/// Held repo packages are passed to pacman as `--ignore`. Builds are skipped when the repo
+/// upgrade fails, so nothing is built against a half-upgraded system.
+pub async fn execute(plan: &UpgradePlan, opts: &InstallOptions) -> Vec<InstallOutcome> {
+    let mut outcomes = Vec::new();
+
+    let repo: Vec<&PendingUpgrade> = plan.for_backend("pacman").collect();
+    let mut repo_failed = None;
+    if !repo.is_empty() {
+        let start = Instant::now();
+        let mut args = vec!["-Su".to_string()];
+        for (held, _) in plan
+            .held
+            .iter()
+            .filter(|(u, _)| u.source.backend() == "pacman")
+        {
+            args.push("--ignore".to_string());
+            args.push(held.name.clone());
+        }
+        if opts.noconfirm {
+            args.push("--noconfirm".to_string());
+        }
+        println!("[reap] Upgrading {} repo packages...", repo.len());
+        let result = crate::pacman::run(&args);
+        if let Err(e) = &result {
+            repo_failed = Some(e.to_string());
+        }
+        let elapsed = start.elapsed();
+        for upgrade in &repo {
+            let result = result.as_ref().map(|_| ()).map_err(|e| e.to_string());
+            outcomes.push(InstallOutcome::from_result(
+                &upgrade.name,
+                Source::Pacman,
+                elapsed,
+                false,
+                result,
+            ));
+        }
+    }
+
+    let builds: Vec<&PendingUpgrade> = plan
+        .upgrades
+        .iter()
+        .filter(|u| matches!(u.source, UpgradeSource::Aur | UpgradeSource::Tap(_)))
+        .collect();
+    if let Some(e) = &repo_failed {
+        for upgrade in &builds {
+            let reason = format!("repo upgrade failed: {}", e);
+            outcomes.push(InstallOutcome::skipped(
+                &upgrade.name,
+                Some(upgrade.source.source()),
+                Duration::ZERO,
+                reason,
+            ));
+        }
+    } else if !builds.is_empty() {
+        outcomes.extend(upgrade_builds(&builds, opts).await);
+    }
+
+    let apps: Vec<&str> = plan
+        .for_backend("flatpak")
+        .map(|u| u.name.as_str())
+        .collect();
+    if !apps.is_empty() {
+        let start = Instant::now();
+        println!("[reap] Upgrading {} Flatpak applications...", apps.len());
+        let result = Command::new("flatpak")
+            .args(["update", "--noninteractive", "-y"])
+            .args(&apps)
+            .status()
+            .map_err(|e| e.to_string())
+            .and_then(|status| {
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("flatpak update exited with {}", status))
+                }
+            });
+        for app in apps {
+            outcomes.push(InstallOutcome::from_result(
+                app,
+                Source::Flatpak,
+                start.elapsed(),
+                false,
+                result.clone(),
+            ));
+        }
+    }
+    outcomes
+}
+
+/// Build and install the AUR and tap upgrades as one solved plan
+async fn upgrade_builds(builds: &[&PendingUpgrade], opts: &InstallOptions) -> Vec<InstallOutcome> {
+    let start = Instant::now();
+    let names: Vec<&str> = builds.iter().map(|u| u.name.as_str()).collect();
+    let reports = match crate::aur::install_planned(names, opts).await {
+        Ok(reports) => reports,
+        Err(e) => {
+            return builds
+                .iter()
+                .map(|u| {
+                    InstallOutcome::failed(
+                        &u.name,
+                        Some(u.source.source()),
+                        start.elapsed(),
+                        e.to_string(),
+                    )
+                })
+                .collect();
+        }
+    };
+    builds
+        .iter()
+        .map(|upgrade| {
+            let source = upgrade.source.source();
+            let report = reports.iter().find(|r| r.pkgnames.contains(&upgrade.name));
+            match report.map(|r| (&r.status, r.duration)) {
+                Some((OutcomeStatus::Installed, duration)) => {
+                    InstallOutcome::installed(&upgrade.name, source, duration)
+                }
+                Some((OutcomeStatus::Failed(e), duration)) => {
+                    InstallOutcome::failed(&upgrade.name, Some(source), duration, e)
+                }
+                Some((OutcomeStatus::Skipped(reason), duration)) => {
+                    InstallOutcome::skipped(&upgrade.name, Some(source), duration, reason.clone())
+                }
+                None => InstallOutcome::skipped(
+                    &upgrade.name,
+                    Some(source),
+                    start.elapsed(),
+                    "not in the build plan",
+                ),
+            }
+        })
+        .collect()
+}
@@ -1,3 +1,4 @@
+use crate::version::Dependency;
 use anyhow::Result;
 use diff::lines;
 use std::fs;
@@ -49,18 +50,30 @@ pub fn ensure_cache_dirs() {
 }
 
 /// Audit a package by checking its source and dependencies
-pub fn audit_package(pkg: &str) {
-    let pkgb = crate::aur::get_pkgbuild_preview(pkg);
-    if let Some((name, ver)) = parse_pkgname_ver(&pkgb) {
-        println!("[preview] Package: {} v{}", name, ver);
-    } else {
-        println!("[preview] Could not parse PKGBUILD for '{}'", pkg);
+pub async fn audit_package(pkg: &str) {
+    // Split members are published under their package base
+    let srcinfo = crate::aur::fetch_srcinfo(pkg).await;
+    let pkgbase = srcinfo.as_ref().map_or(pkg, |info| info.pkgbase.as_str());
+    let pkgb = crate::aur_rpc::client()
+        .fetch_file(pkgbase, "PKGBUILD")
+        .await
+        .unwrap_or_else(|_| String::from("[reap] PKGBUILD not found."));
+    match &srcinfo {
+        Ok(info) => println!(
+            "[preview] Package: {} v{}",
+            info.pkgbase,
+            info.full_version()
+        ),
+        Err(e) => println!("[preview] Could not parse .SRCINFO for '{}': {}", pkg, e),
     }
     println!("PKGBUILD preview:\n{}", pkgb);
-    match crate::core::detect_source(pkg, None, false) {
+    match crate::core::detect_source(pkg, None, false).await {
         Some(crate::core::Source::Aur) => {
             println!("[AUDIT][AUR] Auditing PKGBUILD for {}...", pkg);
-            let deps = crate::aur::get_deps(pkg);
+            let deps = srcinfo
+                .as_ref()
+                .map(|info| crate::aur::get_deps(info, pkg))
+                .unwrap_or_default();
             if deps.is_empty() {
                 println!("[AUDIT][AUR] No dependencies found for {}.", pkg);
             } else {
@@ -92,67 +105,29 @@ type PkgMeta = (
     Vec<String>,
 );
 
-/// Parse PKGBUILD for depends, makedepends, conflicts and check system state
+/// Collect depends, makedepends, conflicts of member `pkgname` from a .SRCINFO and check
+/// system state
 #[allow(dead_code)]
-pub fn resolve_deps(pkgb: &str) -> PkgMeta {
-    let mut depends = Vec::new();
-    let mut makedepends = Vec::new();
-    let mut conflicts = Vec::new();
+pub fn resolve_deps(srcinfo: &crate::srcinfo::SrcInfo, pkgname: &str) -> PkgMeta {
+    let depends = srcinfo.depends_of(pkgname);
+    let makedepends = srcinfo.makedepends.host();
+    let conflicts = match srcinfo.package(pkgname) {
+        Some(member) => member.conflicts.host(),
+        None => srcinfo.conflicts.host(),
+    };
     let mut missing = Vec::new();
     let mut conflicting = Vec::new();
-    for line in pkgb.lines() {
-        let trimmed = line.trim_start();
-        if trimmed.starts_with("depends=") {
-            let dep_line = trimmed.split_once('=').map(|x| x.1).unwrap_or("").trim();
-            depends.extend(
-                dep_line
-                    .trim_matches(&['(', ')', '"', '\'', ' '] as &[_])
-                    .split_whitespace()
-                    .map(|s| s.trim_matches(&['"', '\'', ' '] as &[_]))
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.to_string()),
-            );
-        } else if trimmed.starts_with("makedepends=") {
-            let dep_line = trimmed.split_once('=').map(|x| x.1).unwrap_or("").trim();
-            makedepends.extend(
-                dep_line
-                    .trim_matches(&['(', ')', '"', '\'', ' '] as &[_])
-                    .split_whitespace()
-                    .map(|s| s.trim_matches(&['"', '\'', ' '] as &[_]))
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.to_string()),
-            );
-        } else if trimmed.starts_with("conflicts=") {
-            let dep_line = trimmed.split_once('=').map(|x| x.1).unwrap_or("").trim();
-            conflicts.extend(
-                dep_line
-                    .trim_matches(&['(', ')', '"', '\'', ' '] as &[_])
-                    .split_whitespace()
-                    .map(|s| s.trim_matches(&['"', '\'', ' '] as &[_]))
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.to_string()),
-            );
-        }
-    }
     // Check installed packages
-    let installed: Vec<String> = {
-        let output = std::process::Command::new("pacman").arg("-Q").output();
-        if let Ok(out) = output {
-            String::from_utf8_lossy(&out.stdout)
-                .lines()
-                .map(|l| l.split_whitespace().next().unwrap_or("").to_string())
-                .collect()
-        } else {
-            Vec::new()
-        }
-    };
+    let installed = crate::pacman_db::PacmanDb::system()
+        .local()
+        .unwrap_or_default();
     for dep in depends.iter().chain(makedepends.iter()) {
-        if !installed.contains(dep) {
+        if !installed.contains(&Dependency::parse(dep).name) {
             missing.push(dep.clone());
         }
     }
     for c in &conflicts {
-        if installed.contains(c) {
+        if installed.contains(&Dependency::parse(c).name) {
             conflicting.push(c.clone());
         }
     }
@@ -382,7 +357,7 @@ pub fn doctor_report() -> Result<String, String> {
 }
 
 #[allow(dead_code)]
-pub fn build_pkg(pkgdir: &std::path::Path, edit: bool) -> Result<(), String> {
+pub fn build_pkg(pkgdir: &std::path::Path, edit: bool, chroot: bool) -> Result<(), String> {
     use std::env;
     use std::process::Command;
     let pkgb_path = pkgdir.join("PKGBUILD");
@@ -397,9 +372,24 @@ pub fn build_pkg(pkgdir: &std::path::Path, edit: bool) -> Result<(), String> {
             return Err("[reap] Failed to launch editor".to_string());
         }
     }
+    let store = crate::artifacts::ArtifactStore::default();
+    let pkgdest = store
+        .ensure()
+        .map_err(|e| format!("[reap] Failed to create package directory: {}", e))?;
+    if chroot {
+        let chroot = crate::chroot::Chroot::default();
+        let deps = crate::core::chroot_deps(pkgdir, &[]);
+        return chroot
+            .ensure()
+            .and_then(|()| chroot.build(pkgdir, pkgdest, &deps))
+            .map(|()| println!("[reap] Chroot build succeeded."))
+            .map_err(|e| format!("[reap] Chroot build failed: {}", e));
+    }
     let output = Command::new("makepkg")
-        .arg("-si")
+        .arg("-s")
+        .arg("-f")
         .arg("--noconfirm")
+        .env("PKGDEST", pkgdest)
         .current_dir(pkgdir)
         .output();
     match output {
@@ -679,18 +669,29 @@ pub fn rollback(pkg: &str) {
     }
 }
 
+/// First pkgname and full version from the contents of a .SRCINFO
+#[allow(dead_code)]
 pub fn parse_pkgname_ver(content: &str) -> Option<(String, String)> {
-    let mut name = None;
-    let mut ver = None;
-    for line in content.lines() {
-        if line.starts_with("pkgname=") {
-            name = Some(line.trim_start_matches("pkgname=").trim().to_string());
-        } else if line.starts_with("pkgver=") {
-            ver = Some(line.trim_start_matches("pkgver=").trim().to_string());
-        }
+    let info = crate::srcinfo::SrcInfo::parse(content).ok()?;
+    let name = info
+        .pkgnames()
+        .first()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| info.pkgbase.clone());
+    Some((name, info.full_version()))
+}
+
+/// Human-readable size such as `12.3 MiB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
     }
-    match (name, ver) {
-        (Some(n), Some(v)) => Some((n, v)),
-        _ => None,
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
     }
+    format!("{:.1} {}", size, UNITS[unit])
 }
@@ -0,0 +1,272 @@
+// Native implementation of libalpm's version comparison (vercmp)
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A pacman package version (`[epoch:]pkgver[-pkgrel]`) ordered like `vercmp`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Version {
+    pub epoch: String,
+    pub pkgver: String,
+    pub pkgrel: Option<String>,
+}
+
+impl Version {
+    /// Split a version string into epoch, pkgver and pkgrel the way libalpm's `parseEVR` does
+    pub fn parse(evr: &str) -> Self {
+        let digits = evr.bytes().take_while(u8::is_ascii_digit).count();
+        let (epoch, rest) = if evr.as_bytes().get(digits) == Some(&b':') {
+            let epoch = if digits == 0 { "0" } else { &evr[..digits] };
+            (epoch, &evr[digits + 1..])
+        } else {
+            ("0", evr)
+        };
+        let (pkgver, pkgrel) = match rest.rfind('-') {
+            Some(idx) => (&rest[..idx], Some(rest[idx + 1..].to_string())),
+            None => (rest, None),
+        };
+        Version {
+            epoch: epoch.to_string(),
+            pkgver: pkgver.to_string(),
+            pkgrel,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Version::parse(s))
+    }
+}
+
+impl From<String> for Version {
+    fn from(s: String) -> Self {
+        Version::parse(&s)
+    }
+}
+
+impl From<Version> for String {
+    fn from(v: Version) -> Self {
+        v.to_string()
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.epoch != "0" {
+            write!(f, "{}:", self.epoch)?;
+        }
+        write!(f, "{}", self.pkgver)?;
+        if let Some(rel) = &self.pkgrel {
+            write!(f, "-{}", rel)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        rpmvercmp(&self.epoch, &other.epoch)
+            .then_with(|| rpmvercmp(&self.pkgver, &other.pkgver))
+            .then_with(|| match (&self.pkgrel, &other.pkgrel) {
+                // pkgrel only counts when both sides have one
+                (Some(a), Some(b)) => rpmvercmp(a, b),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+/// Compare two full version strings, equivalent to `vercmp a b`
+#[allow(dead_code)]
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    Version::parse(a).cmp(&Version::parse(b))
+}
+
+/// Segment-wise comparison of a single version component (libalpm's `rpmvercmp`)
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        let sep1 = one;
+        let sep2 = two;
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+        // Differing separator lengths decide the comparison
+        if one - sep1 != two - sep2 {
+            return (one - sep1).cmp(&(two - sep2));
+        }
+
+        let is_num = a[one].is_ascii_digit();
+        let class: fn(&u8) -> bool = if is_num {
+            u8::is_ascii_digit
+        } else {
+            u8::is_ascii_alphabetic
+        };
+        let end1 = one + a[one..].iter().take_while(|c| class(c)).count();
+        let end2 = two + b[two..].iter().take_while(|c| class(c)).count();
+
+        // Numeric segments are always newer than alpha segments
+        if two == end2 {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let mut seg1 = &a[one..end1];
+        let mut seg2 = &b[two..end2];
+        if is_num {
+            while seg1.first() == Some(&b'0') {
+                seg1 = &seg1[1..];
+            }
+            while seg2.first() == Some(&b'0') {
+                seg2 = &seg2[1..];
+            }
+            match seg1.len().cmp(&seg2.len()) {
+                Ordering::Equal => {}
+                longer => return longer,
+            }
+        }
+        match seg1.cmp(seg2) {
+            Ordering::Equal => {}
+            ord => return ord,
+        }
+
+        one = end1;
+        two = end2;
+    }
+
+    let rest1 = &a[one.min(a.len())..];
+    let rest2 = &b[two.min(b.len())..];
+    if rest1.is_empty() && rest2.is_empty() {
+        return Ordering::Equal;
+    }
+    // A remaining alpha segment never beats an empty string
+    let two_alpha = rest2.first().is_some_and(u8::is_ascii_alphabetic);
+    let one_alpha = rest1.first().is_some_and(u8::is_ascii_alphabetic);
+    if (rest1.is_empty() && !two_alpha) || one_alpha {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+/// Comparison operator of a versioned dependency such as `foo>=1.2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DepOp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl DepOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DepOp::Lt => "<",
+            DepOp::Le => "<=",
+            DepOp::Eq => "=",
+            DepOp::Ge => ">=",
+            DepOp::Gt => ">",
+        }
+    }
+
+    fn matches(&self, ord: Ordering) -> bool {
+        match self {
+            DepOp::Lt => ord == Ordering::Less,
+            DepOp::Le => ord != Ordering::Greater,
+            DepOp::Eq => ord == Ordering::Equal,
+            DepOp::Ge => ord != Ordering::Less,
+            DepOp::Gt => ord == Ordering::Greater,
+        }
+    }
+}
+
+/// A dependency string split into name and optional version constraint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    pub constraint: Option<(DepOp, Version)>,
+}
+
+impl Dependency {
+    /// Parse `name`, `name>=ver`, `name<ver`, ... (an optdepends `: description` is ignored)
+    pub fn parse(dep: &str) -> Self {
+        let dep = dep.split(": ").next().unwrap_or(dep).trim();
+        let Some(idx) = dep.find(['<', '>', '=']) else {
+            return Dependency {
+                name: dep.to_string(),
+                constraint: None,
+            };
+        };
+        let (name, rest) = dep.split_at(idx);
+        let (op, ver) = if let Some(v) = rest.strip_prefix(">=") {
+            (DepOp::Ge, v)
+        } else if let Some(v) = rest.strip_prefix("<=") {
+            (DepOp::Le, v)
+        } else if let Some(v) = rest.strip_prefix('>') {
+            (DepOp::Gt, v)
+        } else if let Some(v) = rest.strip_prefix('<') {
+            (DepOp::Lt, v)
+        } else {
+            (DepOp::Eq, rest.trim_start_matches('='))
+        };
+        Dependency {
+            name: name.to_string(),
+            constraint: Some((op, Version::parse(ver))),
+        }
+    }
+
+    /// Whether a package at `version` fulfils this dependency's constraint
+    pub fn satisfied_by(&self, version: &Version) -> bool {
+        match &self.constraint {
+            None => true,
+            Some((op, wanted)) => op.matches(version.cmp(wanted)),
+        }
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some((op, ver)) = &self.constraint {
+            write!(f, "{}{}", op.as_str(), ver)?;
+        }
+        Ok(())
+    }
+}
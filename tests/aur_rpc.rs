@@ -0,0 +1,148 @@
+// AUR RPC client tests against a local mock HTTP server
+use anyhow::Result;
+use reap::aur_rpc::{AurClient, RpcError, SearchBy};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+/// Serve a single canned JSON response and report the request line that was received.
+fn mock_server(body: &'static str) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok() && header.trim() != "" {
+                header.clear();
+            }
+            let _ = tx.send(request_line.trim().to_string());
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (format!("http://{}", addr), rx)
+}
+
+/// Test a multi-arg info request is sent to the configured base URL and decoded into typed records.
+#[tokio::test]
+async fn test_info_batch_against_mock() -> Result<()> {
+    let (url, requests) = mock_server(
+        r#"{"version":5,"type":"multiinfo","resultcount":2,"results":[
+            {"ID":1,"Name":"foo","PackageBase":"foo-base","Version":"1.2-1","Depends":["bar>=2"],"Maintainer":"alice","OutOfDate":null},
+            {"ID":2,"Name":"baz","PackageBase":"baz","Version":"0.1-3","OutOfDate":1700000000}
+        ]}"#,
+    );
+    let client = AurClient::new(&format!("{}/", url));
+    let results = client.info(&["foo", "baz"]).await?;
+
+    let request = requests.recv()?;
+    assert!(request.starts_with("GET /rpc/?v=5&type=info&arg[]=foo&arg[]=baz "));
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].package_base, "foo-base");
+    assert_eq!(results[0].depends, vec!["bar>=2"]);
+    assert_eq!(results[0].maintainer.as_deref(), Some("alice"));
+    assert_eq!(results[1].out_of_date, Some(1_700_000_000));
+    Ok(())
+}
+
+/// Test an error-type RPC response is surfaced as an RPC error.
+#[tokio::test]
+async fn test_error_response_against_mock() -> Result<()> {
+    let (url, requests) = mock_server(
+        r#"{"version":5,"type":"error","resultcount":0,"results":[],"error":"Query arg too small."}"#,
+    );
+    let client = AurClient::new(&url);
+    let result = client.search(SearchBy::Maintainer, "a").await;
+
+    let request = requests.recv()?;
+    assert!(request.starts_with("GET /rpc/?v=5&type=search&by=maintainer&arg=a "));
+    match result {
+        Err(RpcError::Rpc(msg)) => assert_eq!(msg, "Query arg too small."),
+        other => panic!("expected RPC error, got {:?}", other),
+    }
+    Ok(())
+}
+
+/// Test the batched update check only reports packages with a newer AUR version.
+#[tokio::test]
+async fn test_check_updates_batched() -> Result<()> {
+    let (url, requests) = mock_server(
+        r#"{"version":5,"type":"multiinfo","resultcount":3,"results":[
+            {"Name":"foo","PackageBase":"foo","Version":"1.3-1","Maintainer":"alice","OutOfDate":1700000000},
+            {"Name":"bar","PackageBase":"bar","Version":"2.0-1"},
+            {"Name":"qux","PackageBase":"qux","Version":"1.9-1"}
+        ]}"#,
+    );
+    let client = AurClient::new(&url);
+    let installed = vec![
+        ("foo".to_string(), "1.2-1".to_string()),
+        ("bar".to_string(), "2.0-1".to_string()),
+        ("qux".to_string(), "1.10-1".to_string()),
+        ("gone".to_string(), "0.1-1".to_string()),
+    ];
+    let updates = reap::aur::check_updates_with(&client, &installed).await?;
+
+    let request = requests.recv()?;
+    assert!(
+        request.starts_with("GET /rpc/?v=5&type=info&arg[]=foo&arg[]=bar&arg[]=qux&arg[]=gone ")
+    );
+    assert_eq!(
+        updates,
+        vec![reap::aur::UpdateCandidate {
+            name: "foo".into(),
+            local: "1.2-1".into(),
+            remote: "1.3-1".into(),
+            out_of_date: Some(1_700_000_000),
+            maintainer: Some("alice".into()),
+        }]
+    );
+    Ok(())
+}
+
+/// Test split package members are grouped under their pkgbase in request order.
+#[tokio::test]
+async fn test_group_by_pkgbase() -> Result<()> {
+    let (url, requests) = mock_server(
+        r#"{"version":5,"type":"multiinfo","resultcount":3,"results":[
+            {"Name":"suite-gui","PackageBase":"suite","Version":"5-1"},
+            {"Name":"tool","PackageBase":"tool","Version":"1-1"},
+            {"Name":"suite-cli","PackageBase":"suite","Version":"5-1"}
+        ]}"#,
+    );
+    let client = AurClient::new(&url);
+    let groups =
+        reap::aur::group_by_pkgbase(&client, &["suite-cli", "tool", "suite-gui", "local-only"])
+            .await?;
+
+    requests.recv()?;
+    let group = |base: &str, members: &[&str]| {
+        (
+            base.to_string(),
+            members.iter().map(|m| m.to_string()).collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(
+        groups,
+        vec![
+            group("suite", &["suite-cli", "suite-gui"]),
+            group("tool", &["tool"]),
+            group("local-only", &["local-only"]),
+        ]
+    );
+    assert_eq!(
+        reap::aur::built_package_name(std::path::Path::new(
+            "/tmp/out/suite-cli-5-1-x86_64.pkg.tar.zst"
+        )),
+        Some("suite-cli".to_string())
+    );
+    Ok(())
+}
@@ -0,0 +1,140 @@
+// Clone cache tests against a local upstream git repository
+mod common;
+
+use anyhow::Result;
+use common::{git, scratch_dir};
+use reap::clone_cache::{CloneCache, CloneCacheError};
+use reap::core::{InstallOptions, review_changes};
+use std::path::Path;
+
+fn commit_pkgbuild(upstream: &Path, pkgver: &str) {
+    std::fs::write(
+        upstream.join("PKGBUILD"),
+        format!("pkgname=foo\npkgver={}\npkgrel=1\n", pkgver),
+    )
+    .unwrap();
+    git(upstream, &["add", "PKGBUILD"]);
+    git(upstream, &["commit", "-q", "-m", pkgver]);
+}
+
+fn commit_srcinfo(upstream: &Path, pkgver: &str, pkgrel: &str) {
+    std::fs::write(
+        upstream.join(".SRCINFO"),
+        format!(
+            "pkgbase = foo\n\tpkgver = {}\n\tpkgrel = {}\n\npkgname = foo\n",
+            pkgver, pkgrel
+        ),
+    )
+    .unwrap();
+    git(upstream, &["add", ".SRCINFO"]);
+    commit_pkgbuild(upstream, &format!("{}-{}", pkgver, pkgrel));
+}
+
+/// Test a clone is created once, fast-forwarded on sync and diffed against the reviewed commit.
+#[test]
+fn test_sync_fast_forward_and_review() -> Result<()> {
+    let scratch = scratch_dir("clone-cache");
+    let upstream = scratch.join("upstream");
+    std::fs::create_dir_all(&upstream)?;
+    git(&upstream, &["init", "-q"]);
+    commit_pkgbuild(&upstream, "1.0");
+    let url = upstream.to_string_lossy().to_string();
+
+    let cache = CloneCache::new(scratch.join("clones"));
+    let dir = cache.sync("foo", &url)?;
+    assert_eq!(dir, cache.path("foo"));
+    assert!(cache.reviewed("foo").is_none());
+    assert!(cache.commits_since_reviewed("foo").is_none());
+    assert!(cache.needs_review("foo"));
+    assert!(cache.diff_since_reviewed("foo")?.contains("+pkgver=1.0"));
+    let first = cache.head("foo")?;
+    cache.mark_reviewed("foo", &first)?;
+    assert!(!cache.needs_review("foo"));
+
+    // Local edits and build artefacts must not block the fast-forward
+    std::fs::write(dir.join("PKGBUILD"), "edited")?;
+    std::fs::write(dir.join("foo-1.0-1-x86_64.pkg.tar.zst"), "")?;
+    commit_pkgbuild(&upstream, "1.1");
+    cache.sync("foo", &url)?;
+
+    assert_ne!(cache.head("foo")?, first);
+    assert!(dir.join("foo-1.0-1-x86_64.pkg.tar.zst").exists());
+    assert_eq!(cache.reviewed("foo"), Some(first.clone()));
+    assert!(cache.needs_review("foo"));
+    let diff = cache.diff_since_reviewed("foo")?;
+    assert!(diff.contains("-pkgver=1.0") && diff.contains("+pkgver=1.1"));
+    let commits = cache.commits_since_reviewed("foo").unwrap();
+    assert_eq!(commits.len(), 1);
+    assert!(commits[0].ends_with(" 1.1"));
+
+    // An unattended accept builds without moving the reviewed commit
+    let unattended = InstallOptions {
+        noconfirm: true,
+        ..Default::default()
+    };
+    review_changes(&cache, "foo", &unattended)?;
+    assert_eq!(cache.reviewed("foo"), Some(first.clone()));
+    assert!(cache.needs_review("foo"));
+    assert_eq!(
+        cache.file_at("foo", &first, "PKGBUILD").as_deref(),
+        Some("pkgname=foo\npkgver=1.0\npkgrel=1")
+    );
+
+    let empty = scratch.join("empty");
+    std::fs::create_dir_all(&empty)?;
+    git(&empty, &["init", "-q"]);
+    assert!(matches!(
+        cache.sync("empty", &empty.to_string_lossy()),
+        Err(CloneCacheError::MissingPkgbuild(_))
+    ));
+
+    std::fs::remove_dir_all(&scratch)?;
+    Ok(())
+}
+
+/// Test the commit publishing a version is found from the .SRCINFO history and can be checked
+/// out and left again without breaking later syncs.
+#[test]
+fn test_find_and_checkout_version() -> Result<()> {
+    let scratch = scratch_dir("clone-cache-history");
+    let upstream = scratch.join("upstream");
+    std::fs::create_dir_all(&upstream)?;
+    git(&upstream, &["init", "-q"]);
+    commit_srcinfo(&upstream, "1.0", "1");
+    commit_srcinfo(&upstream, "1.1", "1");
+    commit_srcinfo(&upstream, "1.1", "2");
+    commit_pkgbuild(&upstream, "1.1-2 cleanup");
+    let url = upstream.to_string_lossy().to_string();
+
+    let cache = CloneCache::new(scratch.join("clones"));
+    cache.sync("foo", &url)?;
+    let history = cache.file_history("foo", ".SRCINFO")?;
+    assert_eq!(history.len(), 3);
+
+    let (newest_rel, version) = cache.find_version("foo", "1.1").unwrap();
+    assert_eq!(version, "1.1-2");
+    assert_eq!(newest_rel, history[0]);
+    let (first, version) = cache.find_version("foo", "1.0-1").unwrap();
+    assert_eq!(
+        (first.as_str(), version.as_str()),
+        (history[2].as_str(), "1.0-1")
+    );
+    assert!(cache.find_version("foo", "2.0").is_none());
+
+    let head = cache.head("foo")?;
+    let previous = cache.checkout("foo", &first)?;
+    assert_eq!(cache.head("foo")?, first);
+    let pkgbuild = std::fs::read_to_string(cache.path("foo").join("PKGBUILD"))?;
+    assert!(pkgbuild.contains("pkgver=1.0-1"));
+    cache.checkout("foo", &previous)?;
+    assert_eq!(cache.head("foo")?, head);
+
+    commit_srcinfo(&upstream, "1.2", "1");
+    cache.sync("foo", &url)?;
+    assert_eq!(
+        cache.find_version("foo", "1.2").unwrap().0,
+        cache.head("foo")?
+    );
+    std::fs::remove_dir_all(&scratch)?;
+    Ok(())
+}
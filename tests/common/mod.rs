@@ -0,0 +1,66 @@
+// Scratch directories, git repositories and fixture pacman databases shared by the tests
+#![allow(dead_code)]
+use anyhow::Result;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `reap-<name>-<pid>` under the temp dir, with anything left by an earlier run removed
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("reap-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// A fresh, empty [`scratch`] directory
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = scratch(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run git in `dir` as a fixed test identity and return its trimmed stdout
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=reap", "-c", "user.email=reap@localhost"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// A [`scratch_dir`] laid out as a pacman `DBPath` with empty `local` and `sync`
+pub fn fixture_root(name: &str) -> PathBuf {
+    let dir = scratch_dir(name);
+    std::fs::create_dir_all(dir.join("local")).unwrap();
+    std::fs::create_dir_all(dir.join("sync")).unwrap();
+    dir
+}
+
+/// Add `local/<dir>/desc`, and `files` when given, to a fixture database
+pub fn add_local(root: &Path, dir: &str, desc: &str, files: Option<&str>) {
+    let entry = root.join("local").join(dir);
+    std::fs::create_dir_all(&entry).unwrap();
+    std::fs::write(entry.join("desc"), desc).unwrap();
+    if let Some(files) = files {
+        std::fs::write(entry.join("files"), files).unwrap();
+    }
+}
+
+/// Write `sync/<repo>.db` as a gzip-compressed tar of `<dir>/desc` entries
+pub fn add_sync(root: &Path, repo: &str, entries: &[(&str, &str)]) -> Result<()> {
+    let file = std::fs::File::create(root.join("sync").join(format!("{}.db", repo)))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (dir, desc) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(desc.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, format!("{}/desc", dir), desc.as_bytes())?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
@@ -73,3 +73,102 @@ async fn test_flatpak_install_failure() -> Result<()> {
     assert!(result.is_err());
     Ok(())
 }
+
+/// Test `--insecure` no longer implies editing or a dry run and each flag maps to its own option.
+#[test]
+fn test_install_options_from_cli() -> Result<()> {
+    use clap::Parser;
+    use reap::cli::Cli;
+    use reap::core::InstallOptions;
+
+    let opts = InstallOptions::from_cli(&Cli::try_parse_from(["reap", "--insecure", "update"])?);
+    assert!(opts.insecure);
+    assert!(!opts.edit && !opts.dry_run && !opts.diff && !opts.noconfirm && !opts.chroot);
+
+    let cli = Cli::try_parse_from(["reap", "--edit", "--dry-run", "--diff", "--yes", "update"])?;
+    let opts = InstallOptions::from_cli(&cli);
+    assert!(opts.edit && opts.dry_run && opts.diff && opts.noconfirm);
+    assert!(!opts.insecure);
+
+    let opts = InstallOptions::from_cli(&Cli::try_parse_from(["reap", "--chroot", "update"])?);
+    assert!(opts.chroot);
+    assert!(!opts.remove_make);
+
+    let cli = Cli::try_parse_from(["reap", "--remove-make", "update"])?;
+    assert!(InstallOptions::from_cli(&cli).remove_make);
+    assert!(!InstallOptions::from_cli(&cli).devel);
+
+    let cli = Cli::try_parse_from(["reap", "--devel", "update"])?;
+    assert!(InstallOptions::from_cli(&cli).devel);
+    Ok(())
+}
+
+/// Test `reap mark` takes packages and exactly one of --asdeps and --asexplicit.
+#[test]
+fn test_mark_requires_one_reason() -> Result<()> {
+    use clap::Parser;
+    use reap::cli::{Cli, Commands};
+
+    let cli = Cli::try_parse_from(["reap", "mark", "--asdeps", "yay", "paru"])?;
+    match cli.command {
+        Commands::Mark {
+            pkgs,
+            asdeps,
+            asexplicit,
+        } => {
+            assert_eq!(pkgs, vec!["yay", "paru"]);
+            assert!(asdeps && !asexplicit);
+        }
+        other => panic!("parsed {:?}", other),
+    }
+    assert!(Cli::try_parse_from(["reap", "mark", "yay"]).is_err());
+    assert!(Cli::try_parse_from(["reap", "mark", "--asdeps", "--asexplicit", "yay"]).is_err());
+    assert!(Cli::try_parse_from(["reap", "mark", "--asexplicit"]).is_err());
+    Ok(())
+}
+
+/// Test the run summary lists every outcome and a failed step is reported as a failure.
+#[test]
+fn test_install_outcome_summary() -> Result<()> {
+    use reap::core::{InstallOutcome, OutcomeStatus, Source, format_summary};
+    use std::time::Duration;
+
+    let built = InstallOutcome {
+        package: "foo".to_string(),
+        source: Some(Source::Aur),
+        version: Some("1.2-1".to_string()),
+        duration: Duration::from_millis(1500),
+        status: OutcomeStatus::Installed,
+    };
+    let skipped = InstallOutcome::from_result(
+        "bar",
+        Source::Aur,
+        Duration::ZERO,
+        true,
+        Ok::<(), String>(()),
+    );
+    let failed = InstallOutcome::from_result(
+        "baz",
+        Source::Pacman,
+        Duration::ZERO,
+        false,
+        Err("pacman -S baz exited with 1"),
+    );
+    assert!(!built.is_failed() && !skipped.is_failed());
+    assert!(failed.is_failed());
+    assert_eq!(
+        skipped.status,
+        OutcomeStatus::Skipped("dry run".to_string())
+    );
+
+    let summary = format_summary(&[built, skipped, failed]);
+    let lines: Vec<&str> = summary.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("foo") && lines[1].contains("1.2-1"));
+    assert!(lines[1].contains("[AUR]") && lines[1].contains("1.5s  installed"));
+    assert!(lines[2].contains("skipped: dry run"));
+    assert!(
+        lines[3].contains("[PACMAN]") && lines[3].ends_with("failed: pacman -S baz exited with 1")
+    );
+    Ok(())
+}
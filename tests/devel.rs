@@ -0,0 +1,120 @@
+// VCS source tracking tests against scratch git repositories and devel records
+mod common;
+
+use anyhow::Result;
+use common::{git, scratch};
+use reap::devel::{DevelDb, DevelPackage, TrackedSource, VcsSource, vcs_sources};
+use reap::solver::BuildOrigin;
+use reap::srcinfo::SrcInfo;
+use std::path::Path;
+
+fn commit(dir: &Path, message: &str) -> String {
+    git(dir, &["commit", "--allow-empty", "-q", "-m", message]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+/// Test git sources are recognised with their branch, and pinned or non-git ones are not.
+#[test]
+fn test_parse_vcs_sources() -> Result<()> {
+    let srcinfo = SrcInfo::parse(
+        "\
+pkgbase = neovim-git
+\tpkgver = 0.11.0.r10.g1234567
+\tpkgrel = 1
+\tarch = x86_64
+\tsource = neovim::git+https://github.com/neovim/neovim.git
+\tsource = git+https://example.org/plugins.git?signed#branch=stable
+\tsource = git://example.org/runtime.git
+\tsource = git+https://example.org/pinned.git#tag=v1.0
+\tsource = git+https://example.org/fixed.git#commit=abc123
+\tsource = svn+https://example.org/trunk
+\tsource = https://example.org/fix.patch
+\tsource = other::git+https://github.com/neovim/neovim.git
+\tsha256sums = SKIP
+
+pkgname = neovim-git
+",
+    )?;
+    let sources = vcs_sources(&srcinfo);
+    assert_eq!(
+        sources,
+        vec![
+            VcsSource {
+                url: "https://github.com/neovim/neovim.git".into(),
+                branch: None,
+            },
+            VcsSource {
+                url: "https://example.org/plugins.git".into(),
+                branch: Some("stable".into()),
+            },
+            VcsSource {
+                url: "git://example.org/runtime.git".into(),
+                branch: None,
+            },
+        ]
+    );
+    assert_eq!(sources[0].git_ref(), "HEAD");
+    assert_eq!(sources[1].git_ref(), "refs/heads/stable");
+    Ok(())
+}
+
+/// Test a recorded package is reported once its upstream branch gets a new commit.
+#[test]
+fn test_moved_upstream_is_detected() -> Result<()> {
+    let dir = scratch("devel");
+    let upstream = dir.join("upstream");
+    std::fs::create_dir_all(&upstream)?;
+    git(&upstream, &["init", "-q", "-b", "main"]);
+    let built = commit(&upstream, "initial");
+
+    let source = VcsSource {
+        url: upstream.display().to_string(),
+        branch: Some("main".into()),
+    };
+    assert_eq!(source.ls_remote().as_deref(), Some(built.as_str()));
+    let missing = VcsSource {
+        url: dir.join("missing").display().to_string(),
+        branch: None,
+    };
+    assert_eq!(missing.ls_remote(), None);
+
+    let db = DevelDb::new(dir.join("devel.json"));
+    let package = |pkgnames: &[&str]| DevelPackage {
+        pkgnames: pkgnames.iter().map(|n| n.to_string()).collect(),
+        origin: BuildOrigin::Aur,
+        sources: vec![TrackedSource {
+            source: source.clone(),
+            commit: built.clone(),
+        }],
+    };
+    db.record("tool-git", package(&["tool-git"]))?;
+    db.record("tool-git", package(&["tool-git-docs"]))?;
+    assert_eq!(
+        db.load()["tool-git"].pkgnames,
+        vec!["tool-git-docs", "tool-git"]
+    );
+    let installed = |name: &str| name == "tool-git";
+    assert!(db.moved(installed, VcsSource::ls_remote).is_empty());
+
+    let head = commit(&upstream, "new work");
+    let moved = db.moved(installed, VcsSource::ls_remote);
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].0, "tool-git");
+    assert_eq!(moved[0].1.pkgnames, vec!["tool-git"]);
+    assert_eq!(moved[0].1.sources[0].commit, head);
+    // Nothing installed from the base, or an unreachable upstream, is not an upgrade
+    assert!(db.moved(|_| false, VcsSource::ls_remote).is_empty());
+    assert!(db.moved(installed, |_| None).is_empty());
+
+    // Rebuilding without a VCS source drops the record
+    db.record(
+        "tool-git",
+        DevelPackage {
+            sources: Vec::new(),
+            ..package(&["tool-git"])
+        },
+    )?;
+    assert!(db.load().is_empty());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
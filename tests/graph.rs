@@ -0,0 +1,118 @@
+// Reverse-dependency graph and removal plan tests against a fixture local database
+mod common;
+
+use anyhow::Result;
+use common::{add_local, fixture_root};
+use reap::core::{ReapError, plan_removal};
+use reap::graph::GraphVisualizer;
+use reap::pacman_db::{PackageDb, PacmanDb};
+use std::path::Path;
+
+/// Local database of `(name, explicit, depends, provides)` entries
+fn local_db(root: &Path, packages: &[(&str, bool, &[&str], &[&str])]) -> Result<PackageDb> {
+    for (name, explicit, depends, provides) in packages {
+        let desc = format!(
+            "%NAME%\n{}\n\n%VERSION%\n1.0-1\n\n%SIZE%\n1024\n\n%REASON%\n{}\n\n%DEPENDS%\n{}\n\n%PROVIDES%\n{}\n\n",
+            name,
+            if *explicit { 0 } else { 1 },
+            depends.join("\n"),
+            provides.join("\n")
+        );
+        add_local(root, &format!("{}-1.0-1", name), &desc, None);
+    }
+    Ok(PacmanDb::new(root, Vec::new()).local()?)
+}
+
+fn strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// Test removal refuses to break dependents unless cascading, ignores virtual dependencies
+/// another provider still satisfies, and adds unneeded dependencies when recursive.
+#[test]
+fn test_removal_plan() -> Result<()> {
+    let root = fixture_root("graph-removal");
+    let local = local_db(
+        &root,
+        &[
+            ("app", true, &["libfoo>=1", "sh"], &[]),
+            ("tool", true, &["app"], &[]),
+            ("libfoo", false, &["libbase"], &[]),
+            ("libbase", false, &[], &[]),
+            ("bash", false, &[], &["sh"]),
+            ("dash", false, &[], &["sh"]),
+        ],
+    )?;
+
+    let plan = plan_removal(&local, &strings(&["libfoo"]), &[], false, false)?;
+    assert_eq!(plan.broken, vec![("app".to_string(), "libfoo".to_string())]);
+
+    let plan = plan_removal(&local, &strings(&["libfoo"]), &[], true, false)?;
+    assert!(plan.broken.is_empty());
+    assert_eq!(plan.dependents, strings(&["app", "tool"]));
+    assert!(plan.orphaned.is_empty());
+
+    let plan = plan_removal(&local, &strings(&["dash"]), &[], false, false)?;
+    assert!(plan.broken.is_empty());
+    let plan = plan_removal(&local, &strings(&["dash", "bash"]), &[], false, false)?;
+    assert_eq!(plan.broken.len(), 1);
+
+    let plan = plan_removal(&local, &strings(&["app"]), &[], true, true)?;
+    assert_eq!(plan.dependents, strings(&["tool"]));
+    assert_eq!(
+        plan.orphaned,
+        strings(&["bash", "dash", "libbase", "libfoo"])
+    );
+    assert_eq!(plan.packages().len(), 6);
+
+    let plan = plan_removal(
+        &local,
+        &strings(&["app"]),
+        &strings(&["libfoo"]),
+        true,
+        true,
+    )?;
+    assert_eq!(plan.orphaned, strings(&["bash", "dash"]));
+
+    match plan_removal(&local, &strings(&["zsh"]), &[], false, false) {
+        Err(ReapError::NotInstalled(name)) => assert_eq!(name, "zsh"),
+        other => panic!("expected zsh to be missing, got {:?}", other),
+    }
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Test the reverse-dependency index and that orphans of a removal stop at packages
+/// something else still needs, even through dependency cycles.
+#[test]
+fn test_local_graph_orphans() -> Result<()> {
+    let root = fixture_root("graph-orphans");
+    let local = local_db(
+        &root,
+        &[
+            ("editor", true, &["plugin-a", "shared"], &[]),
+            ("viewer", true, &["shared"], &[]),
+            ("plugin-a", false, &["plugin-b"], &[]),
+            ("plugin-b", false, &["plugin-a", "shared"], &[]),
+            ("shared", false, &[], &[]),
+        ],
+    )?;
+    let graph = GraphVisualizer::from_local(&local);
+    assert_eq!(
+        graph.dependents_of("shared"),
+        ["editor", "plugin-b", "viewer"]
+    );
+    assert!(graph.dependents_of("viewer").is_empty());
+
+    assert_eq!(
+        graph.find_orphans_if_removed("editor"),
+        strings(&["plugin-a", "plugin-b"])
+    );
+    assert_eq!(
+        graph.find_orphans_if_all_removed(&strings(&["editor", "viewer"])),
+        strings(&["plugin-a", "plugin-b", "shared"])
+    );
+    assert!(graph.find_orphans_if_removed("viewer").is_empty());
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+// Package history tests against scratch history and archive directories
+mod common;
+
+use anyhow::Result;
+use common::scratch_dir;
+use reap::core::Source;
+use reap::history::{HistoryManager, InstallationHistory};
+use reap::local_repo::RepoPackage;
+
+/// Test rollback candidates skip the installed version and a rollback is recorded as its own
+/// entry that a later manager loads back.
+#[test]
+fn test_rollback_candidates_and_record() -> Result<()> {
+    let root = scratch_dir("history");
+    let archives = root.join("packages");
+    std::fs::create_dir_all(&archives)?;
+    for file in [
+        "yay-12.3.5-1-x86_64.pkg.tar.zst",
+        "yay-12.4.0-1-x86_64.pkg.tar.zst",
+    ] {
+        std::fs::write(archives.join(file), file)?;
+    }
+    let history_dir = root.join("history");
+    let mut history = HistoryManager::with_dirs(&history_dir, vec![archives.clone()]);
+
+    let candidates = history.rollback_candidates("yay", Some("12.4.0-1"));
+    let versions: Vec<&str> = candidates.iter().map(|c| c.version.as_str()).collect();
+    assert_eq!(versions, vec!["12.3.5-1"]);
+    assert_eq!(history.rollback_candidates("yay", None).len(), 2);
+
+    let archive =
+        RepoPackage::from_path(&archives.join("yay-12.3.5-1-x86_64.pkg.tar.zst")).unwrap();
+    history.record_rollback("yay", &archive, Some("12.4.0-1".to_string()))?;
+
+    let saved: InstallationHistory =
+        serde_json::from_str(&std::fs::read_to_string(history_dir.join("yay.json"))?)?;
+    assert_eq!(saved.current_version.as_deref(), Some("12.3.5-1"));
+    let entry = saved.snapshots.last().unwrap();
+    assert_eq!(entry.rollback_from.as_deref(), Some("12.4.0-1"));
+    assert_eq!(entry.source, Source::Aur);
+    assert_eq!(entry.backup_path, archive.path);
+
+    let mut reloaded = HistoryManager::with_dirs(&history_dir, vec![archives]);
+    assert!(reloaded.show_history("yay").is_ok());
+    assert!(reloaded.show_history("paru").is_err());
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
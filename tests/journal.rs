@@ -0,0 +1,102 @@
+// Transaction journal tests against a scratch journal file
+mod common;
+
+use anyhow::Result;
+use common::scratch;
+use reap::core::finish_transaction;
+use reap::journal::{Journal, JournalError, JournalStep, StepStatus, Transaction};
+
+fn transaction() -> Transaction {
+    let mut repo = JournalStep::new("repo", vec!["zlib".into()]);
+    repo.previous.insert("zlib".into(), Some("1:1.3-1".into()));
+    let mut yay = JournalStep::new("yay", vec!["yay".into(), "yay-debug".into()]);
+    yay.previous.insert("yay".into(), None);
+    Transaction::new(
+        "install",
+        vec!["yay".into(), "paru".into()],
+        vec![repo, yay, JournalStep::new("paru", vec!["paru".into()])],
+    )
+}
+
+/// Test an open transaction survives on disk with each step's progress until finished.
+#[test]
+fn test_transaction_progress_is_persisted() -> Result<()> {
+    let dir = scratch("journal");
+    let journal = Journal::new(dir.join("journal.json"));
+    assert!(journal.pending()?.is_none());
+
+    let mut tx = transaction();
+    journal.begin(&tx)?;
+    journal.mark(&mut tx, 0, StepStatus::Done)?;
+    let yay = tx.step("yay").unwrap();
+    journal.mark(&mut tx, yay, StepStatus::Failed("makepkg failed".into()))?;
+
+    // What a later `reap recover` sees after a crash
+    let recovered = journal.pending()?.unwrap();
+    assert_eq!(recovered, tx);
+    let completed: Vec<&str> = recovered.completed().map(|s| s.label.as_str()).collect();
+    assert_eq!(completed, vec!["repo"]);
+    let remaining: Vec<&str> = recovered.remaining().map(|s| s.label.as_str()).collect();
+    assert_eq!(remaining, vec!["yay", "paru"]);
+    assert_eq!(
+        recovered.steps[0].previous["zlib"].as_deref(),
+        Some("1:1.3-1")
+    );
+    assert_eq!(recovered.steps[1].previous["yay"], None);
+    assert_eq!(recovered.targets, vec!["yay", "paru"]);
+    assert!(!dir.join("journal.json.tmp").exists());
+
+    // Journals written before targets were recorded still load
+    let mut old: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(journal.path())?)?;
+    old.as_object_mut().unwrap().remove("targets");
+    std::fs::write(journal.path(), old.to_string())?;
+    assert!(journal.pending()?.unwrap().targets.is_empty());
+
+    journal.finish()?;
+    assert!(journal.pending()?.is_none());
+    journal.finish()?;
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Test a new transaction is refused while an interrupted one is waiting for recovery.
+#[test]
+fn test_interrupted_transaction_blocks_new_ones() -> Result<()> {
+    let dir = scratch("journal-interrupted");
+    let journal = Journal::new(dir.join("journal.json"));
+    let first = transaction();
+    journal.begin(&first)?;
+
+    match journal.begin(&transaction()) {
+        Err(JournalError::Interrupted(id)) => assert_eq!(id, first.id),
+        other => panic!("expected an interrupted transaction, got {:?}", other),
+    }
+    assert_eq!(journal.pending()?.unwrap(), first);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Test a transaction with a failed step is kept for recovery and only a fully finished one
+/// is closed.
+#[test]
+fn test_failed_transaction_is_left_for_recovery() -> Result<()> {
+    let dir = scratch("journal-failed");
+    let journal = Journal::new(dir.join("journal.json"));
+    let mut tx = transaction();
+    journal.begin(&tx)?;
+    journal.mark(&mut tx, 0, StepStatus::Done)?;
+    journal.mark(&mut tx, 1, StepStatus::Failed("makepkg failed".into()))?;
+    journal.mark(&mut tx, 2, StepStatus::Done)?;
+    assert!(!tx.is_complete());
+
+    finish_transaction(&journal);
+    assert_eq!(journal.pending()?.unwrap(), tx);
+
+    journal.mark(&mut tx, 1, StepStatus::Done)?;
+    assert!(tx.is_complete());
+    finish_transaction(&journal);
+    assert!(journal.pending()?.is_none());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,143 @@
+// Local repository tests against a scratch directory of fake package archives
+mod common;
+
+use anyhow::Result;
+use common::scratch_dir;
+use reap::local_repo::{LOCAL_REPO_NAME, LocalRepo, RepoPackage, find_archives};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+
+fn touch(dir: &Path, files: &[&str]) {
+    for file in files {
+        std::fs::write(dir.join(file), file.as_bytes()).unwrap();
+    }
+}
+
+/// Test archive names parse into name, version and arch, and signatures are not packages.
+#[test]
+fn test_repo_package_from_path() {
+    let pkg =
+        RepoPackage::from_path(Path::new("/r/foo-bar-1:2.0.r3-2-x86_64.pkg.tar.zst")).unwrap();
+    assert_eq!(pkg.name, "foo-bar");
+    assert_eq!(pkg.version, "1:2.0.r3-2");
+    assert_eq!(pkg.arch, "x86_64");
+    assert!(RepoPackage::from_path(Path::new("/r/foo-1.0-1-any.pkg.tar.zst.sig")).is_none());
+    assert!(RepoPackage::from_path(Path::new("/r/reap-local.db.tar.gz")).is_none());
+}
+
+/// Test builds are ordered by version, the newest is found and pruning keeps the newest builds.
+#[test]
+fn test_newest_and_prune() -> Result<()> {
+    let root = scratch_dir("local-repo");
+    touch(
+        &root,
+        &[
+            "foo-1.10-1-x86_64.pkg.tar.zst",
+            "foo-1.9-1-x86_64.pkg.tar.zst",
+            "foo-1.9-1-x86_64.pkg.tar.zst.sig",
+            "foo-1.2-1-x86_64.pkg.tar.zst",
+            "bar-0.1-1-any.pkg.tar.zst",
+            "reap-local.db.tar.gz",
+        ],
+    );
+    let repo = LocalRepo::new(&root, LOCAL_REPO_NAME);
+    assert_eq!(repo.db_path(), root.join("reap-local.db.tar.gz"));
+    assert_eq!(repo.packages().len(), 4);
+    assert_eq!(repo.newest("foo").unwrap().version, "1.10-1");
+    assert!(repo.newest("baz").is_none());
+
+    let removed = repo.prune(2)?;
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].version, "1.2-1");
+    let removed = repo.prune(0)?;
+    let versions: Vec<&str> = removed.iter().map(|p| p.version.as_str()).collect();
+    assert_eq!(versions, vec!["1.9-1"]);
+    assert!(!root.join("foo-1.9-1-x86_64.pkg.tar.zst.sig").exists());
+    assert!(root.join("foo-1.10-1-x86_64.pkg.tar.zst").exists());
+    assert!(root.join("bar-0.1-1-any.pkg.tar.zst").exists());
+    assert!(root.join("reap-local.db.tar.gz").exists());
+
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Test archives are gathered across directories newest first, one per version, with the
+/// earlier directory winning.
+#[test]
+fn test_find_archives() -> Result<()> {
+    let kept = scratch_dir("archives-kept");
+    let cache = scratch_dir("archives-cache");
+    touch(
+        &kept,
+        &[
+            "foo-1.2-1-x86_64.pkg.tar.zst",
+            "foo-bar-3.0-1-x86_64.pkg.tar.zst",
+        ],
+    );
+    touch(
+        &cache,
+        &[
+            "foo-1.2-1-x86_64.pkg.tar.zst",
+            "foo-1.10-1-x86_64.pkg.tar.zst",
+            "foo-1.10-1-x86_64.pkg.tar.zst.sig",
+            "foo-1:0.9-1-x86_64.pkg.tar.zst",
+        ],
+    );
+
+    let found = find_archives("foo", &[kept.clone(), cache.clone()]);
+    let versions: Vec<&str> = found.iter().map(|p| p.version.as_str()).collect();
+    assert_eq!(versions, vec!["1:0.9-1", "1.10-1", "1.2-1"]);
+    assert!(found[2].path.starts_with(&kept));
+    assert!(find_archives("baz", &[kept.clone(), cache.clone()]).is_empty());
+    std::fs::remove_dir_all(&kept)?;
+    std::fs::remove_dir_all(&cache)?;
+    Ok(())
+}
+
+/// Test the pacman.conf section is detected and files are served over HTTP.
+#[test]
+fn test_configured_and_serve() -> Result<()> {
+    let root = scratch_dir("local-repo-serve");
+    touch(&root, &["foo-1.0-1-any.pkg.tar.zst"]);
+    let repo = LocalRepo::new(&root, LOCAL_REPO_NAME);
+    assert!(!repo.is_configured("[core]\nInclude = /etc/pacman.d/mirrorlist\n"));
+    let snippet = repo.pacman_conf_snippet("http://host:8080");
+    assert!(repo.is_configured(&format!("[core]\n{}", snippet)));
+    assert!(snippet.contains("SigLevel = Optional TrustAll"));
+    let signed = repo.clone().with_sign_key(Some("0123456789ABCDEF".into()));
+    assert_eq!(signed.sign_key(), Some("0123456789ABCDEF"));
+    assert!(
+        signed
+            .pacman_conf_snippet("http://host:8080")
+            .contains("SigLevel = Required\n")
+    );
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let bind = listener.local_addr()?.to_string();
+    drop(listener);
+    let server = repo.clone();
+    let server_bind = bind.clone();
+    std::thread::spawn(move || server.serve(&server_bind));
+
+    let get = |target: &str| -> Result<String> {
+        for _ in 0..50 {
+            if let Ok(mut stream) = TcpStream::connect(&bind) {
+                write!(stream, "GET {} HTTP/1.0\r\n\r\n", target)?;
+                let mut response = String::new();
+                stream.read_to_string(&mut response)?;
+                return Ok(response);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        anyhow::bail!("server did not start")
+    };
+    let response = get("/foo-1.0-1-any.pkg.tar.zst")?;
+    assert!(response.starts_with("HTTP/1.0 200 OK"));
+    assert!(response.ends_with("foo-1.0-1-any.pkg.tar.zst"));
+    assert!(get("/../etc/passwd")?.starts_with("HTTP/1.0 404"));
+    assert!(get("/missing.pkg.tar.zst")?.starts_with("HTTP/1.0 404"));
+
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
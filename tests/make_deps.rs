@@ -0,0 +1,29 @@
+// Make-only dependency record tests against a scratch record file
+mod common;
+
+use anyhow::Result;
+use common::scratch;
+use reap::make_deps::MakeDeps;
+
+/// Test make-only dependencies are recorded per build and forgotten once removed.
+#[test]
+fn test_record_and_forget() -> Result<()> {
+    let dir = scratch("make-deps");
+    let record = MakeDeps::new(dir.join("makedepends.json"));
+    assert!(record.all().is_empty());
+
+    record.record("yay", &["go".to_string()])?;
+    record.record("paru", &["rust".to_string(), "go".to_string()])?;
+    record.record("nothing", &[])?;
+    assert_eq!(record.all(), vec!["go", "rust"]);
+    assert_eq!(record.load().len(), 2);
+
+    record.forget(&["go".to_string()])?;
+    let entries = record.load();
+    assert_eq!(entries.len(), 1);
+    assert!(entries["paru"].contains("rust"));
+    record.forget(&["rust".to_string()])?;
+    assert!(record.load().is_empty());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
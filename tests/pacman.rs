@@ -0,0 +1,46 @@
+// Pacman lock detection tests against a fake /proc
+mod common;
+
+use common::scratch_dir;
+use reap::pacman::{LockOwner, lock_owner};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+fn add_process(proc_root: &Path, pid: u32, command: &str, open: &[&Path]) {
+    let dir = proc_root.join(pid.to_string());
+    std::fs::create_dir_all(dir.join("fd")).unwrap();
+    std::fs::write(dir.join("comm"), format!("{}\n", command)).unwrap();
+    for (fd, target) in open.iter().enumerate() {
+        symlink(target, dir.join("fd").join(fd.to_string())).unwrap();
+    }
+}
+
+/// Test the process with the lock open is reported, falling back to a running pacman.
+#[test]
+fn test_lock_owner() {
+    let proc_root = scratch_dir("proc");
+    let lock = proc_root.join("db.lck");
+    std::fs::create_dir_all(proc_root.join("self")).unwrap();
+    add_process(&proc_root, 100, "bash", &[Path::new("/dev/null")]);
+    assert_eq!(lock_owner(&proc_root, &lock), None);
+
+    add_process(&proc_root, 200, "pacman", &[]);
+    let owner = lock_owner(&proc_root, &lock).unwrap();
+    assert_eq!(owner.pid, 200);
+    assert_eq!(owner.to_string(), "pacman (pid 200)");
+
+    add_process(
+        &proc_root,
+        300,
+        "pamac-daemon",
+        &[Path::new("/dev/null"), &lock],
+    );
+    assert_eq!(
+        lock_owner(&proc_root, &lock),
+        Some(LockOwner {
+            pid: 300,
+            command: "pamac-daemon".to_string()
+        })
+    );
+    std::fs::remove_dir_all(&proc_root).unwrap();
+}
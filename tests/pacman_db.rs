@@ -0,0 +1,181 @@
+// pacman database reader tests against fixture local and sync databases
+mod common;
+
+use anyhow::Result;
+use common::{add_local, add_sync, fixture_root};
+use reap::pacman_db::{InstallReason, PacmanDb, repos_from_conf};
+use reap::version::Dependency;
+
+const BASH: &str = "%NAME%\nbash\n\n%VERSION%\n5.2.026-2\n\n%DESC%\nThe GNU Bourne Again shell\n\n%SIZE%\n9441280\n\n%REASON%\n0\n\n%DEPENDS%\nreadline\nglibc\n\n%OPTDEPENDS%\nbash-completion: for tab completion\n\n%PROVIDES%\nsh\n\n";
+const READLINE: &str = "%NAME%\nreadline\n\n%VERSION%\n8.2.010-1\n\n%SIZE%\n1000\n\n%REASON%\n1\n\n%PROVIDES%\nlibreadline.so=8-64\n\n";
+const YAY: &str =
+    "%NAME%\nyay-bin\n\n%VERSION%\n12.3.5-1\n\n%BASE%\nyay-bin\n\n%PROVIDES%\nyay=12.3.5\n\n";
+
+/// Test the local database is parsed with reasons, dependencies, provides and files.
+#[test]
+fn test_local_database() -> Result<()> {
+    let root = fixture_root("pacman-local");
+    add_local(
+        &root,
+        "bash-5.2.026-2",
+        BASH,
+        Some("%FILES%\nusr/\nusr/bin/\nusr/bin/bash\n\n%BACKUP%\netc/bash.bashrc\tabc\n\n"),
+    );
+    add_local(&root, "readline-8.2.010-1", READLINE, None);
+    std::fs::write(root.join("local/ALPM_DB_VERSION"), "9\n")?;
+    let db = PacmanDb::new(&root, Vec::new());
+
+    let local = db.local()?;
+    assert_eq!(local.len(), 2);
+    let bash = local.get("bash").unwrap();
+    assert_eq!(bash.version, "5.2.026-2");
+    assert_eq!(bash.depends, vec!["readline", "glibc"]);
+    assert_eq!(bash.optdepends, vec!["bash-completion: for tab completion"]);
+    assert_eq!(bash.reason, InstallReason::Explicit);
+    assert_eq!(bash.installed_size, 9441280);
+    assert!(bash.files.is_empty());
+    assert_eq!(
+        local.get("readline").unwrap().reason,
+        InstallReason::Dependency
+    );
+
+    let providers = local.providers(&Dependency::parse("sh"));
+    assert_eq!(providers.len(), 1);
+    assert!(
+        local
+            .providers(&Dependency::parse("readline>=9"))
+            .is_empty()
+    );
+    assert_eq!(
+        local.providers(&Dependency::parse("libreadline.so=8-64"))[0].name,
+        "readline"
+    );
+
+    let with_files = db.local_with_files()?;
+    assert_eq!(with_files.owner("/usr/bin/bash").unwrap().name, "bash");
+    assert!(with_files.owner("/usr/bin/zsh").is_none());
+
+    assert_eq!(db.installed("readline").unwrap().version, "8.2.010-1");
+    assert!(db.installed("read").is_none());
+    assert!(db.installed("bash").unwrap().files.is_empty());
+    assert_eq!(
+        db.installed_with_files("bash").unwrap().files,
+        vec!["usr/", "usr/bin/", "usr/bin/bash"]
+    );
+    assert_eq!(
+        db.local_entry("bash"),
+        Some(root.join("local/bash-5.2.026-2"))
+    );
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Test sync databases are read from their tarballs, in repository order, and foreign
+/// packages are the installed ones no repository has.
+#[test]
+fn test_sync_database_and_foreign() -> Result<()> {
+    let root = fixture_root("pacman-sync");
+    add_local(&root, "bash-5.2.026-2", BASH, None);
+    add_local(&root, "yay-bin-12.3.5-1", YAY, None);
+    let readline = format!("{}%MAKEDEPENDS%\nncurses\n\n", READLINE);
+    add_sync(
+        &root,
+        "core",
+        &[("bash-5.2.026-2", BASH), ("readline-8.2.010-1", &readline)],
+    )?;
+    add_sync(
+        &root,
+        "extra",
+        &[("bash-5.3-1", &BASH.replace("5.2.026-2", "5.3-1"))],
+    )?;
+    let db = PacmanDb::new(&root, vec!["core".into(), "extra".into(), "missing".into()]);
+
+    let core = db.sync("core")?;
+    assert_eq!(core.repo.as_deref(), Some("core"));
+    assert_eq!(core.len(), 2);
+    assert_eq!(core.get("bash").unwrap().provides, vec!["sh"]);
+    assert_eq!(core.get("readline").unwrap().makedepends, vec!["ncurses"]);
+    assert!(db.sync("missing").is_err());
+
+    let all = db.sync_all();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[1].get("bash").unwrap().version, "5.3-1");
+    assert_eq!(db.sync_repo_of("readline").as_deref(), Some("core"));
+    assert_eq!(db.sync_repo_of("yay-bin"), None);
+
+    let foreign: Vec<String> = db.foreign()?.into_iter().map(|p| p.name).collect();
+    assert_eq!(foreign, vec!["yay-bin"]);
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Test repositories are taken from pacman.conf sections in order, skipping [options].
+#[test]
+fn test_repos_from_conf() {
+    let conf = "[options]\nHoldPkg = pacman\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n\n#[testing]\n[extra]\nInclude = /etc/pacman.d/mirrorlist\n";
+    assert_eq!(repos_from_conf(conf), vec!["core", "extra"]);
+}
+
+fn desc(name: &str, reason: u8, size: u64, depends: &[&str], optdepends: &[&str]) -> String {
+    format!(
+        "%NAME%\n{}\n\n%VERSION%\n1.0-1\n\n%SIZE%\n{}\n\n%REASON%\n{}\n\n%DEPENDS%\n{}\n\n%OPTDEPENDS%\n{}\n\n",
+        name,
+        size,
+        reason,
+        depends.join("\n"),
+        optdepends.join("\n")
+    )
+}
+
+/// Test orphans are everything explicit and kept packages do not reach through depends,
+/// optdepends and provides, including chains and cycles of orphans.
+#[test]
+fn test_orphans_to_fixpoint() -> Result<()> {
+    let root = fixture_root("pacman-orphans");
+    let packages = [
+        desc("app", 0, 10, &["libcommon", "sh"], &["helper: for extras"]),
+        desc("libcommon", 1, 20, &[], &[]),
+        desc("dash", 1, 30, &[], &[]) + "%PROVIDES%\nsh\n\n",
+        desc("helper", 1, 40, &[], &[]),
+        // An orphaned chain: removing yay-bin orphans go-runtime, which alone needs libgo
+        desc("yay-bin", 1, 100, &["go-runtime>=1", "libcommon"], &[]),
+        desc("go-runtime", 1, 200, &["libgo"], &[]),
+        desc("libgo", 1, 300, &[], &[]),
+        // Orphans that only need each other
+        desc("cycle-a", 1, 1, &["cycle-b"], &[]),
+        desc("cycle-b", 1, 2, &["cycle-a"], &[]),
+    ];
+    for pkg in &packages {
+        let name = pkg.lines().nth(1).unwrap();
+        add_local(&root, &format!("{}-1.0-1", name), pkg, None);
+    }
+    let local = PacmanDb::new(&root, Vec::new()).local()?;
+
+    let names = |keep: &[String]| -> Vec<String> {
+        local
+            .orphans(keep)
+            .into_iter()
+            .map(|p| p.name.clone())
+            .collect()
+    };
+    assert_eq!(
+        names(&[]),
+        vec!["cycle-a", "cycle-b", "go-runtime", "libgo", "yay-bin"]
+    );
+    assert_eq!(
+        names(&["yay-bin".into(), "cycle-b".into()]),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        names(&["go-runtime".into()]),
+        vec!["cycle-a", "cycle-b", "yay-bin"]
+    );
+
+    let reclaimed: u64 = local.orphans(&[]).iter().map(|p| p.installed_size).sum();
+    assert_eq!(reclaimed, 603);
+    let needed = local.required_by(&["yay-bin".into()]);
+    assert!(needed.contains("libgo") && needed.contains("libcommon"));
+    assert!(!needed.contains("app"));
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,177 @@
+// Build scheduler tests with a recording executor instead of makepkg and pacman
+use reap::core::OutcomeStatus;
+use reap::scheduler::{BuildExecutor, Scheduler};
+use reap::solver::{BuildOrigin, BuildPlan, PlannedBuild};
+use reap::srcinfo::SrcInfo;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+fn planned(pkgbase: &str, depends: &[&str]) -> PlannedBuild {
+    let mut srcinfo = format!("pkgbase = {}\n\tpkgver = 1\n\tpkgrel = 1\n", pkgbase);
+    for dep in depends {
+        srcinfo.push_str(&format!("\tdepends = {}\n", dep));
+    }
+    srcinfo.push_str(&format!("pkgname = {}\n", pkgbase));
+    PlannedBuild {
+        pkgbase: pkgbase.to_string(),
+        pkgnames: vec![pkgbase.to_string()],
+        version: "1-1".to_string(),
+        origin: BuildOrigin::Aur,
+        srcinfo: SrcInfo::parse(&srcinfo).unwrap(),
+    }
+}
+
+fn plan(builds: Vec<PlannedBuild>) -> BuildPlan {
+    BuildPlan {
+        builds,
+        ..Default::default()
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    fail: Vec<&'static str>,
+    events: Mutex<Vec<String>>,
+    running: AtomicUsize,
+    max_running: AtomicUsize,
+}
+
+impl Recorder {
+    fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn position(&self, event: &str) -> usize {
+        self.events().iter().position(|e| e == event).unwrap()
+    }
+}
+
+impl BuildExecutor for Recorder {
+    fn build(&self, build: &PlannedBuild, deps: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        let deps: Vec<String> = deps.iter().map(|d| d.display().to_string()).collect();
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("build {} [{}]", build.pkgbase, deps.join(" ")));
+        std::thread::sleep(Duration::from_millis(30));
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        if self.fail.contains(&build.pkgbase.as_str()) {
+            return Err("makepkg failed".to_string());
+        }
+        Ok(vec![PathBuf::from(format!(
+            "{}.pkg.tar.zst",
+            build.pkgbase
+        ))])
+    }
+
+    fn install(&self, archives: &[PathBuf]) -> Result<(), String> {
+        let mut names: Vec<String> = archives.iter().map(|a| a.display().to_string()).collect();
+        names.sort();
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("install {}", names.join(" ")));
+        Ok(())
+    }
+}
+
+/// Test independent builds run concurrently up to the job limit and a dependent only starts
+/// once its dependency is installed.
+#[test]
+fn test_dependents_wait_for_install() {
+    let plan = plan(vec![
+        planned("liba", &[]),
+        planned("libb", &[]),
+        planned("libc", &[]),
+        planned("app", &["liba", "libb"]),
+    ]);
+    let recorder = Recorder::default();
+    let reports = Scheduler::new(2, true).run(&plan, &recorder);
+
+    assert!(reports.iter().all(|r| r.status == OutcomeStatus::Installed));
+    assert_eq!(recorder.max_running.load(Ordering::SeqCst), 2);
+    let events = recorder.events();
+    let app = recorder.position("build app [liba.pkg.tar.zst libb.pkg.tar.zst]");
+    for dep in ["liba", "libb"] {
+        let installed = events
+            .iter()
+            .position(|e| e.starts_with("install") && e.contains(&format!("{}.pkg", dep)))
+            .unwrap();
+        assert!(
+            installed < app,
+            "{} installed after app started: {:?}",
+            dep,
+            events
+        );
+    }
+    let installs = events.iter().filter(|e| e.starts_with("install"));
+    let installed: Vec<&str> = installs.flat_map(|e| e.split(' ').skip(1)).collect();
+    assert_eq!(
+        installed.len(),
+        4,
+        "each build installed once: {:?}",
+        events
+    );
+}
+
+/// Test chroot builds get their dependencies' archives instead of waiting for the install.
+#[test]
+fn test_chroot_builds_get_dependency_archives() {
+    let plan = plan(vec![
+        planned("libbase", &[]),
+        planned("libmid", &["libbase"]),
+        planned("app", &["libmid"]),
+    ]);
+    let recorder = Recorder::default();
+    let reports = Scheduler::new(4, false).run(&plan, &recorder);
+
+    assert!(reports.iter().all(|r| r.status == OutcomeStatus::Installed));
+    recorder.position("build libmid [libbase.pkg.tar.zst]");
+    recorder.position("build app [libbase.pkg.tar.zst libmid.pkg.tar.zst]");
+}
+
+/// Test a failed build skips everything depending on it, directly or not, while unrelated
+/// builds still finish.
+#[test]
+fn test_failure_skips_dependents() {
+    let plan = plan(vec![
+        planned("libbroken", &[]),
+        planned("libok", &[]),
+        planned("libmid", &["libbroken"]),
+        planned("app", &["libmid", "libok"]),
+        planned("tool", &["libok"]),
+    ]);
+    let recorder = Recorder {
+        fail: vec!["libbroken"],
+        ..Default::default()
+    };
+    let reports = Scheduler::new(4, true).run(&plan, &recorder);
+    let status = |pkgbase: &str| {
+        reports
+            .iter()
+            .find(|r| r.pkgbase == pkgbase)
+            .unwrap()
+            .status
+            .clone()
+    };
+
+    assert_eq!(
+        status("libbroken"),
+        OutcomeStatus::Failed("makepkg failed".to_string())
+    );
+    assert_eq!(
+        status("libmid"),
+        OutcomeStatus::Skipped("needs libbroken".to_string())
+    );
+    assert_eq!(
+        status("app"),
+        OutcomeStatus::Skipped("needs libmid".to_string())
+    );
+    assert_eq!(status("libok"), OutcomeStatus::Installed);
+    assert_eq!(status("tool"), OutcomeStatus::Installed);
+    assert!(!recorder.events().iter().any(|e| e.starts_with("build app")));
+}
@@ -0,0 +1,248 @@
+// Dependency solver tests against an in-memory package universe
+use anyhow::Result;
+use reap::aur_rpc::AurPackage;
+use reap::solver::{
+    self, BuildCandidate, PackageLookup, Provider, ProviderChooser, ProviderPolicy, SolveError,
+};
+use reap::srcinfo::SrcInfo;
+use reap::version::{Dependency, Version};
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct Fixture {
+    installed: HashMap<&'static str, &'static str>,
+    repo: Vec<&'static str>,
+    aur: Vec<&'static str>,
+}
+
+impl Fixture {
+    fn srcinfos(&self) -> impl Iterator<Item = SrcInfo> + '_ {
+        self.aur.iter().map(|s| SrcInfo::parse(s).unwrap())
+    }
+}
+
+impl PackageLookup for Fixture {
+    fn is_installed(&self, dep: &Dependency) -> bool {
+        self.installed
+            .get(dep.name.as_str())
+            .is_some_and(|v| dep.satisfied_by(&Version::parse(v)))
+    }
+
+    fn repo_providers(&self, dep: &Dependency) -> Vec<String> {
+        self.repo
+            .iter()
+            .filter(|r| **r == dep.name)
+            .map(|r| r.to_string())
+            .collect()
+    }
+
+    fn tap_providers(&self, _dep: &Dependency) -> Vec<BuildCandidate> {
+        Vec::new()
+    }
+
+    fn aur_providers(&self, dep: &Dependency) -> Vec<AurPackage> {
+        let mut providers = Vec::new();
+        for info in self.srcinfos() {
+            for member in &info.packages {
+                let provides = member.provides.host();
+                if solver::provides_dep(&member.pkgname, &info.version(), &provides, dep) {
+                    providers.push(AurPackage {
+                        name: member.pkgname.clone(),
+                        package_base: info.pkgbase.clone(),
+                        version: info.full_version(),
+                        provides,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        providers
+    }
+
+    fn aur_srcinfo(&self, pkgbase: &str) -> Option<SrcInfo> {
+        self.srcinfos().find(|info| info.pkgbase == pkgbase)
+    }
+}
+
+/// Chooser for fixtures without ambiguous providers
+struct NoPrompt;
+
+impl ProviderChooser for NoPrompt {
+    fn choose(&self, _dep: &Dependency, _providers: &[Provider]) -> Option<usize> {
+        None
+    }
+}
+
+fn targets(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+/// Test transitive AUR deps are built first, provides are honoured and make-only deps are split out.
+#[test]
+fn test_recursive_plan_order() -> Result<()> {
+    let fixture = Fixture {
+        installed: HashMap::from([("glibc", "2.40-1")]),
+        repo: vec!["cmake", "python"],
+        aur: vec![
+            "pkgbase = app\n\tpkgver = 1.0\n\tpkgrel = 1\n\tdepends = libwidget>=2\n\tdepends = glibc\n\tmakedepends = cmake\npkgname = app\n",
+            "pkgbase = libwidget\n\tpkgver = 2.1\n\tpkgrel = 1\n\tdepends = render-backend\n\tmakedepends = codegen\npkgname = libwidget\n",
+            "pkgbase = render-backend-bin\n\tpkgver = 0.9\n\tpkgrel = 2\n\tprovides = render-backend\n\tdepends = python\npkgname = render-backend-bin\n",
+            "pkgbase = codegen\n\tpkgver = 3\n\tpkgrel = 1\npkgname = codegen\n",
+        ],
+    };
+    let plan = solver::resolve(&fixture, &NoPrompt, &targets(&["app"]))?;
+
+    let order: Vec<&str> = plan.builds.iter().map(|b| b.pkgbase.as_str()).collect();
+    let pos = |name: &str| order.iter().position(|b| *b == name).unwrap();
+    assert_eq!(order.len(), 4);
+    assert_eq!(order.last(), Some(&"app"));
+    assert!(pos("libwidget") < pos("app"));
+    assert!(pos("render-backend-bin") < pos("libwidget"));
+    assert!(pos("codegen") < pos("libwidget"));
+
+    assert_eq!(plan.repo, vec!["cmake", "python"]);
+    let mut make_only = plan.make_only.clone();
+    make_only.sort();
+    assert_eq!(make_only, vec!["cmake", "codegen"]);
+    assert!(plan.is_target("app"));
+    assert!(!plan.is_target("libwidget"));
+    Ok(())
+}
+
+/// Test which earlier builds must be installed before a later one can be built.
+#[test]
+fn test_build_needs_earlier_builds() -> Result<()> {
+    let fixture = Fixture {
+        repo: vec!["python"],
+        aur: vec![
+            "pkgbase = app\n\tpkgver = 1.0\n\tpkgrel = 1\n\tdepends = libwidget\npkgname = app\n",
+            "pkgbase = libwidget\n\tpkgver = 2.1\n\tpkgrel = 1\n\tdepends = render-backend\n\tmakedepends = codegen\npkgname = libwidget\n",
+            "pkgbase = render-backend-bin\n\tpkgver = 0.9\n\tpkgrel = 2\n\tprovides = render-backend\npkgname = render-backend-bin\n",
+            "pkgbase = codegen\n\tpkgver = 3\n\tpkgrel = 1\npkgname = codegen\n",
+        ],
+        ..Default::default()
+    };
+    let plan = solver::resolve(&fixture, &NoPrompt, &targets(&["app"]))?;
+    let build = |name: &str| plan.builds.iter().find(|b| b.pkgbase == name).unwrap();
+
+    assert!(build("libwidget").build_needs(build("render-backend-bin")));
+    assert!(build("libwidget").build_needs(build("codegen")));
+    assert!(build("app").build_needs(build("libwidget")));
+    assert!(!build("app").build_needs(build("codegen")));
+    assert!(!build("codegen").build_needs(build("libwidget")));
+
+    // app needs libwidget and, through it, everything libwidget was built against
+    let index = |name: &str| plan.builds.iter().position(|b| b.pkgbase == name).unwrap();
+    let mut expected = vec![
+        index("libwidget"),
+        index("render-backend-bin"),
+        index("codegen"),
+    ];
+    expected.sort_unstable();
+    assert_eq!(plan.needed_before(index("app")), expected);
+    assert!(plan.needed_before(index("codegen")).is_empty());
+    Ok(())
+}
+
+/// Test an installed package that is too old does not satisfy a versioned dependency.
+#[test]
+fn test_version_constraint_forces_build() -> Result<()> {
+    let fixture = Fixture {
+        installed: HashMap::from([("libfoo", "1.9-1")]),
+        aur: vec![
+            "pkgbase = tool\n\tpkgver = 1\n\tpkgrel = 1\n\tdepends = libfoo>=1.10\npkgname = tool\n",
+            "pkgbase = libfoo\n\tpkgver = 1.10\n\tpkgrel = 1\npkgname = libfoo\n",
+        ],
+        ..Default::default()
+    };
+    let plan = solver::resolve(&fixture, &NoPrompt, &targets(&["tool"]))?;
+    let order: Vec<&str> = plan.builds.iter().map(|b| b.pkgbase.as_str()).collect();
+    assert_eq!(order, vec!["libfoo", "tool"]);
+    assert!(plan.make_only.is_empty());
+    Ok(())
+}
+
+/// Test split members requested from one base share a single build.
+#[test]
+fn test_split_members_share_build() -> Result<()> {
+    let fixture = Fixture {
+        aur: vec![
+            "pkgbase = suite\n\tpkgver = 5\n\tpkgrel = 1\npkgname = suite-cli\npkgname = suite-gui\n\tdepends = suite-cli\npkgname = suite-docs\n",
+        ],
+        ..Default::default()
+    };
+    let plan = solver::resolve(&fixture, &NoPrompt, &targets(&["suite-gui"]))?;
+    assert_eq!(plan.builds.len(), 1);
+    assert_eq!(plan.builds[0].pkgnames, vec!["suite-gui", "suite-cli"]);
+    Ok(())
+}
+
+/// Test dependency cycles and missing dependencies are reported.
+#[test]
+fn test_cycle_and_missing_dep() {
+    let fixture = Fixture {
+        aur: vec![
+            "pkgbase = alpha\n\tpkgver = 1\n\tpkgrel = 1\n\tdepends = beta\npkgname = alpha\n",
+            "pkgbase = beta\n\tpkgver = 1\n\tpkgrel = 1\n\tmakedepends = alpha\npkgname = beta\n",
+            "pkgbase = gamma\n\tpkgver = 1\n\tpkgrel = 1\n\tdepends = nowhere\npkgname = gamma\n",
+        ],
+        ..Default::default()
+    };
+    match solver::resolve(&fixture, &NoPrompt, &targets(&["alpha"])) {
+        Err(SolveError::Cycle(path)) => {
+            assert_eq!(path.first(), path.last());
+            assert!(path.contains(&"alpha".to_string()) && path.contains(&"beta".to_string()));
+        }
+        other => panic!("expected cycle, got {:?}", other.map(|p| p.builds.len())),
+    }
+    assert_eq!(
+        solver::resolve(&fixture, &NoPrompt, &targets(&["gamma"])).unwrap_err(),
+        SolveError::Unresolvable {
+            dep: "nowhere".to_string(),
+            required_by: Some("gamma".to_string()),
+        }
+    );
+}
+
+/// Test provider selection: installed providers win, then the profile preference, then the first in noconfirm mode.
+#[test]
+fn test_virtual_dep_provider_selection() -> Result<()> {
+    let mut fixture = Fixture {
+        aur: vec![
+            "pkgbase = ide\n\tpkgver = 1\n\tpkgrel = 1\n\tdepends = java-runtime>=17\npkgname = ide\n",
+            "pkgbase = jdk11-bin\n\tpkgver = 11\n\tpkgrel = 1\n\tprovides = java-runtime=11\npkgname = jdk11-bin\n",
+            "pkgbase = jdk17-bin\n\tpkgver = 17\n\tpkgrel = 1\n\tprovides = java-runtime=17\npkgname = jdk17-bin\n",
+            "pkgbase = jdk21-bin\n\tpkgver = 21\n\tpkgrel = 1\n\tprovides = java-runtime=21\npkgname = jdk21-bin\n",
+        ],
+        ..Default::default()
+    };
+    let built = |plan: &solver::BuildPlan| -> Vec<String> {
+        plan.builds.iter().map(|b| b.pkgbase.clone()).collect()
+    };
+
+    // Still ambiguous without a preference and prompting is not possible here
+    assert_eq!(
+        solver::resolve(&fixture, &NoPrompt, &targets(&["ide"])).unwrap_err(),
+        SolveError::NoProviderSelected("java-runtime>=17".to_string())
+    );
+
+    let noconfirm = ProviderPolicy {
+        noconfirm: true,
+        ..Default::default()
+    };
+    let plan = solver::resolve(&fixture, &noconfirm, &targets(&["ide"]))?;
+    assert_eq!(built(&plan), vec!["jdk17-bin", "ide"]);
+
+    let preferred = ProviderPolicy {
+        preferences: HashMap::from([("java-runtime".to_string(), "jdk21-bin".to_string())]),
+        noconfirm: true,
+    };
+    let plan = solver::resolve(&fixture, &preferred, &targets(&["ide"]))?;
+    assert_eq!(built(&plan), vec!["jdk21-bin", "ide"]);
+
+    // An installed provider wins over the profile preference
+    fixture.installed = HashMap::from([("jdk17-bin", "17-1")]);
+    let plan = solver::resolve(&fixture, &preferred, &targets(&["java-runtime"]))?;
+    assert_eq!(built(&plan), vec!["jdk17-bin"]);
+    Ok(())
+}
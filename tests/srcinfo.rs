@@ -0,0 +1,111 @@
+// .SRCINFO parser tests
+use anyhow::Result;
+use reap::srcinfo::{SrcInfo, SrcInfoError};
+
+const SPLIT_SRCINFO: &str = "\
+pkgbase = python-foo
+\tpkgdesc = Foo bindings
+\tpkgver = 1.4.2
+\tpkgrel = 3
+\tepoch = 1
+\turl = https://example.org/foo
+\tarch = x86_64
+\tarch = aarch64
+\tcheckdepends = python-pytest
+\tmakedepends = python-build
+\tmakedepends = cmake>=3.20
+\tdepends = glibc
+\tdepends_x86_64 = lib32-glibc
+\toptdepends = python-numpy: array support
+\tprovides = foo
+\tconflicts = foo-git
+\tsource = https://example.org/foo-1.4.2.tar.gz
+\tsource_x86_64 = foo-x86_64.patch
+\tsha256sums = 0123abcd
+\tsha256sums_x86_64 = 4567ef01
+\tb2sums = SKIP
+
+pkgname = python-foo
+\tdepends = python>=3.11
+\tdepends = glibc
+
+pkgname = foo-docs
+\tpkgdesc = Foo documentation
+\tarch = any
+\tdepends = 
+\tprovides = 
+";
+
+/// Test base-level fields, per-arch arrays and checksums are parsed from a split .SRCINFO.
+#[test]
+fn test_parse_split_srcinfo() -> Result<()> {
+    let info = SrcInfo::parse(SPLIT_SRCINFO)?;
+    assert_eq!(info.pkgbase, "python-foo");
+    assert_eq!(info.full_version(), "1:1.4.2-3");
+    assert_eq!(info.pkgnames(), vec!["python-foo", "foo-docs"]);
+    assert_eq!(info.arch, vec!["x86_64", "aarch64"]);
+    assert_eq!(info.makedepends.any, vec!["python-build", "cmake>=3.20"]);
+    assert_eq!(info.checkdepends.any, vec!["python-pytest"]);
+    assert_eq!(info.depends.get("x86_64"), vec!["glibc", "lib32-glibc"]);
+    assert_eq!(info.depends.get("aarch64"), vec!["glibc"]);
+    assert_eq!(info.optdepends.any, vec!["python-numpy: array support"]);
+    assert_eq!(info.source.get("x86_64").len(), 2);
+    assert_eq!(
+        info.checksums["sha256sums"].get("x86_64"),
+        vec!["0123abcd", "4567ef01"]
+    );
+    assert_eq!(info.checksums["b2sums"].any, vec!["SKIP"]);
+    Ok(())
+}
+
+/// Test split packages inherit base values unless they override (or clear) them.
+#[test]
+fn test_split_package_overrides() -> Result<()> {
+    let info = SrcInfo::parse(SPLIT_SRCINFO)?;
+
+    let main = info.package("python-foo").expect("python-foo section");
+    assert_eq!(main.pkgdesc.as_deref(), Some("Foo bindings"));
+    assert_eq!(main.depends.any, vec!["python>=3.11", "glibc"]);
+    assert_eq!(
+        main.depends.get("x86_64"),
+        vec!["python>=3.11", "glibc", "lib32-glibc"]
+    );
+    assert_eq!(main.provides.any, vec!["foo"]);
+    assert_eq!(main.conflicts.any, vec!["foo-git"]);
+
+    let docs = info.package("foo-docs").expect("foo-docs section");
+    assert_eq!(docs.pkgdesc.as_deref(), Some("Foo documentation"));
+    assert_eq!(docs.arch, vec!["any"]);
+    assert!(docs.depends.any.is_empty());
+    assert!(docs.provides.is_empty());
+    assert_eq!(docs.conflicts.any, vec!["foo-git"]);
+
+    // Dependency lookups go through the member, not the base
+    assert_eq!(info.depends_of("foo-docs"), docs.depends.host());
+    assert_eq!(info.depends_of("python-foo"), main.depends.host());
+    assert_ne!(info.depends_of("python-foo"), info.depends.host());
+    assert_eq!(reap::get_deps(&info, "python-foo"), main.depends.host());
+    Ok(())
+}
+
+/// Test malformed input and a missing pkgbase are rejected.
+#[test]
+fn test_parse_errors() {
+    match SrcInfo::parse("pkgbase = foo\nthis is not a key value line\n") {
+        Err(SrcInfoError::Parse { line, .. }) => assert_eq!(line, 2),
+        other => panic!("expected parse error, got {:?}", other),
+    }
+    assert!(matches!(
+        SrcInfo::parse("pkgver = 1.0\n"),
+        Err(SrcInfoError::MissingPkgbase)
+    ));
+}
+
+/// Test the pkgname/version helper reads .SRCINFO content.
+#[test]
+fn test_parse_pkgname_ver_from_srcinfo() {
+    assert_eq!(
+        reap::utils::parse_pkgname_ver(SPLIT_SRCINFO),
+        Some(("python-foo".to_string(), "1:1.4.2-3".to_string()))
+    );
+}
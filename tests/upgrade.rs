@@ -0,0 +1,130 @@
+// Unified upgrade plan tests against fixture pacman databases
+mod common;
+
+use anyhow::Result;
+use common::{add_local, add_sync, fixture_root};
+use reap::pacman_db::PacmanDb;
+use reap::upgrade::{
+    PendingUpgrade, UpgradePlan, UpgradeSource, parse_flatpak_upgrades, repo_upgrades,
+};
+use std::path::Path;
+
+fn desc(name: &str, version: &str) -> String {
+    format!("%NAME%\n{}\n\n%VERSION%\n{}\n\n", name, version)
+}
+
+fn add_installed(root: &Path, name: &str, version: &str) {
+    let dir = format!("{}-{}", name, version);
+    add_local(root, &dir, &desc(name, version), None);
+}
+
+fn add_repo(root: &Path, repo: &str, packages: &[(&str, &str)]) -> Result<()> {
+    let entries: Vec<(String, String)> = packages
+        .iter()
+        .map(|(name, version)| (format!("{}-{}", name, version), desc(name, version)))
+        .collect();
+    let entries: Vec<(&str, &str)> = entries
+        .iter()
+        .map(|(dir, desc)| (dir.as_str(), desc.as_str()))
+        .collect();
+    add_sync(root, repo, &entries)
+}
+
+/// Test repo upgrades come from the first repository carrying a package and only when newer.
+#[test]
+fn test_repo_upgrades() -> Result<()> {
+    let root = fixture_root("upgrade-repo");
+    add_installed(&root, "bash", "5.2.026-2");
+    add_installed(&root, "linux", "6.9.1-1");
+    add_installed(&root, "mesa", "1:24.1.0-1");
+    add_installed(&root, "yay-bin", "12.3.5-1");
+    add_repo(
+        &root,
+        "core",
+        &[("bash", "5.2.032-1"), ("linux", "6.9.1-1")],
+    )?;
+    add_repo(&root, "extra", &[("mesa", "24.2.0-1"), ("bash", "9.9-1")])?;
+    add_repo(&root, "testing", &[("linux", "6.10.0-1")])?;
+    let db = PacmanDb::new(&root, vec!["core".into(), "extra".into()]);
+
+    let upgrades = repo_upgrades(&db)?;
+    assert_eq!(
+        upgrades,
+        vec![PendingUpgrade::new(
+            "bash",
+            UpgradeSource::Repo("core".into()),
+            "5.2.026-2",
+            "5.2.032-1"
+        )]
+    );
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Test the plan follows backend_order, drops backends not in it and holds back packages.
+#[test]
+fn test_plan_order_and_holds() {
+    let candidates = vec![
+        PendingUpgrade::new("org.gimp.GIMP", UpgradeSource::Flatpak, "2.10", "3.0"),
+        PendingUpgrade::new(
+            "mesa",
+            UpgradeSource::Repo("extra".into()),
+            "24.1-1",
+            "24.2-1",
+        ),
+        PendingUpgrade::new("bash", UpgradeSource::Repo("core".into()), "5.2-1", "5.3-1"),
+        PendingUpgrade::new("paru", UpgradeSource::Aur, "2.0-1", "2.1-1"),
+        PendingUpgrade::new("yay", UpgradeSource::Aur, "12.3-1", "12.4-1"),
+        PendingUpgrade::new(
+            "linux",
+            UpgradeSource::Repo("core".into()),
+            "6.9-1",
+            "6.10-1",
+        ),
+        PendingUpgrade::new(
+            "ghostctl",
+            UpgradeSource::Tap("ghost".into()),
+            "1.0-1",
+            "1.1-1",
+        ),
+    ];
+    let order: Vec<String> = vec!["tap".into(), "aur".into(), "pacman".into()];
+    let plan = UpgradePlan::new(candidates, &order, |upgrade| match upgrade.name.as_str() {
+        "linux" => Some("pinned".to_string()),
+        "paru" => Some("ignored".to_string()),
+        _ => None,
+    });
+
+    let names: Vec<&str> = plan.upgrades.iter().map(|u| u.name.as_str()).collect();
+    assert_eq!(names, vec!["ghostctl", "yay", "bash", "mesa"]);
+    let held: Vec<(&str, &str)> = plan
+        .held
+        .iter()
+        .map(|(u, reason)| (u.name.as_str(), reason.as_str()))
+        .collect();
+    assert_eq!(held, vec![("paru", "ignored"), ("linux", "pinned")]);
+    assert_eq!(plan.for_backend("pacman").count(), 2);
+    assert_eq!(plan.for_backend("flatpak").count(), 0);
+    assert!(!plan.is_empty());
+}
+
+/// Test Flatpak updates are paired with the installed versions.
+#[test]
+fn test_parse_flatpak_upgrades() {
+    let installed =
+        "org.gimp.GIMP\t2.10.38\ncom.valvesoftware.Steam\t1.0.0.79\norg.example.Nightly\t\n";
+    let updates = "org.gimp.GIMP\t3.0.0\norg.example.Nightly\t\n";
+    assert_eq!(
+        parse_flatpak_upgrades(installed, updates),
+        vec![
+            PendingUpgrade::new("org.gimp.GIMP", UpgradeSource::Flatpak, "2.10.38", "3.0.0"),
+            PendingUpgrade::new(
+                "org.example.Nightly",
+                UpgradeSource::Flatpak,
+                "installed",
+                "latest"
+            ),
+        ]
+    );
+    assert!(parse_flatpak_upgrades(installed, "").is_empty());
+}
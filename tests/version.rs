@@ -0,0 +1,104 @@
+// pacman version comparison tests
+use reap::version::{DepOp, Dependency, Version, vercmp};
+
+/// Test vercmp against results produced by pacman's own `vercmp` tool.
+#[test]
+fn test_vercmp_table() {
+    let cases: &[(&str, &str, i32)] = &[
+        // equality
+        ("1.5.0", "1.5.0", 0),
+        ("1.5.1", "1.5.0", 1),
+        ("1.5.1", "1.5", 1),
+        ("1.5.0-1", "1.5.0-1", 0),
+        ("1.5.0-1", "1.5.0-2", -1),
+        ("1.5.0-1", "1.5.1-1", -1),
+        ("1.5.0-2", "1.5.1-1", -1),
+        // multi-digit segments compare numerically
+        ("1.10", "1.9", 1),
+        ("1.010", "1.9", 1),
+        ("1.0001", "1.1", 0),
+        // alpha and pre-release segments
+        ("1.5b-1", "1.5-1", -1),
+        ("1.5b", "1.5", -1),
+        ("1.5b-1", "1.5", -1),
+        ("1.5b", "1.5.1", -1),
+        ("1.0a", "1.0alpha", -1),
+        ("1.0alpha", "1.0b", -1),
+        ("1.0b", "1.0beta", -1),
+        ("1.0beta", "1.0pre", -1),
+        ("1.0pre", "1.0rc", -1),
+        ("1.0rc", "1.0", -1),
+        ("1.0", "1.0.a", -1),
+        ("1.0.a", "1.0.1", -1),
+        ("1.0.1", "1.0.2", -1),
+        ("1.0.2", "1.0.2.a", -1),
+        // separators
+        ("1.0", "1_0", 0),
+        ("1_0_0", "1.0.0", 0),
+        ("1..0", "1.0", 1),
+        ("1.0", "1..0", -1),
+        ("1.0.", "1.0", 1),
+        // pkgrel only counts when both sides have one
+        ("1.5.0", "1.5.0-1", 0),
+        ("1.5.0-1", "1.5.0", 0),
+        ("1.1-1", "1.1", 0),
+        ("1.1-1.1", "1.1-1", 1),
+        ("1.1-1.1", "1.1-1.2", -1),
+        // epochs
+        ("0:1.0", "0:1.0", 0),
+        ("0:1.0", "0:1.1", -1),
+        ("1:1.0", "0:1.0", 1),
+        ("1:1.0", "0:1.1", 1),
+        ("1:1.0", "2:1.1", -1),
+        ("1:1.0", "1.1", 1),
+        ("1.1", "0:1.1", 0),
+        ("1:1.0-1", "2.0-1", 1),
+        // git-style pkgvers
+        ("r123.abcdef0-1", "r99.0123456-1", 1),
+        ("6.2.r17.g1a2b3c4-1", "6.2.r9.g0000000-1", 1),
+    ];
+    for (a, b, expected) in cases {
+        let want = expected.cmp(&0);
+        assert_eq!(vercmp(a, b), want, "vercmp({}, {})", a, b);
+        assert_eq!(vercmp(b, a), want.reverse(), "vercmp({}, {})", b, a);
+    }
+}
+
+/// Test that the Version type sorts like vercmp and round-trips through Display.
+#[test]
+fn test_version_ord_and_display() {
+    let mut versions: Vec<Version> = ["1.10-1", "2:0.1-1", "1.9-3", "1.9-10", "1.9rc1-1"]
+        .iter()
+        .map(|v| Version::parse(v))
+        .collect();
+    versions.sort();
+    let sorted: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+    assert_eq!(sorted, ["1.9rc1-1", "1.9-3", "1.9-10", "1.10-1", "2:0.1-1"]);
+
+    let v = Version::parse("3:2.4.1-2");
+    assert_eq!(v.epoch, "3");
+    assert_eq!(v.pkgver, "2.4.1");
+    assert_eq!(v.pkgrel.as_deref(), Some("2"));
+}
+
+/// Test dependency strings are split into name and constraint and checked with vercmp.
+#[test]
+fn test_dependency_constraints() {
+    let dep = Dependency::parse("python>=3.10");
+    assert_eq!(dep.name, "python");
+    assert_eq!(dep.constraint.as_ref().map(|(op, _)| *op), Some(DepOp::Ge));
+    assert!(dep.satisfied_by(&Version::parse("3.12.1-1")));
+    assert!(!dep.satisfied_by(&Version::parse("3.9.18-1")));
+
+    let dep = Dependency::parse("glibc<2.40");
+    assert!(dep.satisfied_by(&Version::parse("2.39-1")));
+    assert!(!dep.satisfied_by(&Version::parse("2.40-1")));
+
+    let dep = Dependency::parse("libfoo=1.2");
+    assert!(dep.satisfied_by(&Version::parse("1.2-5")));
+    assert!(!dep.satisfied_by(&Version::parse("1.2.1-1")));
+
+    let dep = Dependency::parse("gtk3: GUI frontend");
+    assert_eq!(dep.name, "gtk3");
+    assert!(dep.constraint.is_none());
+}